@@ -0,0 +1,27 @@
+{% macro node(node) -%}
+  {% if node.trait == "struct" -%}
+    struct {{node.name}} {
+       {% for member in node.members -%}
+         {{member.type.name}} {{member.name}};
+       {% endfor -%}
+    };
+  {% elif node.trait == "enum" -%}
+    enum {{node.name}} {
+       {% for member in node.members -%}
+         {{member.name}} = {{member.value}},
+       {% endfor -%}
+    };
+  {% elif node.trait == "union" -%}
+    struct {{node.name}} {
+       {% for member in node.members -%}
+         {% if member.type %}{{member.type.name}}{% else %}void{% endif %} {{member.name}};
+       {% endfor -%}
+    };
+  {% elif node.trait == "module" -%}
+    namespace {{node.name}} {
+    {% for child in node.nodes -%}
+      {{ self::node(node=child) }}
+    {% endfor -%}
+    } // {{node.name}}
+  {% endif %}
+{%- endmacro node %}
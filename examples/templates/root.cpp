@@ -1,19 +1,13 @@
-namespace {{namespace}} {
+{% import "macros.cpp" as m -%}
 
-{% for node in ast.nodes -%}
-  {% if node.trait == "struct" -%}
-    struct {{node.name}} {
-       {% for member in node.members -%}
-         {{member.type.name}} {{member.name}};
-       {% endfor -%}
-    };
-  {% elif node.trait == "enum" -%}
-    enum {{node.name}} {
-       {% for member in node.members -%}
-         {{member.name}},
-       {% endfor -%}
-    };
-  {% endif %}
+{% for ns in order -%}
+{% set defs = modules[ns] -%}
+namespace {{ns}} {
+
+{% for node in defs.nodes -%}
+  {{ m::node(node=node) }}
 {% endfor -%}
 
-} // {{namespace}}
+} // {{ns}}
+
+{% endfor -%}
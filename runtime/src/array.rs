@@ -0,0 +1,115 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::{Serialize, Serializer};
+
+use crate::binary::{Decode, Encode, Reader};
+use crate::error::{Error, ErrorKind};
+
+/// An array of exactly `N` items.
+///
+/// Rust arrays only implement `Default` and the serde traits up to 32 items, so longer
+/// arrays are generated as this instead. Its length can't change once it's made, and
+/// decoding it fails unless there are `N` items.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Array<T, const N: usize>(Vec<T>);
+
+impl<T, const N: usize> Array<T, N> {
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for Array<T, N> {
+    fn from(items: [T; N]) -> Self {
+        Array(Vec::from(items))
+    }
+}
+
+impl<T, const N: usize> TryFrom<Vec<T>> for Array<T, N> {
+    type Error = Error;
+
+    fn try_from(items: Vec<T>) -> Result<Self, Error> {
+        if items.len() == N {
+            Ok(Array(items))
+        } else {
+            let message = format!("array of {} items has {}", N, items.len());
+            Err(Error::new(ErrorKind::Malformed, message))
+        }
+    }
+}
+
+impl<T: Default, const N: usize> Default for Array<T, N> {
+    fn default() -> Self {
+        Array((0..N).map(|_| T::default()).collect())
+    }
+}
+
+impl<T, const N: usize> Deref for Array<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<T, const N: usize> DerefMut for Array<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.0
+    }
+}
+
+impl<T: Encode, const N: usize> Encode for Array<T, N> {
+    fn encode(&self, w: &mut Vec<u8>) {
+        self.0.encode(w)
+    }
+}
+
+impl<T: Decode, const N: usize> Decode for Array<T, N> {
+    fn decode(r: &mut Reader) -> Result<Self, Error> {
+        Self::try_from(Vec::<T>::decode(r)?)
+    }
+}
+
+impl<T: Serialize, const N: usize> Serialize for Array<T, N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, const N: usize> Deserialize<'de> for Array<T, N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(Items(PhantomData))
+    }
+}
+
+/// Visits the items of an array, failing unless there are `N` of them.
+struct Items<T, const N: usize>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>, const N: usize> Visitor<'de> for Items<T, N> {
+    type Value = Array<T, N>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an array of {} items", N)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut items = Vec::with_capacity(N);
+
+        while let Some(item) = seq.next_element()? {
+            if items.len() == N {
+                return Err(de::Error::invalid_length(N + 1, &self));
+            }
+            items.push(item);
+        }
+
+        if items.len() != N {
+            return Err(de::Error::invalid_length(items.len(), &self));
+        }
+
+        Ok(Array(items))
+    }
+}
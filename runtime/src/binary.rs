@@ -0,0 +1,526 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::{TryFrom, TryInto};
+use std::hash::{BuildHasher, Hash};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::codec::{Codec, Request, Response};
+use crate::error::{Error, ErrorKind};
+
+/// Version of the encoding, the first byte of every message.
+pub const VERSION: u8 = 1;
+
+/// Values encoded as the binary encoding of rrpc, see `docs/binary.md`.
+pub trait Encode {
+    fn encode(&self, w: &mut Vec<u8>);
+}
+
+/// Values decoded from the binary encoding of rrpc.
+pub trait Decode: Sized {
+    fn decode(r: &mut Reader) -> Result<Self, Error>;
+}
+
+/// Writes an unsigned LEB128 varint.
+pub fn write_varint(w: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        w.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    w.push(n as u8);
+}
+
+/// Writes a field of a struct: its tag, the length of its value, and the value.
+pub fn write_field<T: Encode + ?Sized>(w: &mut Vec<u8>, tag: u64, value: &T) {
+    let mut buf = Vec::new();
+    value.encode(&mut buf);
+
+    write_varint(w, tag);
+    write_varint(w, buf.len() as u64);
+    w.extend_from_slice(&buf);
+}
+
+fn malformed<S: Into<String>>(message: S) -> Error {
+    Error::new(ErrorKind::Malformed, message)
+}
+
+/// Reads encoded values from a buffer.
+#[derive(Debug, Clone)]
+pub struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    /// Bytes not read yet.
+    pub fn rest(&self) -> &'a [u8] {
+        self.buf
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn bytes(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if n > self.buf.len() {
+            return Err(malformed("unexpected end of message"));
+        }
+
+        let (bytes, rest) = self.buf.split_at(n);
+        self.buf = rest;
+        Ok(bytes)
+    }
+
+    pub fn varint(&mut self) -> Result<u64, Error> {
+        let mut n = 0u64;
+
+        for i in 0..10 {
+            let b = self.byte()?;
+            if i == 9 && b > 1 {
+                break;
+            }
+
+            n |= u64::from(b & 0x7f) << (7 * i);
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+
+        Err(malformed("varint is too long"))
+    }
+
+    /// Reads a count of items, each of which is at least a byte long.
+    pub fn count(&mut self) -> Result<usize, Error> {
+        let n = self.varint()?;
+
+        if n > self.buf.len() as u64 {
+            return Err(malformed(format!("count of {} is past the end of message", n)));
+        }
+
+        Ok(n as usize)
+    }
+
+    /// Reads a field of a struct: its tag, and a reader of its value.
+    pub fn field(&mut self) -> Result<(u64, Reader<'a>), Error> {
+        let tag = self.varint()?;
+        let len = self.count()?;
+
+        Ok((tag, Reader::new(self.bytes(len)?)))
+    }
+
+    pub fn decode<T: Decode>(&mut self) -> Result<T, Error> {
+        T::decode(self)
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode(&self, w: &mut Vec<u8>) {
+        (**self).encode(w)
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for Box<T> {
+    fn encode(&self, w: &mut Vec<u8>) {
+        (**self).encode(w)
+    }
+}
+
+impl<T: Decode> Decode for Box<T> {
+    fn decode(r: &mut Reader) -> Result<Self, Error> {
+        Ok(Box::new(T::decode(r)?))
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, w: &mut Vec<u8>) {
+        w.push(*self as u8)
+    }
+}
+
+impl Decode for bool {
+    fn decode(r: &mut Reader) -> Result<Self, Error> {
+        match r.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(malformed(format!("{} isn't a bool", b))),
+        }
+    }
+}
+
+impl Encode for u8 {
+    fn encode(&self, w: &mut Vec<u8>) {
+        w.push(*self)
+    }
+}
+
+impl Decode for u8 {
+    fn decode(r: &mut Reader) -> Result<Self, Error> {
+        r.byte()
+    }
+}
+
+impl Encode for i8 {
+    fn encode(&self, w: &mut Vec<u8>) {
+        w.push(*self as u8)
+    }
+}
+
+impl Decode for i8 {
+    fn decode(r: &mut Reader) -> Result<Self, Error> {
+        Ok(r.byte()? as i8)
+    }
+}
+
+/// Unsigned integers, encoded as varints.
+macro_rules! unsigned {
+    ($($t:ty),*) => {$(
+        impl Encode for $t {
+            fn encode(&self, w: &mut Vec<u8>) {
+                write_varint(w, u64::from(*self))
+            }
+        }
+
+        impl Decode for $t {
+            fn decode(r: &mut Reader) -> Result<Self, Error> {
+                let n = r.varint()?;
+                <$t>::try_from(n)
+                    .map_err(|_| malformed(format!("{} is out of the range of {}", n, stringify!($t))))
+            }
+        }
+    )*};
+}
+
+unsigned!(u16, u32, u64);
+
+/// Signed integers, encoded as zigzag varints.
+macro_rules! signed {
+    ($($t:ty),*) => {$(
+        impl Encode for $t {
+            fn encode(&self, w: &mut Vec<u8>) {
+                let n = i64::from(*self);
+                write_varint(w, ((n << 1) ^ (n >> 63)) as u64)
+            }
+        }
+
+        impl Decode for $t {
+            fn decode(r: &mut Reader) -> Result<Self, Error> {
+                let n = r.varint()?;
+                let n = (n >> 1) as i64 ^ -((n & 1) as i64);
+                <$t>::try_from(n)
+                    .map_err(|_| malformed(format!("{} is out of the range of {}", n, stringify!($t))))
+            }
+        }
+    )*};
+}
+
+signed!(i16, i32, i64);
+
+/// Floats, encoded little-endian.
+macro_rules! float {
+    ($($t:ty),*) => {$(
+        impl Encode for $t {
+            fn encode(&self, w: &mut Vec<u8>) {
+                w.extend_from_slice(&self.to_le_bytes())
+            }
+        }
+
+        impl Decode for $t {
+            fn decode(r: &mut Reader) -> Result<Self, Error> {
+                const SIZE: usize = std::mem::size_of::<$t>();
+
+                let mut bytes = [0; SIZE];
+                bytes.copy_from_slice(r.bytes(SIZE)?);
+                Ok(<$t>::from_le_bytes(bytes))
+            }
+        }
+    )*};
+}
+
+float!(f32, f64);
+
+impl Encode for str {
+    fn encode(&self, w: &mut Vec<u8>) {
+        write_varint(w, self.len() as u64);
+        w.extend_from_slice(self.as_bytes());
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, w: &mut Vec<u8>) {
+        self.as_str().encode(w)
+    }
+}
+
+impl Decode for String {
+    fn decode(r: &mut Reader) -> Result<Self, Error> {
+        let len = r.count()?;
+        let bytes = r.bytes(len)?;
+
+        String::from_utf8(bytes.to_vec()).map_err(|e| malformed(e.to_string()))
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, w: &mut Vec<u8>) {
+        match *self {
+            Some(ref value) => {
+                w.push(1);
+                value.encode(w);
+            }
+            None => w.push(0),
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode(r: &mut Reader) -> Result<Self, Error> {
+        match r.byte()? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode(r)?)),
+            b => Err(malformed(format!("{} isn't a presence of optional", b))),
+        }
+    }
+}
+
+impl<T: Encode> Encode for [T] {
+    fn encode(&self, w: &mut Vec<u8>) {
+        write_varint(w, self.len() as u64);
+        for item in self {
+            item.encode(w);
+        }
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, w: &mut Vec<u8>) {
+        self.as_slice().encode(w)
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(r: &mut Reader) -> Result<Self, Error> {
+        let n = r.count()?;
+        (0..n).map(|_| T::decode(r)).collect()
+    }
+}
+
+impl<T: Encode, const N: usize> Encode for [T; N] {
+    fn encode(&self, w: &mut Vec<u8>) {
+        self.as_slice().encode(w)
+    }
+}
+
+impl<T: Decode, const N: usize> Decode for [T; N] {
+    fn decode(r: &mut Reader) -> Result<Self, Error> {
+        let items = Vec::<T>::decode(r)?;
+        let len = items.len();
+
+        items
+            .try_into()
+            .map_err(|_| malformed(format!("array of {} items has {}", N, len)))
+    }
+}
+
+/// Encodes each item on its own, in the order of their encodings.
+fn sorted<'a, T: Encode + 'a, I: Iterator<Item = &'a T>>(items: I) -> Vec<Vec<u8>> {
+    let mut items = items
+        .map(|item| {
+            let mut buf = Vec::new();
+            item.encode(&mut buf);
+            buf
+        })
+        .collect::<Vec<_>>();
+
+    items.sort();
+    items
+}
+
+impl<T: Encode, S> Encode for HashSet<T, S> {
+    fn encode(&self, w: &mut Vec<u8>) {
+        write_varint(w, self.len() as u64);
+        for item in sorted(self.iter()) {
+            w.extend_from_slice(&item);
+        }
+    }
+}
+
+impl<T: Decode + Eq + Hash, S: BuildHasher + Default> Decode for HashSet<T, S> {
+    fn decode(r: &mut Reader) -> Result<Self, Error> {
+        let n = r.count()?;
+        (0..n).map(|_| T::decode(r)).collect()
+    }
+}
+
+impl<K: Encode, V: Encode, S> Encode for HashMap<K, V, S> {
+    fn encode(&self, w: &mut Vec<u8>) {
+        let mut entries = self
+            .iter()
+            .map(|(k, v)| {
+                let mut key = Vec::new();
+                k.encode(&mut key);
+                (key, v)
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        write_varint(w, entries.len() as u64);
+        for (key, value) in entries {
+            w.extend_from_slice(&key);
+            value.encode(w);
+        }
+    }
+}
+
+impl<K: Decode + Eq + Hash, V: Decode, S: BuildHasher + Default> Decode for HashMap<K, V, S> {
+    fn decode(r: &mut Reader) -> Result<Self, Error> {
+        let n = r.count()?;
+        (0..n).map(|_| Ok((K::decode(r)?, V::decode(r)?))).collect()
+    }
+}
+
+/// Tuples of arguments and returned values, which are their items one after another.
+macro_rules! tuple {
+    ($($name:ident)*) => {
+        impl<$($name: Encode),*> Encode for ($($name,)*) {
+            #[allow(non_snake_case, unused_variables)]
+            fn encode(&self, w: &mut Vec<u8>) {
+                let ($(ref $name,)*) = *self;
+                $($name.encode(w);)*
+            }
+        }
+
+        impl<$($name: Decode),*> Decode for ($($name,)*) {
+            #[allow(unused_variables)]
+            fn decode(r: &mut Reader) -> Result<Self, Error> {
+                Ok(($($name::decode(r)?,)*))
+            }
+        }
+    };
+}
+
+tuple!();
+tuple!(A);
+tuple!(A B);
+tuple!(A B C);
+tuple!(A B C D);
+tuple!(A B C D E);
+tuple!(A B C D E F);
+tuple!(A B C D E F G);
+tuple!(A B C D E F G H);
+tuple!(A B C D E F G H I);
+tuple!(A B C D E F G H I J);
+tuple!(A B C D E F G H I J K);
+tuple!(A B C D E F G H I J K L);
+
+/// Encodes messages in the binary encoding.
+///
+/// A request is the version, the names of the interface and of the function, and the
+/// arguments. A response is the version and a status: `0` followed by the returned values,
+/// or `1` followed by the kind of the error and its message.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BinaryCodec;
+
+/// Kinds of errors, by their codes in responses.
+const KINDS: &[ErrorKind] = &[
+    ErrorKind::Io,
+    ErrorKind::Malformed,
+    ErrorKind::InvalidRequest,
+    ErrorKind::NotFound,
+    ErrorKind::InvalidArgs,
+    ErrorKind::Service,
+];
+
+impl BinaryCodec {
+    fn version(r: &mut Reader) -> Result<(), Error> {
+        match r.byte()? {
+            VERSION => Ok(()),
+            v => Err(malformed(format!("version {} of the encoding isn't supported", v))),
+        }
+    }
+
+    fn request(frame: &[u8]) -> Result<Request<Vec<u8>>, Error> {
+        let mut r = Reader::new(frame);
+        Self::version(&mut r)?;
+
+        Ok(Request {
+            interface: r.decode()?,
+            method: r.decode()?,
+            args: r.rest().to_vec(),
+        })
+    }
+}
+
+impl Codec for BinaryCodec {
+    type Value = Vec<u8>;
+
+    fn encode<T: Serialize + Encode>(&self, value: &T) -> Result<Vec<u8>, Error> {
+        let mut w = Vec::new();
+        value.encode(&mut w);
+        Ok(w)
+    }
+
+    fn decode<T: DeserializeOwned + Decode>(&self, value: Vec<u8>) -> Result<T, Error> {
+        let mut r = Reader::new(&value);
+        let value = T::decode(&mut r)?;
+
+        if !r.is_empty() {
+            return Err(malformed(format!("{} bytes left after the value", r.rest().len())));
+        }
+
+        Ok(value)
+    }
+
+    fn encode_request(&self, request: &Request<Vec<u8>>) -> Result<Vec<u8>, Error> {
+        let mut w = vec![VERSION];
+        request.interface.encode(&mut w);
+        request.method.encode(&mut w);
+        w.extend_from_slice(&request.args);
+        Ok(w)
+    }
+
+    fn decode_response(&self, frame: &[u8]) -> Result<Response<Vec<u8>>, Error> {
+        let mut r = Reader::new(frame);
+        Self::version(&mut r)?;
+
+        match r.byte()? {
+            0 => Ok(Ok(r.rest().to_vec())),
+            1 => {
+                let code = r.byte()?;
+                let kind = KINDS.get(code as usize).cloned().unwrap_or(ErrorKind::Service);
+                let message: String = r.decode()?;
+
+                Ok(Err(Error::new(kind, message)))
+            }
+            b => Err(malformed(format!("{} isn't a status", b))),
+        }
+    }
+
+    fn answer(
+        &self,
+        frame: &[u8],
+        call: &mut dyn FnMut(Request<Vec<u8>>) -> Response<Vec<u8>>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let mut w = vec![VERSION];
+
+        match Self::request(frame).and_then(call) {
+            Ok(ret) => {
+                w.push(0);
+                w.extend_from_slice(&ret);
+            }
+            Err(e) => {
+                w.push(1);
+                w.push(KINDS.iter().position(|k| *k == e.kind()).unwrap_or(0) as u8);
+                e.message().encode(&mut w);
+            }
+        }
+
+        Ok(Some(w))
+    }
+}
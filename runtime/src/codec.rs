@@ -0,0 +1,96 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::binary::{Decode, Encode};
+use crate::error::Error;
+
+/// Call of the function `method` of the interface `interface`.
+///
+/// Arguments are encoded as a tuple, `()` without any.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Request<V> {
+    pub interface: String,
+    pub method: String,
+    pub args: V,
+}
+
+/// Result of a call: the returned values, a tuple if there are several, or the error.
+pub type Response<V> = Result<V, Error>;
+
+/// Encodes values and the messages carrying them.
+pub trait Codec {
+    /// Values as they are in messages.
+    type Value;
+
+    fn encode<T: Serialize + Encode>(&self, value: &T) -> Result<Self::Value, Error>;
+
+    fn decode<T: DeserializeOwned + Decode>(&self, value: Self::Value) -> Result<T, Error>;
+
+    /// Encodes the tuple of the arguments of a function, named `names`.
+    fn encode_args<A: Serialize + Encode>(&self, names: &[&str], args: &A) -> Result<Self::Value, Error> {
+        let _ = names;
+        self.encode(args)
+    }
+
+    /// Decodes the tuple of the arguments of a function, named `names`.
+    fn decode_args<A: DeserializeOwned + Decode>(
+        &self,
+        names: &[&str],
+        args: Self::Value,
+    ) -> Result<A, Error> {
+        let _ = names;
+        self.decode(args)
+    }
+
+    /// Frame of a call.
+    fn encode_request(&self, request: &Request<Self::Value>) -> Result<Vec<u8>, Error>;
+
+    /// Result of a call in the frame answering it.
+    fn decode_response(&self, frame: &[u8]) -> Result<Response<Self::Value>, Error>;
+
+    /// Answers a frame of requests with what `call` returns for them, or `None` if none
+    /// of them expects an answer.
+    fn answer(
+        &self,
+        frame: &[u8],
+        call: &mut dyn FnMut(Request<Self::Value>) -> Response<Self::Value>,
+    ) -> Result<Option<Vec<u8>>, Error>;
+}
+
+/// Encodes messages as JSON objects, e.g. `{"interface":"store","method":"get","args":[7]}`
+/// and `{"Ok":null}`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JsonCodec;
+
+impl Codec for JsonCodec {
+    type Value = Value;
+
+    fn encode<T: Serialize + Encode>(&self, value: &T) -> Result<Value, Error> {
+        Ok(serde_json::to_value(value)?)
+    }
+
+    fn decode<T: DeserializeOwned + Decode>(&self, value: Value) -> Result<T, Error> {
+        Ok(serde_json::from_value(value)?)
+    }
+
+    fn encode_request(&self, request: &Request<Value>) -> Result<Vec<u8>, Error> {
+        Ok(serde_json::to_vec(request)?)
+    }
+
+    fn decode_response(&self, frame: &[u8]) -> Result<Response<Value>, Error> {
+        Ok(serde_json::from_slice(frame)?)
+    }
+
+    fn answer(
+        &self,
+        frame: &[u8],
+        call: &mut dyn FnMut(Request<Value>) -> Response<Value>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let response = serde_json::from_slice(frame)
+            .map_err(Error::from)
+            .and_then(&mut *call);
+
+        Ok(Some(serde_json::to_vec(&response)?))
+    }
+}
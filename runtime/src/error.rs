@@ -0,0 +1,81 @@
+use std::{error, fmt, io};
+
+use serde::{Deserialize, Serialize};
+
+/// What went wrong with a call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The transport failed.
+    Io,
+    /// A message couldn't be encoded or decoded.
+    Malformed,
+    /// A message isn't a valid request.
+    InvalidRequest,
+    /// There's no such interface or function.
+    NotFound,
+    /// The arguments don't fit the function.
+    InvalidArgs,
+    /// The implementation of the function failed.
+    Service,
+}
+
+/// Error of a call, on either side of the transport.
+///
+/// Errors of the server are sent back to the client, so they only carry a message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Error {
+    kind: ErrorKind,
+    message: String,
+}
+
+impl Error {
+    pub fn new<S: Into<String>>(kind: ErrorKind, message: S) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// Error of the implementation of a function.
+    pub fn service<E: fmt::Display>(e: E) -> Self {
+        Self::new(ErrorKind::Service, e.to_string())
+    }
+
+    /// Error decoding the arguments of a function.
+    pub fn invalid_args(e: Error) -> Self {
+        Self::new(ErrorKind::InvalidArgs, e.message)
+    }
+
+    pub fn not_found(interface: &str, method: &str) -> Self {
+        Self::new(ErrorKind::NotFound, format!("no function {}.{}", interface, method))
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}: {}", self.kind, self.message)
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::new(ErrorKind::Io, e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Self::new(ErrorKind::Malformed, e.to_string())
+    }
+}
@@ -0,0 +1,45 @@
+use std::io::{self, Read, Write};
+
+use crate::error::{Error, ErrorKind};
+
+/// Frames larger than this are rejected rather than allocated.
+const MAX_FRAME: usize = 64 << 20;
+
+/// Writes a frame: its length as a big-endian `u32`, then its bytes.
+pub fn write_frame<W: Write>(w: &mut W, frame: &[u8]) -> Result<(), Error> {
+    if frame.len() > MAX_FRAME {
+        return Err(Error::new(
+            ErrorKind::Malformed,
+            format!("frame of {} bytes is too large", frame.len()),
+        ));
+    }
+
+    let mut buf = Vec::with_capacity(4 + frame.len());
+    buf.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+    buf.extend_from_slice(frame);
+    w.write_all(&buf)?;
+    w.flush()?;
+    Ok(())
+}
+
+/// Reads a frame, or `None` if the stream ended before it.
+pub fn read_frame<R: Read>(r: &mut R) -> Result<Option<Vec<u8>>, Error> {
+    let mut len = [0; 4];
+    match r.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME {
+        return Err(Error::new(
+            ErrorKind::Malformed,
+            format!("frame of {} bytes is too large", len),
+        ));
+    }
+
+    let mut frame = vec![0; len];
+    r.read_exact(&mut frame)?;
+    Ok(Some(frame))
+}
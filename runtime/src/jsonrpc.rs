@@ -0,0 +1,247 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::binary::{Decode, Encode};
+use crate::codec::{Codec, Request, Response};
+use crate::error::{Error, ErrorKind};
+
+const VERSION: &str = "2.0";
+
+/// Encodes messages as JSON-RPC 2.0 requests and responses.
+///
+/// The method of a call is the name of the interface and of the function joined by a dot,
+/// e.g. `store.get`. Arguments are sent as named params, and accepted by name or by
+/// position. What the function returns is the `result`, an array for several values.
+///
+/// Frames may carry batches and notifications, which are answered as the specification
+/// says: a batch with an array of the responses, and notifications not at all.
+#[derive(Debug, Default)]
+pub struct JsonRpcCodec {
+    next_id: AtomicU64,
+}
+
+impl Codec for JsonRpcCodec {
+    type Value = Value;
+
+    fn encode<T: Serialize + Encode>(&self, value: &T) -> Result<Value, Error> {
+        Ok(serde_json::to_value(value)?)
+    }
+
+    fn decode<T: DeserializeOwned + Decode>(&self, value: Value) -> Result<T, Error> {
+        Ok(serde_json::from_value(value)?)
+    }
+
+    fn encode_args<A: Serialize + Encode>(&self, names: &[&str], args: &A) -> Result<Value, Error> {
+        match self.encode(args)? {
+            Value::Array(values) => Ok(Value::Object(
+                names
+                    .iter()
+                    .map(|name| name.to_string())
+                    .zip(values)
+                    .collect(),
+            )),
+            // Functions without arguments have no params.
+            value => Ok(value),
+        }
+    }
+
+    fn decode_args<A: DeserializeOwned + Decode>(&self, names: &[&str], args: Value) -> Result<A, Error> {
+        let values = match args {
+            Value::Object(mut params) => {
+                let values = names
+                    .iter()
+                    .map(|name| params.remove(*name).unwrap_or(Value::Null))
+                    .collect::<Vec<_>>();
+
+                if let Some(name) = params.keys().next() {
+                    return Err(Error::new(
+                        ErrorKind::InvalidArgs,
+                        format!("unknown param `{}`", name),
+                    ));
+                }
+
+                values
+            }
+            Value::Array(values) => values,
+            value => return self.decode(value),
+        };
+
+        // The arguments of a function without any are `()`, which is `null`.
+        if names.is_empty() && values.is_empty() {
+            return self.decode(Value::Null);
+        }
+
+        self.decode(Value::Array(values))
+    }
+
+    fn encode_request(&self, request: &Request<Value>) -> Result<Vec<u8>, Error> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+        let mut message = Map::new();
+        message.insert("jsonrpc".into(), VERSION.into());
+        message.insert(
+            "method".into(),
+            format!("{}.{}", request.interface, request.method).into(),
+        );
+        if !request.args.is_null() {
+            message.insert("params".into(), request.args.clone());
+        }
+        message.insert("id".into(), id.into());
+
+        Ok(serde_json::to_vec(&message)?)
+    }
+
+    fn decode_response(&self, frame: &[u8]) -> Result<Response<Value>, Error> {
+        let mut message = match serde_json::from_slice(frame)? {
+            Value::Object(message) => message,
+            _ => return Err(Error::new(ErrorKind::Malformed, "response isn't an object")),
+        };
+
+        if let Some(result) = message.remove("result") {
+            return Ok(Ok(result));
+        }
+
+        match message.remove("error") {
+            Some(Value::Object(error)) => {
+                let code = error.get("code").and_then(Value::as_i64).unwrap_or(INTERNAL);
+                let message = error.get("message").and_then(Value::as_str).unwrap_or("");
+
+                Ok(Err(Error::new(kind(code), message)))
+            }
+            _ => Err(Error::new(ErrorKind::Malformed, "response has no result")),
+        }
+    }
+
+    fn answer(
+        &self,
+        frame: &[u8],
+        call: &mut dyn FnMut(Request<Value>) -> Response<Value>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let answer = match serde_json::from_slice(frame) {
+            Ok(Value::Array(ref batch)) if batch.is_empty() => Some(response(
+                Value::Null,
+                Err(Error::new(ErrorKind::InvalidRequest, "empty batch")),
+            )),
+            Ok(Value::Array(batch)) => {
+                let responses = batch
+                    .into_iter()
+                    .filter_map(|message| self.request(message, call))
+                    .collect::<Vec<_>>();
+
+                if responses.is_empty() {
+                    None
+                } else {
+                    Some(Value::Array(responses))
+                }
+            }
+            Ok(message) => self.request(message, call),
+            Err(e) => Some(response(Value::Null, Err(e.into()))),
+        };
+
+        match answer {
+            Some(answer) => Ok(Some(serde_json::to_vec(&answer)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl JsonRpcCodec {
+    /// Answers a request, or `None` if it's a notification.
+    fn request(
+        &self,
+        message: Value,
+        call: &mut dyn FnMut(Request<Value>) -> Response<Value>,
+    ) -> Option<Value> {
+        let mut message = match message {
+            Value::Object(message) => message,
+            _ => {
+                let e = Error::new(ErrorKind::InvalidRequest, "request isn't an object");
+                return Some(response(Value::Null, Err(e)));
+            }
+        };
+
+        let id = message.remove("id");
+        let result = parse(message).and_then(&mut *call);
+
+        // Notifications have no id, and are never answered, even if they fail.
+        id.map(|id| response(id, result))
+    }
+}
+
+/// Parses the request of a message.
+fn parse(mut message: Map<String, Value>) -> Result<Request<Value>, Error> {
+    if message.get("jsonrpc").and_then(Value::as_str) != Some(VERSION) {
+        return Err(Error::new(ErrorKind::InvalidRequest, "`jsonrpc` isn't \"2.0\""));
+    }
+
+    let method = match message.remove("method") {
+        Some(Value::String(method)) => method,
+        _ => return Err(Error::new(ErrorKind::InvalidRequest, "`method` isn't a string")),
+    };
+
+    let args = match message.remove("params") {
+        None => Value::Null,
+        Some(params @ Value::Array(_)) | Some(params @ Value::Object(_)) => params,
+        Some(_) => {
+            return Err(Error::new(ErrorKind::InvalidRequest, "`params` isn't structured"));
+        }
+    };
+
+    let (interface, method) = match method.find('.') {
+        Some(dot) => (method[..dot].to_string(), method[dot + 1..].to_string()),
+        None => return Err(Error::new(ErrorKind::NotFound, format!("no method {}", method))),
+    };
+
+    Ok(Request {
+        interface,
+        method,
+        args,
+    })
+}
+
+/// Response to the request `id`.
+fn response(id: Value, result: Response<Value>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": VERSION, "result": result, "id": id }),
+        Err(e) => json!({
+            "jsonrpc": VERSION,
+            "error": { "code": code(e.kind()), "message": e.message() },
+            "id": id,
+        }),
+    }
+}
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL: i64 = -32603;
+/// Errors of the implementations, the first of the range left to servers.
+const SERVER_ERROR: i64 = -32000;
+
+/// Code of the error object of an error.
+fn code(kind: ErrorKind) -> i64 {
+    match kind {
+        ErrorKind::Malformed => PARSE_ERROR,
+        ErrorKind::InvalidRequest => INVALID_REQUEST,
+        ErrorKind::NotFound => METHOD_NOT_FOUND,
+        ErrorKind::InvalidArgs => INVALID_PARAMS,
+        ErrorKind::Io => INTERNAL,
+        ErrorKind::Service => SERVER_ERROR,
+    }
+}
+
+/// Kind of the error of an error object.
+fn kind(code: i64) -> ErrorKind {
+    match code {
+        PARSE_ERROR => ErrorKind::Malformed,
+        INVALID_REQUEST => ErrorKind::InvalidRequest,
+        METHOD_NOT_FOUND => ErrorKind::NotFound,
+        INVALID_PARAMS => ErrorKind::InvalidArgs,
+        INTERNAL => ErrorKind::Io,
+        _ => ErrorKind::Service,
+    }
+}
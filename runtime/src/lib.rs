@@ -0,0 +1,26 @@
+//! Runtime of the Rust code generated by rrpc.
+//!
+//! Generated clients send calls through a [`Transport`], and generated servers implement
+//! [`Service`] to be dispatched to by a [`Router`]. Calls are encoded by a [`Codec`], either
+//! [`JsonCodec`], [`JsonRpcCodec`] for JSON-RPC 2.0 peers, or [`BinaryCodec`] for the
+//! binary encoding, and carried in frames over
+//! streams such as TCP or Unix sockets, or handed to a router in the same process by
+//! [`Memory`].
+
+mod array;
+mod binary;
+mod codec;
+mod error;
+mod frame;
+mod jsonrpc;
+mod server;
+mod transport;
+
+pub use crate::array::Array;
+pub use crate::binary::{write_field, write_varint, BinaryCodec, Decode, Encode, Reader};
+pub use crate::codec::{Codec, JsonCodec, Request, Response};
+pub use crate::error::{Error, ErrorKind};
+pub use crate::frame::{read_frame, write_frame};
+pub use crate::jsonrpc::JsonRpcCodec;
+pub use crate::server::{Router, Service};
+pub use crate::transport::{Memory, Stream, Transport};
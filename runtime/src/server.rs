@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::codec::{Codec, Request, Response};
+use crate::error::Error;
+use crate::frame::{read_frame, write_frame};
+
+/// Server of an interface, implemented by the generated code.
+pub trait Service<C: Codec> {
+    /// Name of the interface.
+    fn interface(&self) -> &str;
+
+    /// Calls the function `method` with the encoded arguments, and encodes what it returns.
+    fn dispatch(&mut self, codec: &C, method: &str, args: C::Value) -> Result<C::Value, Error>;
+}
+
+/// Dispatches calls to the services by the names of their interfaces.
+pub struct Router<C> {
+    codec: C,
+    services: HashMap<String, Box<dyn Service<C> + Send>>,
+}
+
+impl<C: Codec> Router<C> {
+    pub fn new(codec: C) -> Self {
+        Self {
+            codec,
+            services: HashMap::new(),
+        }
+    }
+
+    /// Adds a service, replacing the one of the same interface if any.
+    pub fn with<S: Service<C> + Send + 'static>(mut self, service: S) -> Self {
+        self.add(service);
+        self
+    }
+
+    pub fn add<S: Service<C> + Send + 'static>(&mut self, service: S) {
+        self.services.insert(service.interface().into(), Box::new(service));
+    }
+
+    pub fn codec(&self) -> &C {
+        &self.codec
+    }
+
+    /// Calls a function of a service.
+    pub fn call(&mut self, request: Request<C::Value>) -> Response<C::Value> {
+        call(&mut self.services, &self.codec, request)
+    }
+
+    /// Answers a frame of requests, or `None` if none of them expects an answer.
+    ///
+    /// Errors of the calls are in the answer, only failing to encode it is an error.
+    pub fn handle(&mut self, frame: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        let Router {
+            ref codec,
+            ref mut services,
+        } = *self;
+
+        codec.answer(frame, &mut |request| call(services, codec, request))
+    }
+
+    /// Answers the requests of a stream until it ends.
+    pub fn serve<S: Read + Write>(&mut self, mut stream: S) -> Result<(), Error> {
+        while let Some(frame) = read_frame(&mut stream)? {
+            if let Some(response) = self.handle(&frame)? {
+                write_frame(&mut stream, &response)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn call<C: Codec>(
+    services: &mut HashMap<String, Box<dyn Service<C> + Send>>,
+    codec: &C,
+    request: Request<C::Value>,
+) -> Response<C::Value> {
+    let service = services
+        .get_mut(&request.interface)
+        .ok_or_else(|| Error::not_found(&request.interface, &request.method))?;
+
+    service.dispatch(codec, &request.method, request.args)
+}
+
+impl<C: Codec + Send + 'static> Router<C> {
+    /// Serves the connections of a TCP listener, each on its own thread.
+    pub fn serve_tcp(self, listener: TcpListener) -> Result<(), Error> {
+        let router = Arc::new(Mutex::new(self));
+
+        for stream in listener.incoming() {
+            let stream = stream?;
+            stream.set_nodelay(true)?;
+            spawn(&router, stream);
+        }
+
+        Ok(())
+    }
+
+    /// Serves the connections of a Unix socket listener, each on its own thread.
+    #[cfg(unix)]
+    pub fn serve_unix(self, listener: UnixListener) -> Result<(), Error> {
+        let router = Arc::new(Mutex::new(self));
+
+        for stream in listener.incoming() {
+            spawn(&router, stream?);
+        }
+
+        Ok(())
+    }
+}
+
+/// Serves a connection on a new thread, holding the router for one request at a time.
+fn spawn<C, S>(router: &Arc<Mutex<Router<C>>>, mut stream: S)
+where
+    C: Codec + Send + 'static,
+    S: Read + Write + Send + 'static,
+{
+    let router = router.clone();
+
+    thread::spawn(move || -> Result<(), Error> {
+        while let Some(frame) = read_frame(&mut stream)? {
+            let response = match router.lock() {
+                Ok(mut router) => router.handle(&frame)?,
+                Err(_) => return Ok(()),
+            };
+            if let Some(response) = response {
+                write_frame(&mut stream, &response)?;
+            }
+        }
+
+        Ok(())
+    });
+}
@@ -0,0 +1,146 @@
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+#[cfg(unix)]
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::binary::{Decode, Encode};
+use crate::codec::{Codec, Request};
+use crate::error::{Error, ErrorKind};
+use crate::frame::{read_frame, write_frame};
+use crate::server::Router;
+
+/// Carries the calls of client stubs to a server.
+pub trait Transport {
+    type Error;
+
+    /// Calls a function with the tuple of its arguments, named `names`.
+    fn call<A, R>(
+        &mut self,
+        interface: &str,
+        method: &str,
+        names: &[&str],
+        args: A,
+    ) -> Result<R, Self::Error>
+    where
+        A: Serialize + Encode,
+        R: DeserializeOwned + Decode;
+}
+
+/// Calls the services of a router in the same process.
+///
+/// Calls are encoded in frames as they would be on a stream, so that services can be
+/// tested without a socket.
+pub struct Memory<C> {
+    router: Router<C>,
+}
+
+impl<C> Memory<C> {
+    pub fn new(router: Router<C>) -> Self {
+        Self { router }
+    }
+
+    pub fn into_inner(self) -> Router<C> {
+        self.router
+    }
+}
+
+impl<C: Codec> Transport for Memory<C> {
+    type Error = Error;
+
+    fn call<A, R>(
+        &mut self,
+        interface: &str,
+        method: &str,
+        names: &[&str],
+        args: A,
+    ) -> Result<R, Error>
+    where
+        A: Serialize + Encode,
+        R: DeserializeOwned + Decode,
+    {
+        let request = request(self.router.codec(), interface, method, names, &args)?;
+        let response = self.router
+            .handle(&request)?
+            .ok_or_else(|| Error::new(ErrorKind::Malformed, "no response"))?;
+        response_of(self.router.codec(), &response)
+    }
+}
+
+/// Sends the calls in frames over a stream, and waits for the response of each.
+pub struct Stream<C, S> {
+    codec: C,
+    stream: S,
+}
+
+impl<C, S> Stream<C, S> {
+    pub fn new(codec: C, stream: S) -> Self {
+        Self { codec, stream }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<C> Stream<C, TcpStream> {
+    pub fn tcp<A: ToSocketAddrs>(codec: C, addr: A) -> Result<Self, Error> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        Ok(Self::new(codec, stream))
+    }
+}
+
+#[cfg(unix)]
+impl<C> Stream<C, UnixStream> {
+    pub fn unix<P: AsRef<Path>>(codec: C, path: P) -> Result<Self, Error> {
+        Ok(Self::new(codec, UnixStream::connect(path)?))
+    }
+}
+
+impl<C: Codec, S: Read + Write> Transport for Stream<C, S> {
+    type Error = Error;
+
+    fn call<A, R>(
+        &mut self,
+        interface: &str,
+        method: &str,
+        names: &[&str],
+        args: A,
+    ) -> Result<R, Error>
+    where
+        A: Serialize + Encode,
+        R: DeserializeOwned + Decode,
+    {
+        let request = request(&self.codec, interface, method, names, &args)?;
+        write_frame(&mut self.stream, &request)?;
+
+        let response = read_frame(&mut self.stream)?
+            .ok_or_else(|| Error::new(ErrorKind::Io, "connection closed"))?;
+        response_of(&self.codec, &response)
+    }
+}
+
+/// Frame of a call.
+fn request<C: Codec, A: Serialize + Encode>(
+    codec: &C,
+    interface: &str,
+    method: &str,
+    names: &[&str],
+    args: &A,
+) -> Result<Vec<u8>, Error> {
+    codec.encode_request(&Request {
+        interface: interface.into(),
+        method: method.into(),
+        args: codec.encode_args(names, args)?,
+    })
+}
+
+/// Returned values in a response frame, or the error of the call.
+fn response_of<C: Codec, R: DeserializeOwned + Decode>(codec: &C, frame: &[u8]) -> Result<R, Error> {
+    codec.decode(codec.decode_response(frame)??)
+}
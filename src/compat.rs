@@ -0,0 +1,442 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+
+use serde_json::Value;
+
+use super::lang::{locate, qualify};
+use super::types::*;
+
+use self::Compat::{Breaking, Compatible};
+
+/// Whether a change keeps what was built against the old definitions working.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compat {
+    Compatible,
+    Breaking,
+}
+
+impl fmt::Display for Compat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Compat::Compatible => write!(f, "compatible"),
+            Compat::Breaking => write!(f, "breaking"),
+        }
+    }
+}
+
+/// A difference between two versions of the definitions.
+#[derive(Debug, Clone, Serialize)]
+pub struct Change {
+    /// Path of what changed, e.g. `geo::Point.y` or `geo::store.get`.
+    pub path: String,
+    pub message: String,
+    /// Whether peers built from either version still understand each other.
+    pub wire: Compat,
+    /// Whether code written against the old version still builds against the new one.
+    pub source: Compat,
+}
+
+impl Change {
+    fn new(path: &str, message: String, wire: Compat, source: Compat) -> Self {
+        Self {
+            path: path.into(),
+            message,
+            wire,
+            source,
+        }
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        self.wire == Breaking || self.source == Breaking
+    }
+}
+
+/// The changes from one version of the definitions to another, as `rrpc compat` reports them.
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub breaking: bool,
+    pub changes: Vec<Change>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.changes.is_empty() {
+            return writeln!(f, "no changes");
+        }
+
+        writeln!(f, "{:<11} {:<11} change", "wire", "source")?;
+        for c in &self.changes {
+            writeln!(
+                f,
+                "{:<11} {:<11} {}: {}",
+                c.wire.to_string(),
+                c.source.to_string(),
+                c.path,
+                c.message
+            )?;
+        }
+
+        let breaking = self.changes.iter().filter(|c| c.is_breaking()).count();
+        writeln!(f, "\n{} changes, {} breaking", self.changes.len(), breaking)
+    }
+}
+
+/// Names of the types of a model, qualified with the namespace of their module.
+///
+/// Types of the root module are keyed without a namespace, so that the keys of models
+/// compiled from different roots wouldn't match otherwise.
+struct Names {
+    root: String,
+    files: Vec<String>,
+}
+
+impl Names {
+    fn key(&self, key: &str) -> String {
+        let files = self.files.iter().map(|ns| ns.as_str()).collect::<Vec<_>>();
+
+        match locate(&files, &self.root, key) {
+            (ns, path) if ns == self.root => qualify(ns, path),
+            _ => key.into(),
+        }
+    }
+
+    /// Describes a type the way it's written in the definitions, with qualified names.
+    fn ty(&self, ty: &Type) -> String {
+        match *ty.info() {
+            TypeInfo::Primitive(ref p) => p.name.clone(),
+            TypeInfo::Template(ref t) => {
+                let mut params = t.params.iter().map(|p| self.ty(p)).collect::<Vec<_>>();
+                params.extend(t.size.map(|n| n.to_string()));
+
+                format!("{}<{}>", t.name, params.join(", "))
+            }
+            _ => self.key(&ty.key()),
+        }
+    }
+}
+
+struct Def {
+    node: Node,
+    names: Rc<Names>,
+}
+
+/// Structs, enums, unions and interfaces of a set of models, by their qualified names.
+pub struct Tree {
+    defs: BTreeMap<String, Def>,
+}
+
+impl Tree {
+    pub fn new(models: &[Model]) -> Self {
+        let mut defs = BTreeMap::new();
+
+        for model in models {
+            let names = Rc::new(Names {
+                root: model.root.namespace.clone(),
+                files: model.modules.keys().cloned().collect(),
+            });
+
+            for (ns, module) in &model.modules {
+                let scope = if *ns == names.root { "" } else { ns.as_str() };
+                collect(scope, &module.nodes, &names, &mut defs);
+            }
+        }
+
+        Self { defs }
+    }
+}
+
+fn collect(scope: &str, nodes: &[Node], names: &Rc<Names>, defs: &mut BTreeMap<String, Def>) {
+    for node in nodes {
+        let name = match *node {
+            Node::Struct(ref s) => &s.name,
+            Node::Enum(ref e) => &e.name,
+            Node::Union(ref u) => &u.name,
+            Node::Interface(ref i) => &i.name,
+            Node::Module(ref m) => {
+                collect(&m.namespace, &m.nodes, names, defs);
+                continue;
+            }
+        };
+
+        let def = Def {
+            node: node.clone(),
+            names: names.clone(),
+        };
+        defs.insert(names.key(&qualify(scope, name)), def);
+    }
+}
+
+fn kind(node: &Node) -> &'static str {
+    match *node {
+        Node::Struct(_) => "struct",
+        Node::Enum(_) => "enum",
+        Node::Union(_) => "union",
+        Node::Interface(_) => "interface",
+        Node::Module(_) => "module",
+    }
+}
+
+/// Lists the changes from `old` to `new`.
+///
+/// Fields are matched by their ordinals, variants of enums and functions by their names,
+/// and variants of unions by their names, with their ordinals being their tags.
+pub fn compare(old: &Tree, new: &Tree) -> Report {
+    let mut changes = Vec::new();
+
+    for (path, o) in &old.defs {
+        let n = match new.defs.get(path) {
+            Some(n) => n,
+            None => {
+                // Types can only go once nothing refers to them, which is reported on its own.
+                let wire = match o.node {
+                    Node::Interface(_) => Breaking,
+                    _ => Compatible,
+                };
+                let message = format!("{} removed", kind(&o.node));
+                changes.push(Change::new(path, message, wire, Breaking));
+                continue;
+            }
+        };
+
+        let mut diff = Diff {
+            old: &o.names,
+            new: &n.names,
+            changes: &mut changes,
+        };
+
+        match (&o.node, &n.node) {
+            (Node::Struct(a), Node::Struct(b)) => diff.structure(path, a, b),
+            (Node::Enum(a), Node::Enum(b)) => diff.enumeration(path, a, b),
+            (Node::Union(a), Node::Union(b)) => diff.union(path, a, b),
+            (Node::Interface(a), Node::Interface(b)) => diff.interface(path, a, b),
+            (a, b) => {
+                let message = format!("{} became {} {}", kind(a), article(kind(b)), kind(b));
+                diff.push(path, message, Breaking, Breaking);
+            }
+        }
+    }
+
+    for (path, n) in &new.defs {
+        if !old.defs.contains_key(path) {
+            let message = format!("{} added", kind(&n.node));
+            changes.push(Change::new(path, message, Compatible, Compatible));
+        }
+    }
+
+    Report {
+        breaking: changes.iter().any(|c| c.is_breaking()),
+        changes,
+    }
+}
+
+fn article(word: &str) -> &'static str {
+    match word.chars().next() {
+        Some('a') | Some('e') | Some('i') | Some('o') | Some('u') => "an",
+        _ => "a",
+    }
+}
+
+fn value(v: &Option<Value>) -> String {
+    v.as_ref().map_or("none".into(), |v| v.to_string())
+}
+
+struct Diff<'a> {
+    old: &'a Names,
+    new: &'a Names,
+    changes: &'a mut Vec<Change>,
+}
+
+impl<'a> Diff<'a> {
+    fn push(&mut self, path: &str, message: String, wire: Compat, source: Compat) {
+        self.changes.push(Change::new(path, message, wire, source));
+    }
+
+    /// Reports a change of type, which no encoding reads the same.
+    fn ty(&mut self, path: &str, what: &str, old: &Type, new: &Type) {
+        let (old, new) = (self.old.ty(old), self.new.ty(new));
+
+        if old != new {
+            let message = format!("type of {} changed from {} to {}", what, old, new);
+            self.push(path, message, Breaking, Breaking);
+        }
+    }
+
+    fn structure(&mut self, path: &str, old: &Struct, new: &Struct) {
+        // Ordinals of the new fields matched with an old one.
+        let mut matched = Vec::new();
+
+        for f in &old.members {
+            let path = format!("{}.{}", path, f.name);
+
+            let g = match new.members.iter().find(|g| g.id == f.id) {
+                Some(g) => {
+                    if g.name != f.name {
+                        // The binary encoding reads it the same, but not JSON.
+                        let message = format!("field {} renamed to {}", f.id, g.name);
+                        self.push(&path, message, Breaking, Breaking);
+                    }
+                    g
+                }
+                None => match new.members.iter().find(|g| g.name == f.name) {
+                    Some(g) => {
+                        let message = format!("ordinal changed from {} to {}", f.id, g.id);
+                        self.push(&path, message, Breaking, Compatible);
+                        g
+                    }
+                    None if new.reserved.has_id(f.id) && new.reserved.has_name(&f.name) => {
+                        let message = format!("field {} removed and reserved", f.id);
+                        self.push(&path, message, Compatible, Breaking);
+                        continue;
+                    }
+                    None => {
+                        let message = format!("field {} removed without reserving it", f.id);
+                        self.push(&path, message, Breaking, Breaking);
+                        continue;
+                    }
+                },
+            };
+
+            matched.push(g.id);
+            self.ty(&path, "field", &f.ty, &g.ty);
+
+            // Readers give the default to fields missing from what they read.
+            if f.value != g.value {
+                let message = format!(
+                    "default changed from {} to {}",
+                    value(&f.value),
+                    value(&g.value)
+                );
+                self.push(&path, message, Compatible, Compatible);
+            }
+        }
+
+        for g in &new.members {
+            if !matched.contains(&g.id) {
+                let path = format!("{}.{}", path, g.name);
+                self.push(&path, format!("field {} added", g.id), Compatible, Compatible);
+            }
+        }
+    }
+
+    fn enumeration(&mut self, path: &str, old: &Enum, new: &Enum) {
+        self.ty(path, "enum", &old.utype, &new.utype);
+
+        for v in &old.members {
+            let path = format!("{}.{}", path, v.name);
+
+            match new.members.iter().find(|w| w.name == v.name) {
+                Some(w) if w.value != v.value => {
+                    let message = format!(
+                        "variant renumbered from {} to {}",
+                        value(&v.value),
+                        value(&w.value)
+                    );
+                    self.push(&path, message, Breaking, Compatible);
+                }
+                Some(_) => {}
+                None => self.push(&path, "variant removed".into(), Breaking, Breaking),
+            }
+        }
+
+        for w in &new.members {
+            if !old.members.iter().any(|v| v.name == w.name) {
+                let path = format!("{}.{}", path, w.name);
+                self.push(&path, "variant added".into(), Compatible, Compatible);
+            }
+        }
+    }
+
+    fn union(&mut self, path: &str, old: &Union, new: &Union) {
+        for v in &old.members {
+            let path = format!("{}.{}", path, v.name);
+
+            let w = match new.members.iter().find(|w| w.name == v.name) {
+                Some(w) => w,
+                None => {
+                    self.push(&path, "variant removed".into(), Breaking, Breaking);
+                    continue;
+                }
+            };
+
+            if v.id != w.id {
+                let message = format!("tag changed from {} to {}", v.id, w.id);
+                self.push(&path, message, Breaking, Compatible);
+            }
+
+            match (&v.ty, &w.ty) {
+                (Some(a), Some(b)) => self.ty(&path, "variant", a, b),
+                (None, None) => {}
+                (Some(_), None) => {
+                    self.push(&path, "payload removed".into(), Breaking, Breaking)
+                }
+                (None, Some(_)) => self.push(&path, "payload added".into(), Breaking, Breaking),
+            }
+        }
+
+        for w in &new.members {
+            if !old.members.iter().any(|v| v.name == w.name) {
+                let path = format!("{}.{}", path, w.name);
+                self.push(&path, "variant added".into(), Compatible, Compatible);
+            }
+        }
+    }
+
+    fn interface(&mut self, path: &str, old: &Interface, new: &Interface) {
+        if old.pattern != new.pattern {
+            let message = format!("pattern changed from {} to {}", old.pattern, new.pattern);
+            self.push(path, message, Breaking, Compatible);
+        }
+
+        for f in &old.funcs {
+            let path = format!("{}.{}", path, f.name);
+
+            match new.funcs.iter().find(|g| g.name == f.name) {
+                Some(g) => self.func(&path, f, g),
+                None => self.push(&path, "function removed".into(), Breaking, Breaking),
+            }
+        }
+
+        for g in &new.funcs {
+            if !old.funcs.iter().any(|f| f.name == g.name) {
+                let path = format!("{}.{}", path, g.name);
+                self.push(&path, "function added".into(), Compatible, Compatible);
+            }
+        }
+    }
+
+    fn func(&mut self, path: &str, old: &Func, new: &Func) {
+        if old.args.len() != new.args.len() {
+            let message = format!(
+                "number of arguments changed from {} to {}",
+                old.args.len(),
+                new.args.len()
+            );
+            self.push(path, message, Breaking, Breaking);
+        } else {
+            for (a, b) in old.args.iter().zip(&new.args) {
+                // Arguments are passed by position, but also by name in JSON-RPC.
+                if a.name != b.name {
+                    let message = format!("argument {} renamed to {}", a.name, b.name);
+                    self.push(path, message, Breaking, Compatible);
+                }
+
+                self.ty(path, &format!("argument {}", b.name), &a.ty, &b.ty);
+            }
+        }
+
+        if old.ret.len() != new.ret.len() {
+            let message = format!(
+                "number of return values changed from {} to {}",
+                old.ret.len(),
+                new.ret.len()
+            );
+            self.push(path, message, Breaking, Breaking);
+        } else {
+            for (i, (a, b)) in old.ret.iter().zip(&new.ret).enumerate() {
+                self.ty(path, &format!("return value {}", i + 1), a, b);
+            }
+        }
+    }
+}
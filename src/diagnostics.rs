@@ -0,0 +1,222 @@
+use std::fmt;
+
+use pest::error::{Error as PestError, InputLocation};
+
+use super::error::{Error, ErrorKind};
+use super::parser::Rule;
+
+/// The number of errors reported by a compile unless configured otherwise.
+pub const DEFAULT_MAX_ERRORS: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A position in a source file, 1-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    fn from_offset(source: &str, offset: usize) -> Self {
+        let before = &source[..offset.min(source.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+
+        Self { line, column }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub path: String,
+    pub start: Option<Location>,
+    pub end: Option<Location>,
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Makes a diagnostic from an error which occurred in the file `path` containing `source`.
+    ///
+    /// Errors which don't stop the output from being generated are warnings.
+    pub fn from_error(e: &Error, path: &str, source: &str) -> Self {
+        let (start, end, message) = match located(e, path) {
+            Some((location, mut message)) => {
+                let (start, end) = match location {
+                    InputLocation::Pos(pos) => (pos, None),
+                    InputLocation::Span((start, end)) => (start, Some(end)),
+                };
+
+                for cause in e.iter().skip(1) {
+                    message.push_str(&format!("\n  caused by: {}", cause));
+                }
+
+                (
+                    Some(Location::from_offset(source, start)),
+                    end.map(|end| Location::from_offset(source, end)),
+                    message,
+                )
+            }
+            None => (
+                None,
+                None,
+                e.iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join(": "),
+            ),
+        };
+
+        Self {
+            path: path.into(),
+            start,
+            end,
+            severity: severity(e.kind()),
+            code: code(e.kind()),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.start {
+            Some(_) => write!(f, "{}[{}]: {}", self.severity, self.code, self.message),
+            None => write!(
+                f,
+                "{}[{}]: {}: {}",
+                self.severity, self.code, self.path, self.message
+            ),
+        }
+    }
+}
+
+/// Diagnostics gathered through a compile.
+pub struct Diagnostics {
+    list: Vec<Diagnostic>,
+    max_errors: usize,
+}
+
+impl Diagnostics {
+    pub fn new(max_errors: usize) -> Self {
+        Self {
+            list: Vec::new(),
+            max_errors,
+        }
+    }
+
+    /// Adds a diagnostic. Returns false if no more errors should be reported.
+    pub fn push(&mut self, diag: Diagnostic) -> bool {
+        debug!("Diagnostic: {}", diag);
+
+        self.list.push(diag);
+
+        self.errors() < self.max_errors
+    }
+
+    pub fn errors(&self) -> usize {
+        self.list
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .count()
+    }
+
+    pub fn max_errors(&self) -> usize {
+        self.max_errors
+    }
+
+    /// Takes the diagnostics, ordered by location within each file.
+    pub fn take(&mut self) -> Vec<Diagnostic> {
+        let mut list = std::mem::take(&mut self.list);
+
+        let mut files = Vec::new();
+        for d in &list {
+            if !files.contains(&d.path) {
+                files.push(d.path.clone());
+            }
+        }
+
+        list.sort_by_key(|d| {
+            let file = files.iter().position(|f| f == &d.path);
+            let start = d.start.map(|l| (l.line, l.column));
+            (file, start)
+        });
+
+        list
+    }
+}
+
+/// Location and message of an error with a location.
+fn located(e: &Error, path: &str) -> Option<(InputLocation, String)> {
+    match *e.kind() {
+        ErrorKind::ProtoParseError(ref pe) => {
+            Some((pe.location.clone(), pe.clone().with_path(path).to_string()))
+        }
+        _ => pest_error(e).map(|pe| (pe.location.clone(), pe.clone().with_path(path).to_string())),
+    }
+}
+
+fn pest_error(e: &Error) -> Option<&PestError<Rule>> {
+    match *e.kind() {
+        ErrorKind::ValueError(ref e)
+        | ErrorKind::MismatchedValue(ref e)
+        | ErrorKind::EnumError(ref e)
+        | ErrorKind::OrdinalError(ref e)
+        | ErrorKind::TypeNotFound(ref e)
+        | ErrorKind::TemplateError(ref e)
+        | ErrorKind::InfiniteType(ref e)
+        | ErrorKind::LoadError(ref e)
+        | ErrorKind::ParseError(ref e)
+        | ErrorKind::MissingToken(ref e)
+        | ErrorKind::UnexpectedToken(ref e)
+        | ErrorKind::Duplicated(ref e)
+        | ErrorKind::RedundantUse(ref e)
+        | ErrorKind::ProtoError(ref e) => Some(e),
+        _ => None,
+    }
+}
+
+fn severity(kind: &ErrorKind) -> Severity {
+    match *kind {
+        ErrorKind::RedundantUse(_) => Severity::Warning,
+        _ => Severity::Error,
+    }
+}
+
+fn code(kind: &ErrorKind) -> &'static str {
+    match *kind {
+        ErrorKind::FileError(_) => "E0001",
+        ErrorKind::ParseError(_) => "E0002",
+        ErrorKind::LoadError(_) => "E0003",
+        ErrorKind::ValueError(_) => "E0004",
+        ErrorKind::MismatchedValue(_) => "E0005",
+        ErrorKind::TypeNotFound(_) => "E0006",
+        ErrorKind::Duplicated(_) => "E0007",
+        ErrorKind::TemplateError(_) => "E0008",
+        ErrorKind::InfiniteType(_) => "E0009",
+        ErrorKind::EnumError(_) => "E0010",
+        ErrorKind::MissingToken(_) => "E0011",
+        ErrorKind::UnexpectedToken(_) => "E0012",
+        ErrorKind::ProtoParseError(_) => "E0002",
+        ErrorKind::ProtoError(_) => "E0013",
+        ErrorKind::OrdinalError(_) => "E0014",
+        ErrorKind::RedundantUse(_) => "W0001",
+        _ => "E0000",
+    }
+}
@@ -3,7 +3,11 @@ use pest::iterators::Pair;
 use tera::Error as TeraError;
 use error_chain::ChainedError;
 
+use std::path::Path;
+
 use super::parser::Rule;
+use super::proto::Rule as ProtoRule;
+use super::diagnostics::Diagnostic;
 
 error_chain! {
     errors {
@@ -22,11 +26,36 @@ error_chain! {
                 display("{}", e)
         }
 
+        MismatchedValue(e: PestError<Rule>) {
+            description("compile error")
+                display("{}", e)
+        }
+
+        EnumError(e: PestError<Rule>) {
+            description("compile error")
+                display("{}", e)
+        }
+
+        OrdinalError(e: PestError<Rule>) {
+            description("compile error")
+                display("{}", e)
+        }
+
         TypeNotFound(e: PestError<Rule>) {
             description("compile error")
                 display("{}", e)
         }
 
+        TemplateError(e: PestError<Rule>) {
+            description("compile error")
+                display("{}", e)
+        }
+
+        InfiniteType(e: PestError<Rule>) {
+            description("compile error")
+                display("{}", e)
+        }
+
         LoadError(e: PestError<Rule>) {
             description("compile error")
                 display("{}", e)
@@ -37,11 +66,36 @@ error_chain! {
                 display("{}", e)
         }
 
+        MissingToken(e: PestError<Rule>) {
+            description("compile error")
+                display("{}", e)
+        }
+
+        UnexpectedToken(e: PestError<Rule>) {
+            description("compile error")
+                display("{}", e)
+        }
+
         Duplicated(e: PestError<Rule>) {
             description("compile error")
                 display("{}", e)
         }
 
+        RedundantUse(e: PestError<Rule>) {
+            description("compile warning")
+                display("{}", e)
+        }
+
+        ProtoParseError(e: PestError<ProtoRule>) {
+            description("compile error")
+                display("{}", e)
+        }
+
+        ProtoError(e: PestError<Rule>) {
+            description("compile error")
+                display("{}", e)
+        }
+
         PackError(e: serde_json::error::Error) {
             description("compile error")
                 display("{}", e)
@@ -52,6 +106,24 @@ error_chain! {
                 display("{}", e.display_chain().to_string())
         }
 
+        OutputError(e: String) {
+            description("output error")
+                display("{}", e)
+        }
+
+        Diagnostics(e: Vec<Diagnostic>) {
+            description("compile error")
+                display("{}", e.iter()
+                    .map(|d| d.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n"))
+        }
+
+        TooManyErrors(max: usize) {
+            description("compile error")
+                display("too many errors (max: {})", max)
+        }
+
         GeneratorNotFound(e: String) {
             description("config error")
                 display("{}", e)
@@ -76,6 +148,33 @@ pub fn value_error<T: ToString>(p: &Pair<Rule>, e: T) -> Error {
     )).into()
 }
 
+pub fn mismatched_value<T: ToString>(p: &Pair<Rule>, e: T) -> Error {
+    ErrorKind::MismatchedValue(PestError::new_from_span(
+        ErrorVariant::CustomError {
+            message: format!("invalid value: {}", e.to_string()),
+        },
+        p.as_span(),
+    )).into()
+}
+
+pub fn enum_error<T: ToString>(p: &Pair<Rule>, message: T) -> Error {
+    ErrorKind::EnumError(PestError::new_from_span(
+        ErrorVariant::CustomError {
+            message: message.to_string(),
+        },
+        p.as_span(),
+    )).into()
+}
+
+pub fn ordinal_error<T: ToString>(p: &Pair<Rule>, message: T) -> Error {
+    ErrorKind::OrdinalError(PestError::new_from_span(
+        ErrorVariant::CustomError {
+            message: message.to_string(),
+        },
+        p.as_span(),
+    )).into()
+}
+
 pub fn type_not_found(p: &Pair<Rule>) -> Error {
     ErrorKind::TypeNotFound(PestError::new_from_span(
         ErrorVariant::CustomError {
@@ -85,6 +184,31 @@ pub fn type_not_found(p: &Pair<Rule>) -> Error {
     )).into()
 }
 
+pub fn unknown_template(p: &Pair<Rule>) -> Error {
+    template_error(p, format!("unknown template: {}", p.as_str()))
+}
+
+pub fn template_error<T: ToString>(p: &Pair<Rule>, message: T) -> Error {
+    ErrorKind::TemplateError(PestError::new_from_span(
+        ErrorVariant::CustomError {
+            message: message.to_string(),
+        },
+        p.as_span(),
+    )).into()
+}
+
+pub fn infinite_type(p: &Pair<Rule>) -> Error {
+    ErrorKind::InfiniteType(PestError::new_from_span(
+        ErrorVariant::CustomError {
+            message: format!(
+                "recursive type has infinite size: {} (recursion needs indirection such as vector or optional)",
+                p.as_str()
+            ),
+        },
+        p.as_span(),
+    )).into()
+}
+
 pub fn load_error(p: &Pair<Rule>, module: &str) -> Error {
     ErrorKind::LoadError(PestError::new_from_span(
         ErrorVariant::CustomError {
@@ -103,10 +227,61 @@ pub fn duplicated(name: &str, p: &Pair<Rule>) -> Error {
     )).into()
 }
 
+/// A `use` of a module the scope already imports, which is reported as a warning.
+pub fn redundant_use(p: &Pair<Rule>, ns: &str) -> Error {
+    ErrorKind::RedundantUse(PestError::new_from_span(
+        ErrorVariant::CustomError {
+            message: format!("module {} is already imported", ns),
+        },
+        p.as_span(),
+    )).into()
+}
+
+pub fn missing_token(p: &Pair<Rule>, rule: Rule) -> Error {
+    ErrorKind::MissingToken(PestError::new_from_span(
+        ErrorVariant::CustomError {
+            message: format!("missing {:?} in {:?}", rule, p.as_rule()),
+        },
+        p.as_span(),
+    )).into()
+}
+
+pub fn unexpected_token(p: &Pair<Rule>) -> Error {
+    ErrorKind::UnexpectedToken(PestError::new_from_span(
+        ErrorVariant::CustomError {
+            message: format!("unexpected {:?}", p.as_rule()),
+        },
+        p.as_span(),
+    )).into()
+}
+
 pub fn parse_error(e: PestError<Rule>) -> Error {
     ErrorKind::ParseError(e).into()
 }
 
+pub fn proto_parse_error(e: PestError<ProtoRule>) -> Error {
+    ErrorKind::ProtoParseError(e).into()
+}
+
+/// An error in a proto file, which is located by a span of it.
+pub fn proto_error<T: ToString>(p: &Pair<ProtoRule>, message: T) -> Error {
+    ErrorKind::ProtoError(PestError::new_from_span(
+        ErrorVariant::CustomError {
+            message: message.to_string(),
+        },
+        p.as_span(),
+    )).into()
+}
+
+/// A module imported from a file while another file is loaded under its namespace.
+pub fn module_conflict(ns: &str, loaded: &Path) -> Error {
+    ErrorKind::FileError(format!(
+        "another file is already loaded as module {}: {}",
+        ns,
+        loaded.display()
+    )).into()
+}
+
 pub fn pack_error(e: serde_json::error::Error) -> Error {
     ErrorKind::PackError(e).into()
 }
@@ -115,6 +290,18 @@ pub fn render_error(e: TeraError) -> Error {
     ErrorKind::RenderError(e).into()
 }
 
+pub fn diagnostics(e: Vec<Diagnostic>) -> Error {
+    ErrorKind::Diagnostics(e).into()
+}
+
+pub fn too_many_errors(max: usize) -> Error {
+    ErrorKind::TooManyErrors(max).into()
+}
+
+pub fn output_error<T: ToString>(e: T) -> Error {
+    ErrorKind::OutputError(e.to_string()).into()
+}
+
 pub fn generator_not_found(e: &str) -> Error {
     ErrorKind::GeneratorNotFound(format!("Generator not found: {}", e)).into()
 }
@@ -0,0 +1,82 @@
+use super::error::Result;
+use super::parser;
+
+const INDENT: &str = "    ";
+
+/// Formats the source of a module.
+///
+/// Lines are indented by the nesting of braces, trailing whitespace is removed,
+/// and runs of blank lines are collapsed into one. Comments are kept as they are.
+/// The source needs to be parsable, so that a broken file is never rewritten.
+pub fn format(source: &str) -> Result<String> {
+    parser::parse(source)?;
+
+    let mut out = String::new();
+    let mut depth = 0usize;
+    let mut blank = false;
+
+    for line in source.lines() {
+        let line = line.trim();
+
+        if line.is_empty() {
+            blank = !out.is_empty();
+            continue;
+        }
+
+        let (opens, closes, leading) = braces(line);
+
+        // No blank line right after an opening brace, nor right before a closing one.
+        if blank && !out.ends_with("{\n") && leading == 0 {
+            out.push('\n');
+        }
+        blank = false;
+
+        for _ in 0..depth.saturating_sub(leading) {
+            out.push_str(INDENT);
+        }
+        out.push_str(line);
+        out.push('\n');
+
+        depth = (depth + opens).saturating_sub(closes);
+    }
+
+    Ok(out)
+}
+
+/// Counts braces in a line, skipping strings and comments.
+///
+/// Returns the numbers of opening braces, closing braces, and closing braces the line starts with.
+fn braces(line: &str) -> (usize, usize, usize) {
+    let mut opens = 0;
+    let mut closes = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '/' if chars.peek() == Some(&'/') => break,
+            '{' => opens += 1,
+            '}' => closes += 1,
+            _ => {}
+        }
+    }
+
+    let leading = line.chars()
+        .take_while(|&c| c == '}' || c.is_whitespace())
+        .filter(|&c| c == '}')
+        .count();
+
+    (opens, closes, leading)
+}
@@ -1,15 +1,24 @@
 use pest::iterators::{Pair, Pairs};
+use serde_json::Value;
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::path::PathBuf;
 
 use super::error;
+use super::utils;
 use super::parser::{get, get_all, get_comment, get_opt, parse, parse_value, Rule};
 use super::resolver::Resolver;
-use super::error::{Result, ResultExt};
+use super::error::{Error, Result, ResultExt};
+use super::diagnostics::{Diagnostic, Diagnostics};
 use super::loader::Loader;
+use super::order;
+use super::proto;
+use super::value;
 use super::types::*;
 
 use super::lang::LangGenerator;
+use super::Options;
 
 struct DupChecker<'a> {
     name: &'a str,
@@ -33,6 +42,149 @@ impl<'a> DupChecker<'a> {
     }
 }
 
+/// Assigns discriminants to enum variants.
+///
+/// A variant without a value gets the one following the previous variant.
+struct Discriminants {
+    next: i128,
+    range: Option<(i128, i128)>,
+    seen: HashMap<i128, String>,
+}
+
+impl Discriminants {
+    fn new(range: Option<(i128, i128)>) -> Self {
+        Self {
+            next: 0,
+            range,
+            seen: HashMap::new(),
+        }
+    }
+
+    fn assign(&mut self, p: &Pair<Rule>, value: &Option<Value>) -> Result<Value> {
+        let n = match *value {
+            Some(ref value) => value::as_int(value).ok_or_else(|| {
+                error::enum_error(p, format!("discriminant needs to be an integer: {}", value))
+            })?,
+            None => self.next,
+        };
+
+        if let Some((min, max)) = self.range {
+            if n < min || n > max {
+                return Err(error::enum_error(
+                    p,
+                    format!("discriminant {} is out of range ({}..={})", n, min, max),
+                ));
+            }
+        }
+
+        let ident = get(p, Rule::Identifier)?.as_str().to_string();
+
+        if let Some(other) = self.seen.insert(n, ident) {
+            return Err(error::enum_error(
+                p,
+                format!("discriminant {} is already used by {}", n, other),
+            ));
+        }
+
+        self.next = n + 1;
+
+        // Any discriminant in range of the built-in integer types fits in either of them.
+        Ok(if n < 0 { json!(n as i64) } else { json!(n as u64) })
+    }
+}
+
+/// Assigns ordinals to struct fields and union variants.
+///
+/// A member without an ordinal gets the one following the previous member, from 1.
+struct Ordinals<'r> {
+    next: u64,
+    reserved: &'r Reserved,
+    seen: HashMap<u32, String>,
+}
+
+impl<'r> Ordinals<'r> {
+    fn new(reserved: &'r Reserved) -> Self {
+        Self {
+            next: 1,
+            reserved,
+            seen: HashMap::new(),
+        }
+    }
+
+    fn assign(&mut self, p: &Pair<Rule>) -> Result<u32> {
+        let n = match get_opt(p, Rule::Ordinal) {
+            Some(ref ordinal) => ordinal_of(ordinal)?,
+            None => u32::try_from(self.next)
+                .map_err(|_| error::ordinal_error(p, "ordinal is out of range"))?,
+        };
+
+        let ident = get(p, Rule::Identifier)?;
+
+        if self.reserved.has_name(ident.as_str()) {
+            return Err(error::ordinal_error(
+                &ident,
+                format!("name {} is reserved", ident.as_str()),
+            ));
+        }
+
+        if self.reserved.has_id(n) {
+            return Err(error::ordinal_error(p, format!("ordinal {} is reserved", n)));
+        }
+
+        if let Some(other) = self.seen.insert(n, ident.as_str().into()) {
+            return Err(error::ordinal_error(
+                p,
+                format!("ordinal {} is already used by {}", n, other),
+            ));
+        }
+
+        self.next = u64::from(n) + 1;
+
+        Ok(n)
+    }
+}
+
+/// Value of an `Ordinal`, which is from 1 to `u32::MAX`.
+fn ordinal_of(p: &Pair<Rule>) -> Result<u32> {
+    match p.as_str().parse::<u32>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(error::ordinal_error(
+            p,
+            format!("ordinal needs to be between 1 and {}", u32::MAX),
+        )),
+    }
+}
+
+/// Ordinals and names of the `reserved` declarations of a struct.
+fn reserved(p: &Pair<Rule>) -> Result<Reserved> {
+    let mut reserved = Reserved::default();
+
+    for r in get_all(p, Rule::Reserved) {
+        for item in r.into_inner() {
+            match item.as_rule() {
+                Rule::Range => {
+                    let bounds = get_all(&item, Rule::Ordinal);
+                    let start = ordinal_of(&bounds[0])?;
+                    let end = match bounds.get(1) {
+                        Some(end) => ordinal_of(end)?,
+                        None => start,
+                    };
+
+                    if end < start {
+                        return Err(error::ordinal_error(&item, "range ends before it starts"));
+                    }
+
+                    reserved.ranges.push((start, end));
+                }
+                Rule::Identifier => reserved.names.push(item.as_str().into()),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(reserved)
+}
+
 fn primitive(resolver: &mut Resolver, lang: &mut LangGenerator, ident: &str, tt: Trait) {
     resolver.add_type(
         ident.into(),
@@ -40,14 +192,26 @@ fn primitive(resolver: &mut Resolver, lang: &mut LangGenerator, ident: &str, tt:
     );
 }
 
+fn template(resolver: &mut Resolver, ident: &str, params: Vec<ParamKind>, indirect: bool) {
+    resolver.add_template(TemplateDef::new(ident, params, indirect));
+}
+
 pub struct Generator<'g> {
     resolver: Resolver,
     loader: Loader,
     lang: &'g mut LangGenerator,
+    /// Files of the loaded modules, by their namespaces.
+    loaded: HashMap<String, PathBuf>,
+    modules: BTreeMap<String, Defs>,
+    order: Vec<String>,
+    diags: Diagnostics,
+    aborted: bool,
+    /// Paths and contents of the files being generated.
+    files: Vec<(String, String)>,
 }
 
 impl<'g> Generator<'g> {
-    pub fn new(lang: &'g mut LangGenerator) -> Self {
+    pub fn new(lang: &'g mut LangGenerator, opts: &Options) -> Self {
         let mut resolver = Resolver::new();
 
         primitive(&mut resolver, lang, "bool", Trait::Bool);
@@ -63,87 +227,350 @@ impl<'g> Generator<'g> {
         primitive(&mut resolver, lang, "f64", Trait::Float);
         primitive(&mut resolver, lang, "string", Trait::String);
 
+        template(&mut resolver, "vector", vec![ParamKind::Type], true);
+        template(&mut resolver, "map", vec![ParamKind::Key, ParamKind::Type], true);
+        template(&mut resolver, "set", vec![ParamKind::Key], true);
+        template(&mut resolver, "optional", vec![ParamKind::Type], true);
+        template(&mut resolver, "array", vec![ParamKind::Type, ParamKind::Size], false);
+
+        for def in lang.templates() {
+            resolver.add_template(def);
+        }
+
         Self {
             resolver,
-            loader: Loader::new(),
+            loader: Loader::new(&opts.include),
             lang,
+            loaded: HashMap::new(),
+            modules: BTreeMap::new(),
+            order: Vec::new(),
+            diags: Diagnostics::new(opts.max_errors),
+            aborted: false,
+            files: Vec::new(),
         }
     }
 
-    pub fn generate(&mut self, path: &str) -> Result<Defs> {
+    pub fn generate(&mut self, path: &str) -> Result<Model> {
         debug!("Generating from {}", path);
 
+        let ns = utils::namespace(path)?;
         let contents = self.loader.load(path)?;
 
+        // `path` is relative to the working directory, until the loader enters its own.
+        self.loaded.insert(ns.into(), self.loader.canonical(path)?);
         self.loader.enter_dir(path)?;
+        self.files.push((path.into(), contents.clone()));
 
-        let pairs = parse(&contents)?;
-        let model = self.generate_defs(pairs)?;
+        let root = self.generate_file(ns, path, &contents);
 
+        // An error which stops the compile is reported along with the ones found before it.
+        let root = match root {
+            Ok(root) => Some(root),
+            Err(e) => {
+                let _ = self.report(e);
+                None
+            }
+        };
+
+        self.files.pop();
         self.loader.exit_dir();
 
+        let root = match root {
+            Some(root) if self.diags.errors() == 0 => root,
+            _ => return Err(error::diagnostics(self.diags.take())),
+        };
+
+        let mut modules = std::mem::take(&mut self.modules);
+        let mut order = std::mem::take(&mut self.order);
+        modules.insert(ns.into(), root.clone());
+        order.push(ns.into());
+
+        let model = self.lang.generate_model(Model::new(root, modules, order));
+        let model = model.map_err(|e| {
+            self.diags.push(Diagnostic::from_error(&e, path, &contents));
+            error::diagnostics(self.diags.take())
+        })?;
+
+        for warning in self.diags.take() {
+            warn!("{}", warning);
+        }
+
         Ok(model)
     }
 
     fn load_submodule(&mut self, path: &str, ns: &str) -> Result<()> {
+        let file = self.loader.canonical(path)?;
+
+        // Each module is generated once, no matter how many files import it, and a
+        // namespace is a single file.
+        match self.loaded.get(ns) {
+            Some(loaded) if *loaded == file => {
+                debug!("Submodule already loaded: {} ({})", ns, path);
+                return Ok(());
+            }
+            Some(loaded) => return Err(error::module_conflict(ns, loaded)),
+            None => {}
+        }
+        self.loaded.insert(ns.into(), file);
+
         debug!("Loading submodule: {} ({})", ns, path);
 
+        let fullpath = format!("{}/{}", self.loader.current_dir(), path);
         let contents = self.loader.load(path)?;
 
-        self.loader.enter_dir(&path)?;
-        self.resolver.enter_ns(&ns);
+        self.loader.enter_dir(path)?;
+        self.resolver.enter_root_ns(ns);
+        self.files.push((fullpath, contents.clone()));
 
-        let pairs = parse(&contents)?;
-        let _ = self.generate_defs(pairs)?;
+        let defs = self.generate_file(ns, path, &contents);
 
+        self.files.pop();
         self.resolver.exit_ns();
         self.loader.exit_dir();
 
+        self.modules.insert(ns.into(), defs?);
+        self.order.push(ns.into());
+
         Ok(())
     }
 
-    fn generate_defs(&mut self, pairs: Pairs<Rule>) -> Result<Defs> {
+    fn generate_file(&mut self, ns: &str, path: &str, contents: &str) -> Result<Defs> {
+        let mut package = None;
+        let translated;
+        let contents = if path.ends_with(".proto") {
+            match self.translate(contents)? {
+                Some(t) => {
+                    package = Some(t.package);
+                    translated = t.source;
+                    translated.as_str()
+                }
+                None => return Ok(Defs::new(ns, path, Vec::new(), Vec::new())),
+            }
+        } else {
+            contents
+        };
+
+        // A file which can't be parsed is taken as empty, so that other files are still checked.
+        let pairs = match self.recover(parse(contents))? {
+            Some(pairs) => pairs,
+            None => return Ok(Defs::new(ns, path, Vec::new(), Vec::new())),
+        };
+
+        let mut defs = self.generate_defs(ns, path, pairs)?;
+
+        // Proto exports of a proto file keep its package.
+        if let Some(package) = package {
+            defs.custom["proto"] = json!({ "package": package });
+        }
+
+        Ok(defs)
+    }
+
+    /// Translates a proto file into rpc definitions, reporting what can't be translated.
+    fn translate(&mut self, contents: &str) -> Result<Option<proto::Translation>> {
+        let loader = &self.loader;
+        let translated = proto::translate(contents, |path| loader.load(path).ok());
+
+        let mut translated = match self.recover(translated)? {
+            Some(translated) => translated,
+            None => return Ok(None),
+        };
+
+        for e in std::mem::take(&mut translated.errors) {
+            self.report(e)?;
+        }
+
+        // The translation keeps the lines of the proto file, so that the errors found in it
+        // are reported on them.
+        if let Some(file) = self.files.last_mut() {
+            file.1 = translated.source.clone();
+        }
+
+        Ok(Some(translated))
+    }
+
+    /// Reports an error, and lets the caller go on unless too many errors are reported.
+    ///
+    /// Warnings are reported the same way, and never stop the compile.
+    fn report(&mut self, e: Error) -> Result<()> {
+        if self.aborted {
+            return Err(e);
+        }
+
+        let diag = match self.files.last() {
+            Some((path, source)) => Diagnostic::from_error(&e, path, source),
+            None => Diagnostic::from_error(&e, "", ""),
+        };
+
+        if self.diags.push(diag) {
+            Ok(())
+        } else {
+            self.aborted = true;
+            Err(error::too_many_errors(self.diags.max_errors()))
+        }
+    }
+
+    fn recover<T>(&mut self, result: Result<T>) -> Result<Option<T>> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(e) => {
+                self.report(e)?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn generate_defs(&mut self, ns: &str, path: &str, pairs: Pairs<Rule>) -> Result<Defs> {
+        let (uses, nodes) = self.generate_items(pairs)?;
+
+        self.lang.generate_defs(Defs::new(ns, path, uses, nodes))
+    }
+
+    fn generate_items<'a, I>(&mut self, pairs: I) -> Result<(Vec<Use>, Vec<Node>)>
+    where
+        I: IntoIterator<Item = Pair<'a, Rule>>,
+    {
         let mut uses = Vec::new();
-        let mut nodes = Vec::new();
+        let mut items = Vec::new();
 
         let mut checker = DupChecker::new("type name");
 
         for p in pairs {
             match p.as_rule() {
                 Rule::Use => {
-                    uses.push(self.generate_use(p)?);
+                    let value = self.generate_use(p.clone());
+
+                    if let Some(value) = self.recover(value)? {
+                        if uses.iter().any(|u: &Use| u.namespace == value.namespace) {
+                            self.report(error::redundant_use(&p, &value.namespace))?;
+                        } else {
+                            uses.push(value);
+                        }
+                    }
                 }
-                Rule::Struct => {
-                    let (ident, value) = self.generate_struct(p)?;
+                Rule::Struct | Rule::Enum | Rule::Union | Rule::Interface | Rule::Module => {
+                    let checked =
+                        get(&p, Rule::Identifier).and_then(|ident| checker.check(&ident));
 
-                    checker.check(&ident)?;
+                    if self.recover(checked)?.is_some() {
+                        items.push(p);
+                    }
+                }
+                Rule::EOI => {}
+                _ => {
+                    self.report(error::unexpected_token(&p))?;
+                }
+            }
+        }
 
-                    self.resolver.add_type(ident.as_str(), value.clone());
+        // Declare all the types first, so that they can be referred to before their definitions.
+        for p in &items {
+            let declared = self.declare(p);
+            self.recover(declared)?;
+        }
 
-                    nodes.push(Node::Struct(value));
-                }
-                Rule::Enum => {
-                    let (ident, value) = self.generate_enum(p)?;
+        let mut nodes = items.iter().map(|_| None).collect::<Vec<_>>();
 
-                    checker.check(&ident)?;
+        let sorted = order::sort(&self.resolver, &items);
+        let sorted = self.recover(sorted)?
+            .unwrap_or_else(|| (0..items.len()).collect());
 
-                    self.resolver.add_type(ident.as_str(), value.clone());
+        // A type which fails to generate stays declared, so that its users can still be checked.
+        for i in sorted {
+            let node = self.generate_item(items[i].clone());
+            nodes[i] = self.recover(node)?;
+        }
 
-                    nodes.push(Node::Enum(value));
-                }
-                Rule::Interface => {
-                    let (ident, value) = self.generate_interface(p)?;
+        Ok((uses, nodes.into_iter().flatten().collect()))
+    }
 
-                    checker.check(&ident)?;
+    fn declare(&mut self, p: &Pair<Rule>) -> Result<()> {
+        let tt = match p.as_rule() {
+            Rule::Struct => Trait::Struct,
+            Rule::Enum => Trait::Enum,
+            Rule::Union => Trait::Union,
+            Rule::Module => {
+                let ident = get(p, Rule::Identifier)?;
 
-                    nodes.push(Node::Interface(value));
-                }
-                Rule::EOI => {}
-                _ => unreachable!("unexpected token {:?}", p),
+                self.resolver.enter_ns(ident.as_str());
+
+                let declared = p.clone()
+                    .into_inner()
+                    .map(|p| self.declare(&p))
+                    .collect::<Result<Vec<_>>>();
+
+                self.resolver.exit_ns();
+
+                return declared.map(|_| ());
+            }
+            _ => return Ok(()),
+        };
+
+        let ident = get(p, Rule::Identifier)?;
+
+        self.resolver
+            .add_type(ident.as_str(), Ref::new(ident.as_str(), tt));
+
+        Ok(())
+    }
+
+    fn generate_item(&mut self, p: Pair<Rule>) -> Result<Node> {
+        match p.as_rule() {
+            Rule::Struct => {
+                let (ident, value) = self.generate_struct(p)?;
+
+                self.resolver.add_type(ident.as_str(), value.clone());
+
+                Ok(Node::Struct(value))
+            }
+            Rule::Enum => {
+                let (ident, value) = self.generate_enum(p)?;
+
+                self.resolver.add_type(ident.as_str(), value.clone());
+
+                Ok(Node::Enum(value))
             }
+            Rule::Union => {
+                let (ident, value) = self.generate_union(p)?;
+
+                self.resolver.add_type(ident.as_str(), value.clone());
+
+                Ok(Node::Union(value))
+            }
+            Rule::Interface => Ok(Node::Interface(self.generate_interface(p)?.1)),
+            Rule::Module => Ok(Node::Module(self.generate_module(p)?.1)),
+            _ => Err(error::unexpected_token(&p)),
         }
+    }
+
+    fn generate_module<'a>(&mut self, p: Pair<'a, Rule>) -> Result<(Pair<'a, Rule>, Module)> {
+        trace!("Generating module:\n {}", p.as_str());
+
+        let comment = get_comment(&p);
+        let ident = get(&p, Rule::Identifier)?;
 
-        Ok(self.lang.generate_defs(Defs::new(uses, nodes))?)
+        self.resolver.enter_ns(ident.as_str());
+
+        let items = p.clone()
+            .into_inner()
+            .filter(|p| p.as_rule() != Rule::CommentLine && p.as_rule() != Rule::Identifier);
+        let items = self.generate_items(items);
+        let namespace = self.resolver.current_ns();
+
+        self.resolver.exit_ns();
+
+        let (uses, nodes) = items?;
+
+        Ok((
+            ident.clone(),
+            self.lang.generate_module(Module::new(
+                comment,
+                ident.as_str(),
+                &namespace,
+                uses,
+                nodes,
+            ))?,
+        ))
     }
 
     fn generate_use(&mut self, p: Pair<Rule>) -> Result<Use> {
@@ -159,14 +586,22 @@ impl<'g> Generator<'g> {
             .map(|p| p.as_str())
             .collect::<Vec<_>>()
             .join("/");
+
+        // Proto files are imported where there's no rpc file.
+        let proto = format!("{}.proto", path);
         let path = format!("{}.rpc", path);
+        let path = if !self.loader.exists(&path) && self.loader.exists(&proto) {
+            proto
+        } else {
+            path
+        };
         let fullpath = format!("{}/{}", self.loader.current_dir(), path);
 
         self.load_submodule(&path, &ns)
             .chain_err(|| error::error(&path))
             .chain_err(|| error::load_error(&p, &fullpath))?;
 
-        Ok(self.lang.generate_use(Use::new(&ns, &path))?)
+        self.lang.generate_use(Use::new(&ns, &path))
     }
 
     fn generate_struct<'a>(&mut self, p: Pair<'a, Rule>) -> Result<(Pair<'a, Rule>, Struct)> {
@@ -174,36 +609,49 @@ impl<'g> Generator<'g> {
 
         let mut checker = DupChecker::new("struct member name");
 
+        let reserved = reserved(&p)?;
+        let mut ordinals = Ordinals::new(&reserved);
+
         let mut fields = Vec::new();
 
         for f in get_all(&p, Rule::Field) {
-            fields.push(self.generate_field(f, &mut checker)?);
+            let field = self.generate_field(f, &mut checker, &mut ordinals);
+
+            if let Some(field) = self.recover(field)? {
+                fields.push(field);
+            }
         }
 
         let comment = get_comment(&p);
-        let ident = get(&p, Rule::Identifier);
+        let ident = get(&p, Rule::Identifier)?;
 
-        Ok((
-            ident.clone(),
-            self.lang
-                .generate_struct(Struct::new(comment, ident.as_str(), fields))?,
-        ))
+        let mut def = Struct::new(comment, ident.as_str(), fields);
+        def.reserved = reserved;
+
+        Ok((ident.clone(), self.lang.generate_struct(def)?))
     }
 
-    fn generate_field<'a>(&mut self, p: Pair<'a, Rule>, checker: &mut DupChecker) -> Result<Field> {
+    fn generate_field<'a>(
+        &mut self,
+        p: Pair<'a, Rule>,
+        checker: &mut DupChecker,
+        ordinals: &mut Ordinals,
+    ) -> Result<Field> {
         let comment = get_comment(&p);
-        let ident = get(&p, Rule::Identifier);
-        let gty = get(&p, Rule::GenericType);
+        let ident = get(&p, Rule::Identifier)?;
+        let gty = get(&p, Rule::GenericType)?;
         let value = parse_value(&p)?;
 
         checker.check(&ident)?;
 
-        Ok(self.lang.generate_field(Field::new(
-            comment,
-            ident.as_str(),
-            self.resolver.resolve_generic_type(&gty)?,
-            value,
-        ))?)
+        let id = ordinals.assign(&p)?;
+
+        let ty = self.resolver.resolve_generic_type(&gty)?;
+
+        self.check_value(&p, &ty, &value)?;
+
+        self.lang
+            .generate_field(Field::new(comment, ident.as_str(), id, ty, value))
     }
 
     fn generate_enum<'a>(&mut self, p: Pair<'a, Rule>) -> Result<(Pair<'a, Rule>, Enum)> {
@@ -213,44 +661,121 @@ impl<'g> Generator<'g> {
 
         let mut variants = Vec::new();
 
-        let uty = get(&p, Rule::Type);
+        let uty = get(&p, Rule::Type)?;
+        let utype = self.resolver.resolve_type(&uty)?;
+
+        let range = match utype.info() {
+            TypeInfo::Primitive(ref p) if p.tt == Trait::Integer => value::int_range(&p.name),
+            _ => {
+                return Err(error::enum_error(
+                    &uty,
+                    format!("underlying type needs to be an integer: {}", uty.as_str()),
+                ))
+            }
+        };
+
+        let mut discriminants = Discriminants::new(range);
 
         for f in get_all(&p, Rule::Variant) {
-            variants.push(self.generate_variant(f, &uty, &mut checker)?);
+            let variant = self.generate_variant(f, &utype, &mut checker, &mut discriminants);
+
+            if let Some(variant) = self.recover(variant)? {
+                variants.push(variant);
+            }
         }
 
         let comment = get_comment(&p);
-        let ident = get(&p, Rule::Identifier);
+        let ident = get(&p, Rule::Identifier)?;
 
         Ok((
             ident.clone(),
-            self.lang.generate_enum(Enum::new(
-                comment,
-                ident.as_str(),
-                self.resolver.resolve_type(&uty)?,
-                variants,
-            ))?,
+            self.lang
+                .generate_enum(Enum::new(comment, ident.as_str(), utype, variants))?,
         ))
     }
 
     fn generate_variant<'a>(
         &mut self,
         p: Pair<'a, Rule>,
-        uty: &Pair<'a, Rule>,
+        utype: &Type,
         checker: &mut DupChecker,
+        discriminants: &mut Discriminants,
     ) -> Result<Variant> {
         let comment = get_comment(&p);
-        let ident = get(&p, Rule::Identifier);
+        let ident = get(&p, Rule::Identifier)?;
         let value = parse_value(&p)?;
 
         checker.check(&ident)?;
 
-        Ok(self.lang.generate_variant(Variant::new(
+        // Discriminants are integers of the underlying type, which `assign` checks.
+        let value = discriminants.assign(&p, &value)?;
+
+        self.lang.generate_variant(Variant::new(
             comment,
             ident.as_str(),
-            self.resolver.resolve_type(uty)?,
-            value,
-        ))?)
+            utype.clone(),
+            Some(value),
+        ))
+    }
+
+    fn check_value(&self, p: &Pair<Rule>, ty: &Type, value: &Option<Value>) -> Result<()> {
+        match *value {
+            Some(ref value) => match value::check(&self.resolver, ty, value) {
+                Ok(()) => Ok(()),
+                Err(e) => Err(error::mismatched_value(&get(p, Rule::Value)?, e)),
+            },
+            None => Ok(()),
+        }
+    }
+
+    fn generate_union<'a>(&mut self, p: Pair<'a, Rule>) -> Result<(Pair<'a, Rule>, Union)> {
+        trace!("Generating union:\n {}", p.as_str());
+
+        let mut checker = DupChecker::new("union variant name");
+
+        let reserved = Reserved::default();
+        let mut ordinals = Ordinals::new(&reserved);
+
+        let mut variants = Vec::new();
+
+        for f in get_all(&p, Rule::UnionVariant) {
+            let variant = self.generate_union_variant(f, &mut checker, &mut ordinals);
+
+            if let Some(variant) = self.recover(variant)? {
+                variants.push(variant);
+            }
+        }
+
+        let comment = get_comment(&p);
+        let ident = get(&p, Rule::Identifier)?;
+
+        Ok((
+            ident.clone(),
+            self.lang
+                .generate_union(Union::new(comment, ident.as_str(), variants))?,
+        ))
+    }
+
+    fn generate_union_variant<'a>(
+        &mut self,
+        p: Pair<'a, Rule>,
+        checker: &mut DupChecker,
+        ordinals: &mut Ordinals,
+    ) -> Result<UnionVariant> {
+        let comment = get_comment(&p);
+        let ident = get(&p, Rule::Identifier)?;
+
+        checker.check(&ident)?;
+
+        let id = ordinals.assign(&p)?;
+
+        let ty = match get_opt(&p, Rule::GenericType) {
+            Some(gty) => Some(self.resolver.resolve_generic_type(&gty)?),
+            None => None,
+        };
+
+        self.lang
+            .generate_union_variant(UnionVariant::new(comment, ident.as_str(), id, ty))
     }
 
     fn generate_interface<'a>(&mut self, p: Pair<'a, Rule>) -> Result<(Pair<'a, Rule>, Interface)> {
@@ -261,33 +786,39 @@ impl<'g> Generator<'g> {
         let mut checker = DupChecker::new("function name");
 
         for f in get_all(&p, Rule::Function) {
-            funcs.push(self.generate_func(f, &mut checker)?);
+            let func = self.generate_func(f, &mut checker);
+
+            if let Some(func) = self.recover(func)? {
+                funcs.push(func);
+            }
         }
 
         let comment = get_comment(&p);
-        let ident = get(&p, Rule::Identifier);
-        let pattern = get(&p, Rule::Pattern).as_str();
+        let ident = get(&p, Rule::Identifier)?;
+        let pattern = get(&p, Rule::Pattern)?.as_str();
 
         Ok((
             ident.clone(),
             self.lang
-                .generate_interface(Interface::new(comment, ident.as_str(), &pattern, funcs))?,
+                .generate_interface(Interface::new(comment, ident.as_str(), pattern, funcs))?,
         ))
     }
 
     fn generate_arg<'a>(&mut self, p: Pair<'a, Rule>, checker: &mut DupChecker) -> Result<Arg> {
-        let ident = get(&p, Rule::Identifier);
-        let ty = get(&p, Rule::Type);
+        let ident = get(&p, Rule::Identifier)?;
+        let gty = get(&p, Rule::GenericType)?;
 
         checker.check(&ident)?;
 
-        Ok(self.lang
-            .generate_arg(Arg::new(ident.as_str(), self.resolver.resolve_type(&ty)?))?)
+        self.lang.generate_arg(Arg::new(
+            ident.as_str(),
+            self.resolver.resolve_generic_type(&gty)?,
+        ))
     }
 
     fn generate_func<'a>(&mut self, p: Pair<'a, Rule>, checker: &mut DupChecker) -> Result<Func> {
         let comment = get_comment(&p);
-        let ident = get(&p, Rule::Identifier);
+        let ident = get(&p, Rule::Identifier)?;
         let mut args = Vec::new();
 
         checker.check(&ident)?;
@@ -295,15 +826,19 @@ impl<'g> Generator<'g> {
         let mut arg_checker = DupChecker::new("argument name");
 
         for a in get_all(&p, Rule::Argument) {
-            args.push(self.generate_arg(a, &mut arg_checker)?);
+            let arg = self.generate_arg(a, &mut arg_checker);
+
+            if let Some(arg) = self.recover(arg)? {
+                args.push(arg);
+            }
         }
 
         let r = get_opt(&p, Rule::ReturnType);
         let r = if let Some(r) = r {
             let mut rs = Vec::new();
 
-            for ty in get_all(&r, Rule::Type) {
-                rs.push(self.resolver.resolve_type(&ty)?);
+            for gty in get_all(&r, Rule::GenericType) {
+                rs.push(self.resolver.resolve_generic_type(&gty)?);
             }
 
             Some(rs)
@@ -311,11 +846,11 @@ impl<'g> Generator<'g> {
             None
         };
 
-        Ok(self.lang.generate_func(Func::new(
+        self.lang.generate_func(Func::new(
             comment,
             ident.as_str(),
             args,
             r.unwrap_or(Vec::new()),
-        ))?)
+        ))
     }
 }
@@ -0,0 +1,618 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use serde_json::Value;
+
+use super::{qualify, split, uses, LangGenerator};
+use crate::Result;
+use crate::render::Output;
+use crate::types::*;
+
+/// Words which can't be used as identifiers.
+const KEYWORDS: &[&str] = &[
+    "alignas", "alignof", "and", "and_eq", "asm", "auto", "bitand", "bitor", "bool", "break",
+    "case", "catch", "char", "char8_t", "char16_t", "char32_t", "class", "compl", "concept",
+    "const", "consteval", "constexpr", "constinit", "const_cast", "continue", "co_await",
+    "co_return", "co_yield", "decltype", "default", "delete", "do", "double", "dynamic_cast",
+    "else", "enum", "explicit", "export", "extern", "false", "float", "for", "friend", "goto",
+    "if", "inline", "int", "long", "mutable", "namespace", "new", "noexcept", "not", "not_eq",
+    "nullptr", "operator", "or", "or_eq", "private", "protected", "public", "register",
+    "reinterpret_cast", "requires", "return", "short", "signed", "sizeof", "static",
+    "static_assert", "static_cast", "struct", "switch", "template", "this", "thread_local",
+    "throw", "true", "try", "typedef", "typeid", "typename", "union", "unsigned", "using",
+    "virtual", "void", "volatile", "wchar_t", "while", "xor", "xor_eq",
+];
+
+/// Generates C++17 headers, one for each module, and `rrpc.hpp`, the codecs they share.
+///
+/// Every type is annotated with a `cpp` object, e.g. `cpp.type` of a field is its
+/// fully qualified C++ type. Each module gets `cpp.items`, its types in the order
+/// they need to be defined, along with the namespace of each, and `cpp.codecs`, the
+/// ones which get `write_value` and `read_value` of the binary encoding.
+pub struct CppGenerator;
+
+impl LangGenerator for CppGenerator {
+    fn outputs(&self) -> Vec<Output> {
+        vec![
+            Output::new("cpp/module.hpp", "{{namespace_path}}.hpp", true),
+            Output::new("cpp/rrpc.hpp", "rrpc.hpp", false),
+        ]
+    }
+
+    fn embedded_templates(&self) -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("cpp/module.hpp", include_str!("../../templates/cpp/module.hpp")),
+            ("cpp/macros.hpp", include_str!("../../templates/cpp/macros.hpp")),
+            ("cpp/rrpc.hpp", include_str!("../../templates/cpp/rrpc.hpp")),
+        ]
+    }
+
+    fn generate_model(&mut self, mut model: Model) -> Result<Model> {
+        let root = model.root.namespace.clone();
+
+        // Types of the root module are keyed without a namespace, but live in the namespace
+        // named after the module in C++, like the types of the other modules.
+        let mut files = HashMap::new();
+        for (ns, defs) in &model.modules {
+            let scope = if *ns == root { "" } else { ns.as_str() };
+            files.insert(ns.clone(), flatten(scope, ns, &defs.nodes));
+        }
+
+        let mut names = HashMap::new();
+        let mut types = HashMap::new();
+        for items in files.values() {
+            for item in items {
+                names.insert(item.key.clone(), name(&item.namespace, &item.name));
+                types.insert(item.key.clone(), item.ty.clone());
+            }
+        }
+
+        for (ns, defs) in model.modules.iter_mut() {
+            let items = files.remove(ns).unwrap_or_default();
+            let local = items.iter().map(|i| i.key.clone()).collect();
+            let mut annotator = Annotator {
+                names: &names,
+                types: &types,
+                local,
+                complete: HashSet::new(),
+            };
+
+            let mut forward = Vec::new();
+            let mut sorted = Vec::new();
+            let mut codecs = Vec::new();
+
+            for i in sort(&items) {
+                let mut item = items[i].clone();
+
+                if let Some(decl) = annotator.node(&mut item.node) {
+                    forward.push(json!({ "namespace": item.namespace, "decl": decl }));
+                }
+                annotator.complete.insert(item.key.clone());
+
+                let item = json!({
+                    "namespace": item.namespace,
+                    "node": serde_json::to_value(&item.node).unwrap_or(Value::Null),
+                });
+                if item["node"]["trait"] != "interface" {
+                    codecs.push(item.clone());
+                }
+                sorted.push(item);
+            }
+
+            let dirs = split(ns).len().saturating_sub(1);
+            let mut includes = BTreeSet::new();
+            uses(&defs.uses, &defs.nodes, &mut includes);
+            let includes = includes
+                .into_iter()
+                .map(|u| format!("{}{}.hpp", "../".repeat(dirs), u.replace("::", "/")))
+                .collect::<Vec<_>>();
+
+            defs.custom["cpp"] = json!({
+                "guard": format!("RRPC_{}_HPP", ns.replace("::", "_").to_uppercase()),
+                "runtime": format!("{}rrpc.hpp", "../".repeat(dirs)),
+                "includes": includes,
+                "forward": forward,
+                "items": sorted,
+                "codecs": codecs,
+            });
+        }
+
+        if let Some(defs) = model.modules.get(&root) {
+            model.root = defs.clone();
+        }
+
+        Ok(model)
+    }
+}
+
+/// A type or an interface, with where it's defined.
+#[derive(Clone)]
+struct Item {
+    key: String,
+    /// C++ namespace the item is defined in.
+    namespace: String,
+    name: String,
+    ty: Type,
+    node: Node,
+    /// Keys of the types the item holds by value, which need to be defined before it.
+    direct: Vec<String>,
+    /// Keys of the types the item holds through a container or an optional.
+    indirect: Vec<String>,
+}
+
+/// Lists the items of a module and of the modules nested in it, in source order.
+fn flatten(scope: &str, cpp_ns: &str, nodes: &[Node]) -> Vec<Item> {
+    let mut items = Vec::new();
+
+    for node in nodes {
+        let (name, ty, mut deps) = match *node {
+            Node::Struct(ref s) => (
+                &s.name,
+                Type::from(s.clone()),
+                s.members.iter().map(|m| m.ty.clone()).collect::<Vec<_>>(),
+            ),
+            Node::Enum(ref e) => (&e.name, Type::from(e.clone()), vec![]),
+            Node::Union(ref u) => (
+                &u.name,
+                Type::from(u.clone()),
+                u.members.iter().filter_map(|m| m.ty.clone()).collect(),
+            ),
+            Node::Interface(ref i) => (
+                &i.name,
+                Type::from(Ref::new(&i.name, Trait::Interface)),
+                i.funcs
+                    .iter()
+                    .flat_map(|f| f.args.iter().map(|a| a.ty.clone()).chain(f.ret.clone()))
+                    .collect(),
+            ),
+            Node::Module(ref m) => {
+                let nested = qualify(cpp_ns, &ident(&m.name));
+                items.extend(flatten(&m.namespace, &nested, &m.nodes));
+                continue;
+            }
+        };
+
+        let key = qualify(scope, name);
+        let (mut direct, mut indirect) = (Vec::new(), Vec::new());
+        for ty in deps.drain(..) {
+            refs(&ty, &mut direct, &mut indirect);
+        }
+
+        items.push(Item {
+            ty: ty.namespaced(&key),
+            key,
+            namespace: cpp_ns.into(),
+            name: name.clone(),
+            node: node.clone(),
+            direct,
+            indirect,
+        });
+    }
+
+    items
+}
+
+/// Collects the keys of the named types `ty` is made of, held by value or not.
+fn refs(ty: &Type, direct: &mut Vec<String>, indirect: &mut Vec<String>) {
+    match *ty.info() {
+        TypeInfo::Primitive(_) => {}
+        TypeInfo::Template(ref t) if t.name == "array" => {
+            for p in &t.params {
+                refs(p, direct, indirect);
+            }
+        }
+        TypeInfo::Template(ref t) => {
+            for p in &t.params {
+                let mut keys = Vec::new();
+                refs(p, &mut keys, indirect);
+                indirect.append(&mut keys);
+            }
+        }
+        _ => direct.push(ty.key()),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mark {
+    New,
+    Visiting,
+    Done,
+}
+
+/// Orders the items so that every type is defined after the types it holds by value.
+///
+/// The types held through containers or optionals are defined first too, unless they
+/// hold the type being defined, in which case they're forward declared, and optionals
+/// of them become `std::unique_ptr`.
+fn sort(items: &[Item]) -> Vec<usize> {
+    let index = |key: &String| items.iter().position(|item| &item.key == key);
+
+    // Whether defining `i` first would need an item which is being defined.
+    fn blocked(i: usize, items: &[Item], marks: &[Mark], seen: &mut Vec<bool>) -> bool {
+        if marks[i] == Mark::Visiting {
+            return true;
+        }
+        if marks[i] == Mark::Done || seen[i] {
+            return false;
+        }
+        seen[i] = true;
+
+        items[i].direct.iter().any(|dep| {
+            match items.iter().position(|item| &item.key == dep) {
+                Some(j) => blocked(j, items, marks, seen),
+                None => false,
+            }
+        })
+    }
+
+    fn visit<F>(i: usize, items: &[Item], index: &F, marks: &mut Vec<Mark>, order: &mut Vec<usize>)
+    where
+        F: Fn(&String) -> Option<usize>,
+    {
+        if marks[i] != Mark::New {
+            return;
+        }
+        marks[i] = Mark::Visiting;
+
+        for j in items[i].direct.iter().filter_map(index) {
+            visit(j, items, index, marks, order);
+        }
+
+        for j in items[i].indirect.iter().filter_map(index) {
+            if !blocked(j, items, marks, &mut vec![false; items.len()]) {
+                visit(j, items, index, marks, order);
+            }
+        }
+
+        marks[i] = Mark::Done;
+        order.push(i);
+    }
+
+    let mut marks = vec![Mark::New; items.len()];
+    let mut order = Vec::new();
+
+    for i in 0..items.len() {
+        visit(i, items, &index, &mut marks, &mut order);
+    }
+
+    order
+}
+
+struct Annotator<'a> {
+    /// Fully qualified C++ names of the types by their keys.
+    names: &'a HashMap<String, String>,
+    types: &'a HashMap<String, Type>,
+    /// Types defined in the module being annotated.
+    local: HashSet<String>,
+    /// Types of the module already defined.
+    complete: HashSet<String>,
+}
+
+impl<'a> Annotator<'a> {
+    /// Annotates a node, and returns its forward declaration if it has one.
+    fn node(&self, node: &mut Node) -> Option<String> {
+        match *node {
+            Node::Struct(ref mut s) => {
+                s.custom["cpp"] = json!({ "name": ident(&s.name) });
+
+                for f in s.members.iter_mut() {
+                    f.custom["cpp"] = json!({
+                        "name": ident(&f.name),
+                        "type": self.ty(&f.ty),
+                        "default": match f.value {
+                            Some(ref v) => self.value(&f.ty, v),
+                            None => self.default(&f.ty),
+                        },
+                    });
+                }
+
+                Some(format!("struct {}", ident(&s.name)))
+            }
+            Node::Enum(ref mut e) => {
+                let decl = format!("enum class {} : {}", ident(&e.name), self.ty(&e.utype));
+
+                e.custom["cpp"] = json!({
+                    "name": ident(&e.name),
+                    "utype": self.ty(&e.utype),
+                });
+
+                let utype = &e.utype;
+                for v in e.members.iter_mut() {
+                    v.custom["cpp"] = json!({
+                        "name": ident(&v.name),
+                        "value": v.value.as_ref().and_then(|value| self.value(utype, value)),
+                    });
+                }
+
+                Some(decl)
+            }
+            Node::Union(ref mut u) => {
+                let alternatives = u.members
+                    .iter()
+                    .map(|v| match v.ty {
+                        Some(ref ty) => self.ty(ty),
+                        None => ident(&v.name),
+                    })
+                    .collect::<Vec<_>>();
+
+                u.custom["cpp"] = json!({
+                    "name": ident(&u.name),
+                    "variant": format!("std::variant<{}>", alternatives.join(", ")),
+                });
+
+                for v in u.members.iter_mut() {
+                    v.custom["cpp"] = json!({ "name": ident(&v.name) });
+                }
+
+                Some(format!("struct {}", ident(&u.name)))
+            }
+            Node::Interface(ref mut i) => {
+                i.custom["cpp"] = json!({ "name": ident(&i.name) });
+
+                for f in i.funcs.iter_mut() {
+                    for a in f.args.iter_mut() {
+                        a.custom["cpp"] = json!({
+                            "name": ident(&a.name),
+                            "type": self.param(&a.ty),
+                        });
+                    }
+
+                    let ret = f.ret.iter().map(|ty| self.ty(ty)).collect::<Vec<_>>();
+                    let ret = match ret.len() {
+                        0 => "void".into(),
+                        1 => ret[0].clone(),
+                        _ => format!("std::tuple<{}>", ret.join(", ")),
+                    };
+
+                    f.custom["cpp"] = json!({ "name": ident(&f.name), "ret": ret });
+                }
+
+                None
+            }
+            Node::Module(_) => None,
+        }
+    }
+
+    fn incomplete(&self, ty: &Type) -> bool {
+        let key = ty.key();
+        self.local.contains(&key) && !self.complete.contains(&key)
+    }
+
+    /// C++ type of `ty`.
+    fn ty(&self, ty: &Type) -> String {
+        match *ty.info() {
+            TypeInfo::Primitive(ref p) => match p.name.as_str() {
+                "bool" => "bool".into(),
+                "f32" => "float".into(),
+                "f64" => "double".into(),
+                "string" => "std::string".into(),
+                name if name.starts_with('u') => format!("std::uint{}_t", &name[1..]),
+                name if name.starts_with('i') => format!("std::int{}_t", &name[1..]),
+                name => name.into(),
+            },
+            TypeInfo::Template(ref t) => {
+                let params = t.params.iter().map(|p| self.ty(p)).collect::<Vec<_>>();
+
+                match (t.name.as_str(), params.as_slice()) {
+                    ("vector", [p]) => format!("std::vector<{}>", p),
+                    ("set", [k]) => format!("std::set<{}>", k),
+                    ("map", [k, v]) => format!("std::map<{}, {}>", k, v),
+                    // std::optional needs the definition of the type it holds.
+                    ("optional", [p]) if self.incomplete(&t.params[0]) => {
+                        format!("std::unique_ptr<{}>", p)
+                    }
+                    ("optional", [p]) => format!("std::optional<{}>", p),
+                    ("array", [p]) => format!("std::array<{}, {}>", p, t.size.unwrap_or(0)),
+                    (name, _) => format!("{}<{}>", ident(name), params.join(", ")),
+                }
+            }
+            _ => self.names
+                .get(&ty.key())
+                .cloned()
+                .unwrap_or_else(|| name("", &ty.key())),
+        }
+    }
+
+    /// C++ type of an argument: scalars by value, anything else by const reference.
+    fn param(&self, ty: &Type) -> String {
+        match ty.tt() {
+            Trait::Bool | Trait::Integer | Trait::Float | Trait::Enum => self.ty(ty),
+            _ => format!("const {}&", self.ty(ty)),
+        }
+    }
+
+    /// C++ expression of the default of `ty`, or `None` if it's `{}`.
+    ///
+    /// Enums default to their first variant, as they do in the binary encoding, which
+    /// isn't the zero of `{}` when it has another value.
+    fn default(&self, ty: &Type) -> Option<String> {
+        let def = match *ty.info() {
+            TypeInfo::Ref(_) => self.types.get(&ty.key())?,
+            _ => ty,
+        };
+
+        match *def.info() {
+            TypeInfo::Enum(ref e) => {
+                let first = e.members.first()?;
+                Some(format!("{}::{}", self.ty(ty), ident(&first.name)))
+            }
+            _ => None,
+        }
+    }
+
+    /// C++ expression of `value` of `ty`, or `None` if it doesn't fit the type.
+    fn value(&self, ty: &Type, value: &Value) -> Option<String> {
+        match *ty.info() {
+            TypeInfo::Primitive(ref p) => match p.tt {
+                Trait::Bool => value.as_bool().map(|b| b.to_string()),
+                Trait::Integer => integer(value),
+                Trait::Float if p.name == "f32" => value.as_f64().map(|f| format!("{:?}f", f)),
+                Trait::Float => value.as_f64().map(|f| format!("{:?}", f)),
+                Trait::String => value.as_str().map(|s| format!("std::string({})", string(s))),
+                _ => None,
+            },
+            TypeInfo::Struct(ref s) => {
+                let given = |i: usize, m: &Field| match *value {
+                    Value::Object(ref o) => o.get(&m.name),
+                    Value::Array(ref a) => a.get(i),
+                    _ => None,
+                };
+
+                if !value.is_object() && !value.is_array() {
+                    return None;
+                }
+
+                // Members are initialized in order, so the ones not given take their defaults.
+                let mut exprs = Vec::new();
+                for (i, m) in s.members.iter().enumerate() {
+                    let expr = match given(i, m).or(m.value.as_ref()) {
+                        Some(v) => self.value(&m.ty, v)?,
+                        None => "{}".into(),
+                    };
+                    exprs.push(expr);
+                }
+
+                Some(format!("{}{{{}}}", self.ty(ty), exprs.join(", ")))
+            }
+            TypeInfo::Enum(ref e) => {
+                let name = value.as_str()?;
+                e.members.iter().find(|m| m.name == name)?;
+
+                Some(format!("{}::{}", self.ty(ty), ident(name)))
+            }
+            TypeInfo::Union(ref u) => {
+                let (name, payload) = match *value {
+                    Value::String(ref name) => (name, None),
+                    Value::Object(ref o) if o.len() == 1 => {
+                        let (name, payload) = o.iter().next()?;
+                        (name, Some(payload))
+                    }
+                    _ => return None,
+                };
+
+                let index = u.members.iter().position(|m| &m.name == name)?;
+                let payload = match (&u.members[index].ty, payload) {
+                    (Some(ty), Some(payload)) => format!(", {}", self.value(ty, payload)?),
+                    (None, None) => String::new(),
+                    _ => return None,
+                };
+                let name = self.ty(ty);
+
+                Some(format!(
+                    "{}{{decltype({}::value)(std::in_place_index<{}>{})}}",
+                    name, name, index, payload
+                ))
+            }
+            TypeInfo::Template(ref t) => self.template(ty, t, value),
+            TypeInfo::Ref(_) => {
+                let def = self.types.get(&ty.key())?;
+
+                if def.is_ref() {
+                    return None;
+                }
+
+                self.value(def, value)
+            }
+        }
+    }
+
+    fn template(&self, ty: &Type, t: &Template, value: &Value) -> Option<String> {
+        match (t.name.as_str(), t.params.as_slice()) {
+            ("vector", [p]) | ("set", [p]) | ("array", [p]) => {
+                let mut items = Vec::new();
+                for v in value.as_array()? {
+                    items.push(self.value(p, v)?);
+                }
+
+                Some(format!("{}{{{}}}", self.ty(ty), items.join(", ")))
+            }
+            ("map", [k, v]) => {
+                let mut items = Vec::new();
+                for (key, value) in value.as_object()? {
+                    let key = match k.tt() {
+                        Trait::Integer => serde_json::from_str(key).ok()?,
+                        Trait::Bool => Value::Bool(key.parse().ok()?),
+                        _ => Value::String(key.clone()),
+                    };
+
+                    items.push(format!(
+                        "{{{}, {}}}",
+                        self.value(k, &key)?,
+                        self.value(v, value)?
+                    ));
+                }
+
+                Some(format!("{}{{{}}}", self.ty(ty), items.join(", ")))
+            }
+            ("optional", [p]) => {
+                let boxed = self.incomplete(p);
+
+                match (value.is_null(), boxed) {
+                    (true, true) => Some("nullptr".into()),
+                    (true, false) => Some("std::nullopt".into()),
+                    (false, true) => Some(format!(
+                        "std::make_unique<{}>({})",
+                        self.ty(p),
+                        self.value(p, value)?
+                    )),
+                    (false, false) => Some(format!("{}({})", self.ty(ty), self.value(p, value)?)),
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Fully qualified C++ name of `name` in the namespace `ns`.
+fn name(ns: &str, name: &str) -> String {
+    let mut path = split(ns)
+        .into_iter()
+        .chain(split(name))
+        .map(ident)
+        .collect::<Vec<_>>()
+        .join("::");
+    path.insert_str(0, "::");
+    path
+}
+
+/// Appends an underscore to C++ keywords, leaving other names as they are.
+fn ident(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.into()
+    }
+}
+
+/// Integer literal of a value, which can't be spelled as is if it's beyond `long long`.
+fn integer(value: &Value) -> Option<String> {
+    if let Some(n) = value.as_i64() {
+        if n == i64::MIN {
+            return Some(format!("({} - 1)", n + 1));
+        }
+        return Some(n.to_string());
+    }
+
+    value.as_u64().map(|n| format!("{}u", n))
+}
+
+/// String literal of a string.
+fn string(s: &str) -> String {
+    let mut out = String::from("\"");
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            // Octal escapes take at most three digits, unlike hex ones, which would
+            // swallow the digits following them.
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\{:03o}", c as u32)),
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
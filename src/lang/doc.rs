@@ -0,0 +1,217 @@
+use serde_json::Value;
+
+use super::{locate, qualify, split, LangGenerator};
+use crate::Result;
+use crate::render::Output;
+use crate::types::*;
+
+/// Formats of the documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocFormat {
+    Html,
+    Markdown,
+}
+
+impl DocFormat {
+    /// Pages of the documentation: one for each module, and an index.
+    pub fn outputs(self) -> Vec<Output> {
+        match self {
+            DocFormat::Html => vec![
+                Output::new("doc/module.html", "{{namespace_path}}.html", true),
+                Output::new("doc/index.html", "index.html", false),
+            ],
+            DocFormat::Markdown => vec![
+                Output::new("doc/module.md", "{{namespace_path}}.md", true),
+                Output::new("doc/index.md", "index.md", false),
+            ],
+        }
+    }
+}
+
+/// Generates documentation of the definitions from their comments.
+///
+/// Every type, interface and function has an anchor on the page of its module, its path
+/// in the module with dots, e.g. `geo.Point` or `store.get`, in `doc.anchor`. Types of
+/// fields, arguments and return values are in `doc.type` as a list of parts, each a
+/// `text` or a link to a definition with its `text`, `page` and `anchor`, where `page`
+/// is the path of the page without extension. Links are relative to the directory of
+/// the module's page, which is `doc.up` from the output directory.
+pub struct DocGenerator;
+
+impl LangGenerator for DocGenerator {
+    fn outputs(&self) -> Vec<Output> {
+        DocFormat::Html.outputs()
+    }
+
+    fn embedded_templates(&self) -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("doc/module.html", include_str!("../../templates/doc/module.html")),
+            ("doc/index.html", include_str!("../../templates/doc/index.html")),
+            ("doc/macros.html", include_str!("../../templates/doc/macros.html")),
+            ("doc/module.md", include_str!("../../templates/doc/module.md")),
+            ("doc/index.md", include_str!("../../templates/doc/index.md")),
+            ("doc/macros.md", include_str!("../../templates/doc/macros.md")),
+        ]
+    }
+
+    fn generate_model(&mut self, mut model: Model) -> Result<Model> {
+        let root = model.root.namespace.clone();
+        let files = model.modules.keys().cloned().collect::<Vec<_>>();
+        let files = files.iter().map(|ns| ns.as_str()).collect::<Vec<_>>();
+
+        for (ns, defs) in model.modules.iter_mut() {
+            let annotator = Annotator {
+                root: &root,
+                files: &files,
+                file: ns,
+            };
+
+            let scope = if *ns == root { "" } else { ns.as_str() };
+            for node in defs.nodes.iter_mut() {
+                annotator.node(scope, node);
+            }
+
+            for u in defs.uses.iter_mut() {
+                u.custom["doc"] = json!({ "page": page(&u.namespace) });
+            }
+
+            let depth = split(ns).len();
+            defs.custom["doc"] = json!({
+                "page": page(ns),
+                "up": "../".repeat(depth.saturating_sub(1)),
+            });
+        }
+
+        if let Some(defs) = model.modules.get(&root) {
+            model.root = defs.clone();
+        }
+
+        Ok(model)
+    }
+}
+
+/// Path of the page of a module, without extension.
+fn page(ns: &str) -> String {
+    split(ns).join("/")
+}
+
+struct Annotator<'a> {
+    root: &'a str,
+    /// Modules with a page of their own, which types link to.
+    files: &'a [&'a str],
+    file: &'a str,
+}
+
+impl<'a> Annotator<'a> {
+    /// Annotates a node of the module `scope`.
+    fn node(&self, scope: &str, node: &mut Node) {
+        match *node {
+            Node::Struct(ref mut s) => {
+                s.custom["doc"] = json!({ "anchor": self.anchor(scope, &s.name) });
+
+                for f in s.members.iter_mut() {
+                    f.custom["doc"] = json!({
+                        "type": self.ty(&f.ty),
+                        "default": f.value.as_ref().map(Value::to_string),
+                    });
+                }
+            }
+            Node::Enum(ref mut e) => {
+                e.custom["doc"] = json!({
+                    "anchor": self.anchor(scope, &e.name),
+                    "type": self.ty(&e.utype),
+                });
+            }
+            Node::Union(ref mut u) => {
+                u.custom["doc"] = json!({ "anchor": self.anchor(scope, &u.name) });
+
+                for v in u.members.iter_mut() {
+                    v.custom["doc"] = json!({ "type": v.ty.as_ref().map(|ty| self.ty(ty)) });
+                }
+            }
+            Node::Interface(ref mut i) => {
+                let anchor = self.anchor(scope, &i.name);
+
+                for f in i.funcs.iter_mut() {
+                    for a in f.args.iter_mut() {
+                        a.custom["doc"] = json!({ "type": self.ty(&a.ty) });
+                    }
+
+                    let ret = f.ret.iter().map(|ty| self.ty(ty)).collect::<Vec<_>>();
+                    f.custom["doc"] = json!({
+                        "anchor": format!("{}.{}", anchor, f.name),
+                        "ret": ret,
+                    });
+                }
+
+                i.custom["doc"] = json!({ "anchor": anchor });
+            }
+            Node::Module(ref mut m) => {
+                m.custom["doc"] = json!({ "anchor": self.anchor(scope, &m.name) });
+
+                for node in m.nodes.iter_mut() {
+                    self.node(&m.namespace, node);
+                }
+            }
+        }
+    }
+
+    /// Anchor of the definition `name` of the module `scope`.
+    fn anchor(&self, scope: &str, name: &str) -> String {
+        let key = qualify(scope, name);
+        let (_, path) = locate(self.files, self.root, &key);
+        split(path).join(".")
+    }
+
+    /// Parts of `ty`, with links to the definitions of the types it's made of.
+    fn ty(&self, ty: &Type) -> Vec<Value> {
+        let mut parts = Vec::new();
+        self.parts(ty, &mut parts);
+        parts
+    }
+
+    fn parts(&self, ty: &Type, parts: &mut Vec<Value>) {
+        match *ty.info() {
+            TypeInfo::Primitive(ref p) => text(parts, &p.name),
+            TypeInfo::Template(ref t) => {
+                text(parts, &format!("{}<", t.name));
+                for (i, p) in t.params.iter().enumerate() {
+                    if i > 0 {
+                        text(parts, ", ");
+                    }
+                    self.parts(p, parts);
+                }
+                if let Some(size) = t.size {
+                    text(parts, &format!(", {}", size));
+                }
+                text(parts, ">");
+            }
+            _ => {
+                let key = ty.key();
+                let (file, path) = locate(self.files, self.root, &key);
+
+                // Types of the module are named as they're written in it.
+                let name = if file == self.file { path.to_string() } else { key.clone() };
+
+                parts.push(json!({
+                    "text": name,
+                    "page": page(file),
+                    "anchor": split(path).join("."),
+                }));
+            }
+        }
+    }
+}
+
+/// Adds text to the parts of a type, joining it to the text before it.
+fn text(parts: &mut Vec<Value>, s: &str) {
+    if let Some(last) = parts.last_mut() {
+        if last.get("page").is_none() {
+            let joined = format!("{}{}", last["text"].as_str().unwrap_or(""), s);
+            last["text"] = json!(joined);
+            return;
+        }
+    }
+
+    parts.push(json!({ "text": s }));
+}
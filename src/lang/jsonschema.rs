@@ -0,0 +1,373 @@
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use super::{locate, qualify, split, LangGenerator};
+use crate::Result;
+use crate::render::Output;
+use crate::types::*;
+use crate::value::int_range;
+
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Exports the types as JSON Schema (draft 2020-12) documents, one for each module.
+///
+/// Schemas describe the JSON the other generators serialize to. Each type is a
+/// definition in `$defs` of the document of its module, and types of other modules
+/// are referred to by the relative path of their document. The document of a module
+/// is in `jsonschema.document`.
+pub struct JsonSchemaGenerator;
+
+impl LangGenerator for JsonSchemaGenerator {
+    fn outputs(&self) -> Vec<Output> {
+        vec![Output::new("jsonschema/schema.json", "{{namespace_path}}.schema.json", true)]
+    }
+
+    fn embedded_templates(&self) -> Vec<(&'static str, &'static str)> {
+        vec![("jsonschema/schema.json", include_str!("../../templates/jsonschema/schema.json"))]
+    }
+
+    fn generate_model(&mut self, mut model: Model) -> Result<Model> {
+        let root = model.root.namespace.clone();
+        let files = model.modules.keys().cloned().collect::<Vec<_>>();
+        let files = files.iter().map(|ns| ns.as_str()).collect::<Vec<_>>();
+        let types = types(&model);
+
+        for (ns, defs) in model.modules.iter_mut() {
+            let schemas = Schemas {
+                root: &root,
+                files: &files,
+                file: ns,
+                types: &types,
+                refs: Refs::Documents,
+            };
+
+            let scope = if *ns == root { "" } else { ns.as_str() };
+            let mut definitions = Map::new();
+            schemas.defs(scope, &defs.nodes, &mut definitions);
+
+            defs.custom["jsonschema"] = json!({
+                "document": {
+                    "$schema": DIALECT,
+                    "$id": document(ns),
+                    "title": ns,
+                    "$defs": definitions,
+                }
+            });
+        }
+
+        if let Some(defs) = model.modules.get(&root) {
+            model.root = defs.clone();
+        }
+
+        Ok(model)
+    }
+}
+
+/// Collects the definitions of the types of every module by their keys.
+pub(super) fn types(model: &Model) -> HashMap<String, Type> {
+    fn collect(scope: &str, nodes: &[Node], types: &mut HashMap<String, Type>) {
+        for node in nodes {
+            let (name, ty) = match *node {
+                Node::Struct(ref s) => (&s.name, Type::from(s.clone())),
+                Node::Enum(ref e) => (&e.name, Type::from(e.clone())),
+                Node::Union(ref u) => (&u.name, Type::from(u.clone())),
+                Node::Module(ref m) => {
+                    collect(&m.namespace, &m.nodes, types);
+                    continue;
+                }
+                Node::Interface(_) => continue,
+            };
+
+            let key = qualify(scope, name);
+            types.insert(key.clone(), ty.namespaced(&key));
+        }
+    }
+
+    let mut types = HashMap::new();
+    for (ns, defs) in &model.modules {
+        let scope = if *ns == model.root.namespace { "" } else { ns.as_str() };
+        collect(scope, &defs.nodes, &mut types);
+    }
+    types
+}
+
+/// Path of the document of a module, relative to the output directory.
+fn document(ns: &str) -> String {
+    format!("{}.schema.json", split(ns).join("/"))
+}
+
+/// Where the schemas of the types are.
+pub(super) enum Refs {
+    /// In `$defs` of a document for each module.
+    Documents,
+    /// In `components/schemas` of an OpenAPI document, named by their dotted keys.
+    Components,
+}
+
+/// Builds the schemas of the types as seen from the module `file`.
+pub(super) struct Schemas<'a> {
+    pub root: &'a str,
+    /// Modules with a schema document of their own, which references point into.
+    pub files: &'a [&'a str],
+    pub file: &'a str,
+    pub types: &'a HashMap<String, Type>,
+    pub refs: Refs,
+}
+
+impl<'a> Schemas<'a> {
+    /// Adds the schemas of the types defined by the nodes of the module `scope`,
+    /// by their names in the document.
+    pub fn defs(&self, scope: &str, nodes: &[Node], out: &mut Map<String, Value>) {
+        for node in nodes {
+            let (name, schema) = match *node {
+                Node::Struct(ref s) => (&s.name, self.structure(s)),
+                Node::Enum(ref e) => (&e.name, self.enumeration(e)),
+                Node::Union(ref u) => (&u.name, self.union(u)),
+                Node::Module(ref m) => {
+                    self.defs(&m.namespace, &m.nodes, out);
+                    continue;
+                }
+                Node::Interface(_) => continue,
+            };
+
+            out.insert(self.name(&qualify(scope, name)), schema);
+        }
+    }
+
+    /// Splits the type `key` into the module it's in and its name in the document of the module.
+    fn locate(&self, key: &str) -> (String, String) {
+        let (file, name) = locate(self.files, self.root, key);
+        (file.into(), split(name).join("."))
+    }
+
+    /// Name of the schema of the type `key` in the document it's in.
+    pub fn name(&self, key: &str) -> String {
+        match self.refs {
+            Refs::Documents => self.locate(key).1,
+            Refs::Components => split(key).join("."),
+        }
+    }
+
+    /// Reference to the definition of the type `key`.
+    fn reference(&self, key: &str) -> Value {
+        if let Refs::Components = self.refs {
+            return json!({ "$ref": format!("#/components/schemas/{}", self.name(key)) });
+        }
+
+        let (file, name) = self.locate(key);
+
+        let path = if file == self.file {
+            String::new()
+        } else {
+            let up = split(self.file).len().saturating_sub(1);
+            format!("{}{}", "../".repeat(up), document(&file))
+        };
+
+        json!({ "$ref": format!("{}#/$defs/{}", path, name) })
+    }
+
+    fn structure(&self, s: &Struct) -> Value {
+        // Every member is serialized, with a `null` for an empty optional.
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+
+        for f in &s.members {
+            let mut schema = self.ty(&f.ty);
+            describe(&mut schema, &f.comment);
+
+            if let Some(default) = f.value.as_ref().and_then(|v| self.wire(&f.ty, v)) {
+                schema["default"] = default;
+            }
+
+            properties.insert(f.name.clone(), schema);
+            required.push(f.name.clone());
+        }
+
+        let mut schema = json!({
+            "type": "object",
+            "properties": properties,
+        });
+        if !required.is_empty() {
+            schema["required"] = json!(required);
+        }
+        describe(&mut schema, &s.comment);
+        schema
+    }
+
+    fn enumeration(&self, e: &Enum) -> Value {
+        let mut schema = if e.members.iter().any(|v| v.comment.is_some()) {
+            let variants = e.members
+                .iter()
+                .map(|v| {
+                    let mut schema = json!({ "const": v.name });
+                    describe(&mut schema, &v.comment);
+                    schema
+                })
+                .collect::<Vec<_>>();
+
+            json!({ "type": "string", "oneOf": variants })
+        } else {
+            let names = e.members.iter().map(|v| v.name.clone()).collect::<Vec<_>>();
+
+            json!({ "type": "string", "enum": names })
+        };
+
+        describe(&mut schema, &e.comment);
+        schema
+    }
+
+    fn union(&self, u: &Union) -> Value {
+        let variants = u.members
+            .iter()
+            .map(|v| {
+                let mut schema = match v.ty {
+                    Some(ref ty) => json!({
+                        "type": "object",
+                        "properties": { v.name.clone(): self.ty(ty) },
+                        "required": [v.name],
+                        "additionalProperties": false,
+                    }),
+                    None => json!({ "const": v.name }),
+                };
+                describe(&mut schema, &v.comment);
+                schema
+            })
+            .collect::<Vec<_>>();
+
+        let mut schema = if variants.is_empty() {
+            json!({ "not": {} })
+        } else {
+            json!({ "oneOf": variants })
+        };
+
+        describe(&mut schema, &u.comment);
+        schema
+    }
+
+    /// Schema of `ty`.
+    pub fn ty(&self, ty: &Type) -> Value {
+        match *ty.info() {
+            TypeInfo::Primitive(ref p) => match p.tt {
+                Trait::Bool => json!({ "type": "boolean" }),
+                Trait::Integer => match int_range(&p.name) {
+                    Some((min, max)) => json!({
+                        "type": "integer",
+                        "minimum": integer(min),
+                        "maximum": integer(max),
+                    }),
+                    None => json!({ "type": "integer" }),
+                },
+                Trait::Float => json!({ "type": "number" }),
+                _ => json!({ "type": "string" }),
+            },
+            TypeInfo::Template(ref t) => match (t.name.as_str(), t.params.as_slice()) {
+                ("vector", [p]) => json!({ "type": "array", "items": self.ty(p) }),
+                ("set", [p]) => json!({
+                    "type": "array",
+                    "items": self.ty(p),
+                    "uniqueItems": true,
+                }),
+                ("array", [p]) => json!({
+                    "type": "array",
+                    "items": self.ty(p),
+                    "minItems": t.size,
+                    "maxItems": t.size,
+                }),
+                // Keys of objects are strings, so the keys are written as such.
+                ("map", [k, v]) => {
+                    let names = match k.tt() {
+                        Trait::Integer => json!({ "pattern": "^-?[0-9]+$" }),
+                        Trait::Bool => json!({ "enum": ["true", "false"] }),
+                        Trait::Enum => self.ty(k),
+                        _ => json!({}),
+                    };
+
+                    json!({
+                        "type": "object",
+                        "propertyNames": names,
+                        "additionalProperties": self.ty(v),
+                    })
+                }
+                ("optional", [p]) => json!({ "anyOf": [self.ty(p), { "type": "null" }] }),
+                _ => json!({}),
+            },
+            _ => self.reference(&ty.key()),
+        }
+    }
+
+    /// The JSON a value of `ty` given in the definitions is serialized to.
+    ///
+    /// Structs can be given as arrays of their members, which are serialized as objects.
+    pub fn wire(&self, ty: &Type, value: &Value) -> Option<Value> {
+        match *ty.info() {
+            TypeInfo::Struct(ref s) => match *value {
+                Value::Object(ref o) => {
+                    let mut out = Map::new();
+                    for m in &s.members {
+                        if let Some(v) = o.get(&m.name) {
+                            out.insert(m.name.clone(), self.wire(&m.ty, v)?);
+                        }
+                    }
+                    Some(Value::Object(out))
+                }
+                Value::Array(ref a) => {
+                    let mut out = Map::new();
+                    for (m, v) in s.members.iter().zip(a) {
+                        out.insert(m.name.clone(), self.wire(&m.ty, v)?);
+                    }
+                    Some(Value::Object(out))
+                }
+                _ => None,
+            },
+            TypeInfo::Union(ref u) => match *value {
+                Value::Object(ref o) if o.len() == 1 => {
+                    let (name, payload) = o.iter().next()?;
+                    let member = u.members.iter().find(|m| &m.name == name)?;
+                    let payload = self.wire(member.ty.as_ref()?, payload)?;
+
+                    Some(json!({ name.clone(): payload }))
+                }
+                _ => Some(value.clone()),
+            },
+            TypeInfo::Template(ref t) => match (t.name.as_str(), t.params.as_slice()) {
+                ("vector", [p]) | ("set", [p]) | ("array", [p]) => {
+                    let mut items = Vec::new();
+                    for v in value.as_array()? {
+                        items.push(self.wire(p, v)?);
+                    }
+                    Some(Value::Array(items))
+                }
+                ("map", [_, v]) => {
+                    let mut out = Map::new();
+                    for (key, value) in value.as_object()? {
+                        out.insert(key.clone(), self.wire(v, value)?);
+                    }
+                    Some(Value::Object(out))
+                }
+                ("optional", [p]) if !value.is_null() => self.wire(p, value),
+                _ => Some(value.clone()),
+            },
+            TypeInfo::Ref(_) => match self.types.get(&ty.key()) {
+                Some(def) if !def.is_ref() => self.wire(def, value),
+                _ => None,
+            },
+            _ => Some(value.clone()),
+        }
+    }
+}
+
+/// Adds the comment as the description of a schema.
+pub(super) fn describe(schema: &mut Value, comment: &Option<String>) {
+    if let Some(ref comment) = *comment {
+        schema["description"] = json!(comment.trim());
+    }
+}
+
+fn integer(n: i128) -> Value {
+    if n < 0 {
+        json!(n as i64)
+    } else {
+        json!(n as u64)
+    }
+}
@@ -4,8 +4,26 @@ use std::collections::HashMap;
 use crate::Result;
 use crate::types::*;
 use crate::error;
+use crate::render::Output;
 
 pub trait LangGenerator {
+    /// Files rendered unless the caller asks for others.
+    fn outputs(&self) -> Vec<Output> {
+        vec![Output::new("root.cpp", "{{namespace}}.cpp", false)]
+    }
+
+    /// Templates bundled with the generator, as pairs of name and source.
+    ///
+    /// Templates in the template directory with the same names take precedence.
+    fn embedded_templates(&self) -> Vec<(&'static str, &'static str)> {
+        Vec::new()
+    }
+
+    /// Generic types available in addition to the built-in ones.
+    fn templates(&mut self) -> Vec<TemplateDef> {
+        Vec::new()
+    }
+
     fn generate_primitive(&mut self, value: Primitive) -> Primitive {
         value
     }
@@ -26,6 +44,14 @@ pub trait LangGenerator {
         Ok(value)
     }
 
+    fn generate_union(&mut self, value: Union) -> Result<Union> {
+        Ok(value)
+    }
+
+    fn generate_union_variant(&mut self, value: UnionVariant) -> Result<UnionVariant> {
+        Ok(value)
+    }
+
     fn generate_arg(&mut self, value: Arg) -> Result<Arg> {
         Ok(value)
     }
@@ -42,14 +68,41 @@ pub trait LangGenerator {
         Ok(value)
     }
 
+    fn generate_module(&mut self, value: Module) -> Result<Module> {
+        Ok(value)
+    }
+
     fn generate_defs(&mut self, value: Defs) -> Result<Defs> {
         Ok(value)
     }
+
+    /// Called last, with every module of the compile.
+    fn generate_model(&mut self, value: Model) -> Result<Model> {
+        Ok(value)
+    }
 }
 
 mod null;
+mod rust;
+mod cpp;
+mod typescript;
+mod python;
+mod jsonschema;
+mod openapi;
+mod proto;
+mod doc;
+mod vectors;
 
 pub use self::null::NullGenerator;
+pub use self::rust::RustGenerator;
+pub use self::cpp::CppGenerator;
+pub use self::typescript::TypeScriptGenerator;
+pub use self::python::PythonGenerator;
+pub use self::jsonschema::JsonSchemaGenerator;
+pub use self::openapi::OpenApiGenerator;
+pub use self::proto::ProtoGenerator;
+pub use self::doc::{DocFormat, DocGenerator};
+pub use self::vectors::VectorsGenerator;
 
 type Gen = Arc<Mutex<LangGenerator + Send + Sync>>;
 type GenTable = HashMap<String, Gen>;
@@ -59,6 +112,15 @@ lazy_static! {
         let mut map = GenTable::new();
 
         map.insert("null".into(), Arc::new(Mutex::new(NullGenerator)));
+        map.insert("rust".into(), Arc::new(Mutex::new(RustGenerator)));
+        map.insert("cpp".into(), Arc::new(Mutex::new(CppGenerator)));
+        map.insert("typescript".into(), Arc::new(Mutex::new(TypeScriptGenerator)));
+        map.insert("python".into(), Arc::new(Mutex::new(PythonGenerator)));
+        map.insert("jsonschema".into(), Arc::new(Mutex::new(JsonSchemaGenerator)));
+        map.insert("openapi".into(), Arc::new(Mutex::new(OpenApiGenerator)));
+        map.insert("proto".into(), Arc::new(Mutex::new(ProtoGenerator)));
+        map.insert("doc".into(), Arc::new(Mutex::new(DocGenerator)));
+        map.insert("vectors".into(), Arc::new(Mutex::new(VectorsGenerator)));
 
         Arc::new(Mutex::new(map))
     };
@@ -71,6 +133,59 @@ pub fn register_generator<T: LangGenerator + Send + Sync + 'static>(key: &str, g
         .insert(key.into(), Arc::new(Mutex::new(gen)));
 }
 
+pub fn generator_names() -> Vec<String> {
+    let mut names = LANG_GENERATORS
+        .lock()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    names.sort();
+    names
+}
+
+/// Segments of a namespace, none for the root.
+fn split(ns: &str) -> Vec<&str> {
+    ns.split("::").filter(|s| !s.is_empty()).collect()
+}
+
+/// Path of `name` in the namespace `scope`.
+pub(crate) fn qualify(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.into()
+    } else {
+        format!("{}::{}", scope, name)
+    }
+}
+
+/// Collects the namespaces of the modules imported by a module and its inline modules.
+pub(crate) fn uses<E: Extend<String>>(list: &[Use], nodes: &[Node], out: &mut E) {
+    out.extend(list.iter().map(|u| u.namespace.clone()));
+
+    for node in nodes {
+        if let Node::Module(ref m) = *node {
+            uses(&m.uses, &m.nodes, out);
+        }
+    }
+}
+
+/// Splits the type `key` into the namespace of the module it's in and its path in it.
+///
+/// Types of the root module are keyed without a namespace, so `files` are the
+/// namespaces of the other modules.
+pub(crate) fn locate<'a>(files: &[&'a str], root: &'a str, key: &'a str) -> (&'a str, &'a str) {
+    let file = files
+        .iter()
+        .filter(|ns| **ns != root && key.starts_with(&format!("{}::", ns)))
+        .max_by_key(|ns| ns.len());
+
+    match file {
+        Some(ns) => (ns, &key[ns.len() + 2..]),
+        None => (root, key),
+    }
+}
+
 pub fn get_generator(key: &str) -> Result<Gen> {
     LANG_GENERATORS
         .lock()
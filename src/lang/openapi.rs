@@ -0,0 +1,183 @@
+use serde_json::{Map, Value};
+
+use super::jsonschema::{describe, types, Refs, Schemas};
+use super::{qualify, split, LangGenerator};
+use crate::Result;
+use crate::render::Output;
+use crate::types::*;
+
+const VERSION: &str = "3.1.0";
+
+/// Exports the interfaces as an OpenAPI 3.1 document.
+///
+/// Each function is a `POST` operation on the pattern of its interface followed by the
+/// function name, e.g. `/store/get` for `fn get` of `interface store(store)`, behind the
+/// path of the module if it's not the root one, e.g. `/geo/store/get`. Arguments are the
+/// properties of a JSON object in the request body, and the return value is the
+/// response, an array for several values.
+///
+/// The types of every module are schemas in `components`, named by their paths with
+/// dots, e.g. `geo.Point`. The document is in `openapi.document` of the root module.
+pub struct OpenApiGenerator;
+
+impl LangGenerator for OpenApiGenerator {
+    fn outputs(&self) -> Vec<Output> {
+        vec![Output::new("openapi/openapi.json", "openapi.json", false)]
+    }
+
+    fn embedded_templates(&self) -> Vec<(&'static str, &'static str)> {
+        vec![("openapi/openapi.json", include_str!("../../templates/openapi/openapi.json"))]
+    }
+
+    fn generate_model(&mut self, mut model: Model) -> Result<Model> {
+        let root = model.root.namespace.clone();
+        let files = model.modules.keys().cloned().collect::<Vec<_>>();
+        let files = files.iter().map(|ns| ns.as_str()).collect::<Vec<_>>();
+        let types = types(&model);
+
+        let mut schemas = Map::new();
+        let mut paths = Map::new();
+        let mut tags = Vec::new();
+
+        for (ns, defs) in &model.modules {
+            let operations = Operations {
+                schemas: Schemas {
+                    root: &root,
+                    files: &files,
+                    file: ns,
+                    types: &types,
+                    refs: Refs::Components,
+                },
+            };
+
+            let scope = if *ns == root { "" } else { ns.as_str() };
+            operations.schemas.defs(scope, &defs.nodes, &mut schemas);
+            operations.nodes(scope, &defs.nodes, &mut paths, &mut tags);
+        }
+
+        let mut document = json!({
+            "openapi": VERSION,
+            "info": { "title": root, "version": "0.0.0" },
+            "paths": paths,
+            "components": { "schemas": schemas },
+        });
+        if !tags.is_empty() {
+            document["tags"] = json!(tags);
+        }
+
+        if let Some(defs) = model.modules.get_mut(&root) {
+            defs.custom["openapi"] = json!({ "document": document });
+            model.root = defs.clone();
+        }
+
+        Ok(model)
+    }
+}
+
+/// Builds the operations of the interfaces of a module.
+struct Operations<'a> {
+    schemas: Schemas<'a>,
+}
+
+impl<'a> Operations<'a> {
+    /// Adds the operations of the interfaces in the nodes of the module `scope`.
+    fn nodes(
+        &self,
+        scope: &str,
+        nodes: &[Node],
+        paths: &mut Map<String, Value>,
+        tags: &mut Vec<Value>,
+    ) {
+        for node in nodes {
+            match *node {
+                Node::Interface(ref i) => self.interface(scope, i, paths, tags),
+                Node::Module(ref m) => self.nodes(&m.namespace, &m.nodes, paths, tags),
+                _ => {}
+            }
+        }
+    }
+
+    fn interface(
+        &self,
+        scope: &str,
+        i: &Interface,
+        paths: &mut Map<String, Value>,
+        tags: &mut Vec<Value>,
+    ) {
+        // Interfaces are tagged by their names, qualified like the schemas.
+        let tag = self.schemas.name(&qualify(scope, &i.name));
+
+        let mut t = json!({ "name": tag });
+        describe(&mut t, &i.comment);
+        tags.push(t);
+
+        for f in &i.funcs {
+            let mut path = split(scope);
+            path.push(&i.pattern);
+            path.push(&f.name);
+
+            let mut operation = json!({
+                "operationId": format!("{}.{}", tag, f.name),
+                "tags": [tag],
+                "responses": self.responses(f),
+            });
+            describe(&mut operation, &f.comment);
+            if let Some(body) = self.request(f) {
+                operation["requestBody"] = body;
+            }
+
+            paths.insert(format!("/{}", path.join("/")), json!({ "post": operation }));
+        }
+    }
+
+    /// Request body of a function, which has none without arguments.
+    fn request(&self, f: &Func) -> Option<Value> {
+        if f.args.is_empty() {
+            return None;
+        }
+
+        let mut properties = Map::new();
+        for a in &f.args {
+            properties.insert(a.name.clone(), self.schemas.ty(&a.ty));
+        }
+        let required = f.args.iter().map(|a| a.name.clone()).collect::<Vec<_>>();
+
+        Some(json!({
+            "required": true,
+            "content": {
+                "application/json": {
+                    "schema": {
+                        "type": "object",
+                        "properties": properties,
+                        "required": required,
+                        "additionalProperties": false,
+                    }
+                }
+            }
+        }))
+    }
+
+    fn responses(&self, f: &Func) -> Value {
+        let schema = match f.ret.as_slice() {
+            [] => return json!({ "204": { "description": "No content" } }),
+            [ty] => self.schemas.ty(ty),
+            ret => {
+                let items = ret.iter().map(|ty| self.schemas.ty(ty)).collect::<Vec<_>>();
+
+                json!({
+                    "type": "array",
+                    "prefixItems": items,
+                    "minItems": ret.len(),
+                    "maxItems": ret.len(),
+                })
+            }
+        };
+
+        json!({
+            "200": {
+                "description": "Success",
+                "content": { "application/json": { "schema": schema } },
+            }
+        })
+    }
+}
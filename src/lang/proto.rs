@@ -0,0 +1,563 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::{locate, qualify, split, uses, LangGenerator};
+use crate::Result;
+use crate::error;
+use crate::render::Output;
+use crate::types::*;
+use crate::value::as_int;
+
+/// Exports the definitions as proto3 files, one for each module.
+///
+/// Structs become messages, unions messages with a `oneof`, inline modules messages
+/// holding their types, and interfaces services. An optional union held by a struct is
+/// a `oneof` of its message instead when the ordinals and names of its variants are free
+/// in it, as they are in the unions translated from `oneof`s, and the union has no
+/// message if it isn't used otherwise. An rpc takes and returns the struct of a function
+/// with a single argument or return value of one, `google.protobuf.Empty` for none, and
+/// a message synthesized for the function otherwise. Types protobuf can't nest, e.g. a
+/// vector of vectors or an optional in a `oneof`, are held by synthesized messages named
+/// after the type.
+///
+/// Fields are numbered by their ordinals, and reserved ordinals and names are kept.
+/// Files translated from proto files keep their packages.
+pub struct ProtoGenerator;
+
+impl LangGenerator for ProtoGenerator {
+    fn outputs(&self) -> Vec<Output> {
+        vec![Output::new("proto/module.proto", "{{namespace_path}}.proto", true)]
+    }
+
+    fn embedded_templates(&self) -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("proto/module.proto", include_str!("../../templates/proto/module.proto")),
+            ("proto/macros.proto", include_str!("../../templates/proto/macros.proto")),
+        ]
+    }
+
+    fn generate_model(&mut self, mut model: Model) -> Result<Model> {
+        let root = model.root.namespace.clone();
+        let files = model.modules.keys().cloned().collect::<Vec<_>>();
+        let files = files.iter().map(|ns| ns.as_str()).collect::<Vec<_>>();
+        let types = super::jsonschema::types(&model);
+        let inlined = inlined(&model, &types);
+
+        let packages = model.modules
+            .iter()
+            .map(|(ns, defs)| {
+                let package = match defs.custom["proto"]["package"] {
+                    serde_json::Value::String(ref package) => package.clone(),
+                    _ => package(ns),
+                };
+                (ns.clone(), package)
+            })
+            .collect::<HashMap<_, _>>();
+
+        for (ns, defs) in model.modules.iter_mut() {
+            let mut annotator = Annotator {
+                root: &root,
+                files: &files,
+                file: ns,
+                packages: &packages,
+                types: &types,
+                inlined: &inlined,
+                wrappers: Vec::new(),
+                messages: Vec::new(),
+                services: Vec::new(),
+                empty: false,
+            };
+
+            for node in defs.nodes.iter_mut() {
+                annotator.node("", node)?;
+            }
+
+            let mut imports = BTreeSet::new();
+            uses(&defs.uses, &defs.nodes, &mut imports);
+            let mut imports = imports
+                .into_iter()
+                .filter(|u| u != ns)
+                .map(|u| format!("{}.proto", split(&u).join("/")))
+                .collect::<Vec<_>>();
+            if annotator.empty {
+                imports.insert(0, "google/protobuf/empty.proto".into());
+            }
+
+            let mut messages = annotator.messages;
+            messages.extend(annotator.wrappers.into_iter().map(|(_, m)| m));
+
+            defs.custom["proto"] = json!({
+                "package": packages[ns.as_str()],
+                "imports": imports,
+                "services": annotator.services,
+                "messages": messages,
+            });
+        }
+
+        if let Some(defs) = model.modules.get(&root) {
+            model.root = defs.clone();
+        }
+
+        Ok(model)
+    }
+}
+
+fn package(ns: &str) -> String {
+    split(ns).join(".")
+}
+
+/// Union held by the field `f` of `s` which is a `oneof` of its message, with its key.
+///
+/// Variants of a `oneof` are fields of the message, so their ordinals and names need to
+/// be free in it, including in the unions of the other fields.
+fn oneof<'t>(
+    types: &'t HashMap<String, Type>,
+    s: &Struct,
+    f: &Field,
+) -> Option<(String, &'t Union)> {
+    let union = |ty: &Type| match *ty.info() {
+        TypeInfo::Template(ref t) if t.name == "optional" => {
+            let key = t.params.first()?.key();
+            match types.get(&key).map(Type::info) {
+                Some(TypeInfo::Union(u)) => Some((key, u)),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+
+    let (key, u) = union(&f.ty)?;
+
+    let free = |id: u32, name: &str| {
+        name != f.name
+            && !s.reserved.has_id(id)
+            && !s.reserved.has_name(name)
+            && s.members.iter().filter(|g| g.name != f.name).all(|g| {
+                let variants = union(&g.ty).map_or(&[][..], |(_, u)| &u.members[..]);
+                g.id != id
+                    && g.name != name
+                    && variants.iter().all(|w| w.id != id && w.name != name)
+            })
+    };
+
+    if u.members.iter().all(|v| free(v.id, &v.name)) {
+        Some((key, u))
+    } else {
+        None
+    }
+}
+
+/// Keys of the unions which are only held by `oneof`s, which need no messages.
+fn inlined(model: &Model, types: &HashMap<String, Type>) -> HashSet<String> {
+    fn walk(
+        types: &HashMap<String, Type>,
+        nodes: &[Node],
+        oneofs: &mut HashSet<String>,
+        held: &mut HashSet<String>,
+    ) {
+        for node in nodes {
+            match *node {
+                Node::Struct(ref s) => for f in &s.members {
+                    match oneof(types, s, f) {
+                        Some((key, _)) => {
+                            oneofs.insert(key);
+                        }
+                        None => unions(&f.ty, held),
+                    }
+                },
+                Node::Union(ref u) => for ty in u.members.iter().filter_map(|v| v.ty.as_ref()) {
+                    unions(ty, held);
+                },
+                Node::Interface(ref i) => for f in &i.funcs {
+                    for ty in f.args.iter().map(|a| &a.ty).chain(&f.ret) {
+                        unions(ty, held);
+                    }
+                },
+                Node::Module(ref m) => walk(types, &m.nodes, oneofs, held),
+                Node::Enum(_) => {}
+            }
+        }
+    }
+
+    /// Adds the keys of the unions `ty` refers to.
+    fn unions(ty: &Type, out: &mut HashSet<String>) {
+        match *ty.info() {
+            TypeInfo::Template(ref t) => for p in &t.params {
+                unions(p, out);
+            },
+            _ if ty.tt() == Trait::Union => {
+                out.insert(ty.key());
+            }
+            _ => {}
+        }
+    }
+
+    let mut oneofs = HashSet::new();
+    let mut held = HashSet::new();
+    for defs in model.modules.values() {
+        walk(types, &defs.nodes, &mut oneofs, &mut held);
+    }
+
+    oneofs.difference(&held).cloned().collect()
+}
+
+struct Annotator<'a> {
+    root: &'a str,
+    /// Modules exported to proto files of their own.
+    files: &'a [&'a str],
+    file: &'a str,
+    /// Packages of the files, by their namespaces.
+    packages: &'a HashMap<String, String>,
+    types: &'a HashMap<String, Type>,
+    /// Unions which are only held by `oneof`s.
+    inlined: &'a HashSet<String>,
+    /// Messages holding types which can't be nested, by their names.
+    wrappers: Vec<(String, serde_json::Value)>,
+    /// Requests and responses synthesized for the functions of the services.
+    messages: Vec<serde_json::Value>,
+    services: Vec<serde_json::Value>,
+    /// Whether `google.protobuf.Empty` is used.
+    empty: bool,
+}
+
+/// Where a field is declared, which limits the labels it can have.
+#[derive(Clone, Copy, PartialEq)]
+enum Place {
+    Message,
+    OneOf,
+    MapValue,
+}
+
+impl<'a> Annotator<'a> {
+    /// Annotates a node of the inline module `scope`, "" at the top of the file.
+    fn node(&mut self, scope: &str, node: &mut Node) -> Result<()> {
+        match *node {
+            Node::Struct(ref mut s) => {
+                s.custom["proto"] = json!({
+                    "name": s.name,
+                    "reserved": reserved(&s.reserved),
+                });
+
+                let types = self.types;
+                let fields = s.members
+                    .iter()
+                    .map(|f| match oneof(types, s, f) {
+                        Some((_, u)) => json!({ "oneof": self.variants(u) }),
+                        None => json!({
+                            "decl": self.field(&f.ty, &f.name, f.id as usize, Place::Message),
+                        }),
+                    })
+                    .collect::<Vec<_>>();
+
+                for (f, proto) in s.members.iter_mut().zip(fields) {
+                    f.custom["proto"] = proto;
+                }
+            }
+            Node::Enum(ref mut e) => {
+                let prefix = upper_snake(&e.name);
+                let mut values = Vec::new();
+
+                for v in &e.members {
+                    let n = v.value.as_ref().and_then(as_int).unwrap_or(0);
+
+                    if n < i128::from(i32::MIN) || n > i128::from(i32::MAX) {
+                        return Err(error::output_error(format!(
+                            "{}::{} = {} doesn't fit in a protobuf enum, which is an int32",
+                            e.name, v.name, n
+                        )));
+                    }
+
+                    values.push(json!({
+                        "comment": v.comment,
+                        "name": format!("{}_{}", prefix, upper_snake(&v.name)),
+                        "number": n as i64,
+                    }));
+                }
+
+                // The first value is the default in proto3, which needs to be zero.
+                match values.iter().position(|v| v["number"] == 0) {
+                    Some(i) => {
+                        let zero = values.remove(i);
+                        values.insert(0, zero);
+                    }
+                    None => values.insert(
+                        0,
+                        json!({ "name": format!("{}_UNSPECIFIED", prefix), "number": 0 }),
+                    ),
+                }
+
+                e.custom["proto"] = json!({ "name": e.name, "values": values });
+            }
+            Node::Union(ref mut u) => {
+                let file = if self.file == self.root { "" } else { self.file };
+                let key = qualify(file, &qualify(scope, &u.name));
+
+                if self.inlined.contains(&key) {
+                    u.custom["proto"] = json!({ "inlined": true });
+                } else {
+                    let fields = self.variants(u);
+                    u.custom["proto"] = json!({ "name": u.name, "fields": fields });
+                }
+            }
+            Node::Interface(ref mut i) => {
+                // Services can't be nested, so the ones of inline modules are prefixed with them.
+                let service = split(scope)
+                    .into_iter()
+                    .chain(Some(i.name.as_str()))
+                    .collect::<Vec<_>>()
+                    .join("_");
+
+                let mut rpcs = Vec::new();
+                for f in &i.funcs {
+                    let stem = format!("{}{}", pascal(&service), pascal(&f.name));
+
+                    let args = f.args.iter().map(|a| (a.name.clone(), &a.ty)).collect::<Vec<_>>();
+                    let ret = f.ret
+                        .iter()
+                        .enumerate()
+                        .map(|(n, ty)| match f.ret.len() {
+                            1 => ("value".to_string(), ty),
+                            _ => (format!("value_{}", n), ty),
+                        })
+                        .collect::<Vec<_>>();
+
+                    let request = self.message(format!("{}Request", stem), &args);
+                    let response = self.message(format!("{}Response", stem), &ret);
+
+                    rpcs.push(json!({
+                        "comment": f.comment,
+                        "name": f.name,
+                        "request": request,
+                        "response": response,
+                    }));
+                }
+
+                self.services.push(json!({
+                    "comment": i.comment,
+                    "name": service,
+                    "rpcs": rpcs,
+                }));
+            }
+            Node::Module(ref mut m) => {
+                m.custom["proto"] = json!({ "name": m.name });
+
+                let scope = super::qualify(scope, &m.name);
+                for node in m.nodes.iter_mut() {
+                    self.node(&scope, node)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fields of the `oneof` of the variants of `u`.
+    fn variants(&mut self, u: &Union) -> Vec<serde_json::Value> {
+        let mut fields = Vec::new();
+
+        for v in &u.members {
+            let decl = match v.ty {
+                Some(ref ty) => self.field(ty, &v.name, v.id as usize, Place::OneOf),
+                None => {
+                    self.empty = true;
+                    format!(".google.protobuf.Empty {} = {}", v.name, v.id)
+                }
+            };
+
+            fields.push(json!({ "comment": v.comment, "decl": decl }));
+        }
+
+        fields
+    }
+
+    /// Type of the request or response of an rpc with the `fields`, which is the struct
+    /// of a single one, `google.protobuf.Empty` for none, or else the message `name`.
+    fn message(&mut self, name: String, fields: &[(String, &Type)]) -> String {
+        match *fields {
+            [] => {
+                self.empty = true;
+                ".google.protobuf.Empty".into()
+            }
+            [(_, ty)] if ty.tt() == Trait::Struct => self.path(&ty.key()),
+            _ => {
+                let fields = fields
+                    .iter()
+                    .enumerate()
+                    .map(|(n, (field, ty))| self.field(ty, field, n + 1, Place::Message))
+                    .collect::<Vec<_>>();
+
+                let path = self.full(self.file, &name);
+                self.messages.push(json!({ "name": name, "fields": fields }));
+                path
+            }
+        }
+    }
+
+    /// Declaration of a field of `ty`, without the `;`.
+    fn field(&mut self, ty: &Type, name: &str, number: usize, place: Place) -> String {
+        let (label, ty) = self.label(ty, place);
+
+        format!("{}{} {} = {}", label, ty, name, number)
+    }
+
+    /// Label and type of a field of `ty`.
+    fn label(&mut self, ty: &Type, place: Place) -> (&'static str, String) {
+        let t = match *ty.info() {
+            TypeInfo::Template(_) if bytes(ty) => return ("", "bytes".into()),
+            TypeInfo::Template(ref t) => t,
+            _ => return ("", self.scalar(ty)),
+        };
+
+        match (t.name.as_str(), t.params.as_slice(), place) {
+            ("vector", [p], Place::Message)
+            | ("set", [p], Place::Message)
+            | ("array", [p], Place::Message) => ("repeated ", self.singular(p)),
+            ("map", [k, v], Place::Message) => {
+                // Enums can't be keys, so they're keyed by their names, as in JSON.
+                let key = match k.tt() {
+                    Trait::Enum => "string".into(),
+                    _ => self.scalar(k),
+                };
+                let (_, value) = self.label(v, Place::MapValue);
+
+                ("", format!("map<{}, {}>", key, value))
+            }
+            ("optional", [p], Place::Message) => ("optional ", self.singular(p)),
+            _ => ("", self.wrapper(ty)),
+        }
+    }
+
+    /// Type of a field of `ty` without a label, wrapping it if needed.
+    fn singular(&mut self, ty: &Type) -> String {
+        match *ty.info() {
+            TypeInfo::Template(_) if bytes(ty) => "bytes".into(),
+            TypeInfo::Template(_) => self.wrapper(ty),
+            _ => self.scalar(ty),
+        }
+    }
+
+    /// Type of a primitive or a named type.
+    fn scalar(&self, ty: &Type) -> String {
+        match *ty.info() {
+            TypeInfo::Primitive(ref p) => match p.name.as_str() {
+                "u8" | "u16" | "u32" => "uint32".into(),
+                "i8" | "i16" | "i32" => "int32".into(),
+                "u64" => "uint64".into(),
+                "i64" => "int64".into(),
+                "f32" => "float".into(),
+                "f64" => "double".into(),
+                name => name.into(),
+            },
+            _ => self.path(&ty.key()),
+        }
+    }
+
+    /// Fully qualified name of the type `key`.
+    fn path(&self, key: &str) -> String {
+        let (file, name) = locate(self.files, self.root, key);
+
+        self.full(file, &split(name).join("."))
+    }
+
+    /// Fully qualified name of the message or enum `name` of the file of the module `file`.
+    fn full(&self, file: &str, name: &str) -> String {
+        match self.packages[file].as_str() {
+            "" => format!(".{}", name),
+            package => format!(".{}.{}", package, name),
+        }
+    }
+
+    /// Name of the message holding a value of `ty` in its field `value`, which is added
+    /// to the messages of the file the first time.
+    fn wrapper(&mut self, ty: &Type) -> String {
+        let name = wrapper_name(ty);
+
+        if self.wrappers.iter().all(|(n, _)| *n != name) {
+            let field = self.field(ty, "value", 1, Place::Message);
+            self.wrappers.push((name.clone(), json!({ "name": name, "fields": [field] })));
+        }
+
+        self.full(self.file, &name)
+    }
+}
+
+/// Whether `ty` is `vector<u8>`, which is `bytes` in protobuf.
+fn bytes(ty: &Type) -> bool {
+    match *ty.info() {
+        TypeInfo::Template(ref t) if t.name == "vector" => match t.params.as_slice() {
+            [p] => matches!(*p.info(), TypeInfo::Primitive(ref p) if p.name == "u8"),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Name of the message holding a value of `ty`, e.g. `VectorVectorU32`.
+fn wrapper_name(ty: &Type) -> String {
+    match *ty.info() {
+        TypeInfo::Template(ref t) => {
+            let mut name = pascal(&t.name);
+            for p in &t.params {
+                name.push_str(&wrapper_name(p));
+            }
+            if let Some(size) = t.size {
+                name.push_str(&size.to_string());
+            }
+            name
+        }
+        _ => split(&ty.key()).into_iter().map(pascal).collect(),
+    }
+}
+
+fn pascal(name: &str) -> String {
+    name.split('_')
+        .map(|s| {
+            let mut chars = s.chars();
+            match chars.next() {
+                Some(c) => c.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Converts `CamelCase` or `snake_case` to `UPPER_SNAKE_CASE`.
+fn upper_snake(name: &str) -> String {
+    let mut out = String::new();
+    let mut prev: Option<char> = None;
+
+    for c in name.chars() {
+        if c.is_uppercase() && matches!(prev, Some(p) if p.is_lowercase() || p.is_ascii_digit()) {
+            out.push('_');
+        }
+        out.extend(c.to_uppercase());
+        prev = Some(c);
+    }
+
+    out
+}
+
+/// Declarations of the reserved ordinals and names of a struct.
+fn reserved(reserved: &Reserved) -> Vec<String> {
+    let mut decls = Vec::new();
+
+    if !reserved.ranges.is_empty() {
+        let ranges = reserved.ranges
+            .iter()
+            .map(|&(start, end)| {
+                if start == end {
+                    start.to_string()
+                } else {
+                    format!("{} to {}", start, end)
+                }
+            })
+            .collect::<Vec<_>>();
+        decls.push(format!("reserved {}", ranges.join(", ")));
+    }
+
+    if !reserved.names.is_empty() {
+        let names = reserved.names.iter().map(|n| format!("{:?}", n)).collect::<Vec<_>>();
+        decls.push(format!("reserved {}", names.join(", ")));
+    }
+
+    decls
+}
@@ -0,0 +1,452 @@
+use std::collections::{BTreeSet, HashMap};
+
+use serde_json::Value;
+
+use super::{locate, qualify, split, uses, LangGenerator};
+use crate::Result;
+use crate::render::Output;
+use crate::types::*;
+
+/// Words which can't be used as identifiers.
+///
+/// `self` isn't a keyword, but it's the first argument of methods and `__init__`.
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
+    "continue", "def", "del", "elif", "else", "except", "finally", "for", "from", "global",
+    "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return",
+    "self", "try", "while", "with", "yield",
+];
+
+/// Generates Python 3.7+ modules with dataclasses, one for each module.
+///
+/// Inline modules become classes holding their types. Every type is annotated with
+/// a `python` object, e.g. `python.type` of a field is its annotation and
+/// `python.default` the expression of its default. Classes have the codec of the
+/// binary encoding in `__rrpc__`, of which `python.codec` is the expression.
+pub struct PythonGenerator;
+
+impl LangGenerator for PythonGenerator {
+    fn outputs(&self) -> Vec<Output> {
+        vec![
+            Output::new("python/module.py", "{{namespace_path}}.py", true),
+            Output::new("python/rrpc.py", "rrpc.py", false),
+        ]
+    }
+
+    fn embedded_templates(&self) -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("python/module.py", include_str!("../../templates/python/module.py")),
+            ("python/macros.py", include_str!("../../templates/python/macros.py")),
+            ("python/rrpc.py", include_str!("../../templates/python/rrpc.py")),
+        ]
+    }
+
+    fn generate_model(&mut self, mut model: Model) -> Result<Model> {
+        let root = model.root.namespace.clone();
+        let files = model.modules.keys().cloned().collect::<Vec<_>>();
+        let files = files.iter().map(|ns| ns.as_str()).collect::<Vec<_>>();
+
+        let mut types = HashMap::new();
+        for (ns, defs) in &model.modules {
+            let scope = if *ns == root { "" } else { ns.as_str() };
+            collect(scope, &defs.nodes, &mut types);
+        }
+
+        for (ns, defs) in model.modules.iter_mut() {
+            let annotator = Annotator {
+                root: &root,
+                files: &files,
+                file: ns,
+                types: &types,
+            };
+
+            let mut namespaces = BTreeSet::new();
+            uses(&defs.uses, &defs.nodes, &mut namespaces);
+            let imports = namespaces
+                .iter()
+                .filter(|u| *u != ns)
+                .map(|u| json!({ "alias": alias(u), "module": split(u).join(".") }))
+                .collect::<Vec<_>>();
+
+            let mut clients = false;
+            for node in defs.nodes.iter_mut() {
+                clients |= annotator.node(node);
+            }
+
+            defs.custom["python"] = json!({
+                "imports": imports,
+                "clients": clients,
+            });
+        }
+
+        if let Some(defs) = model.modules.get(&root) {
+            model.root = defs.clone();
+        }
+
+        Ok(model)
+    }
+}
+
+/// Collects the definitions of types by their keys, to look up `Ref`s.
+fn collect(scope: &str, nodes: &[Node], types: &mut HashMap<String, Type>) {
+    for node in nodes {
+        let (name, ty) = match *node {
+            Node::Struct(ref s) => (&s.name, Type::from(s.clone())),
+            Node::Enum(ref e) => (&e.name, Type::from(e.clone())),
+            Node::Union(ref u) => (&u.name, Type::from(u.clone())),
+            Node::Module(ref m) => {
+                collect(&m.namespace, &m.nodes, types);
+                continue;
+            }
+            Node::Interface(_) => continue,
+        };
+
+        let key = qualify(scope, name);
+        types.insert(key.clone(), ty.namespaced(&key));
+    }
+}
+
+struct Annotator<'a> {
+    root: &'a str,
+    /// Modules generated as Python modules of their own, which types are imported from.
+    files: &'a [&'a str],
+    /// Namespace of the module being annotated.
+    file: &'a str,
+    types: &'a HashMap<String, Type>,
+}
+
+impl<'a> Annotator<'a> {
+    /// Annotates a node, and returns whether it has a client class.
+    fn node(&self, node: &mut Node) -> bool {
+        match *node {
+            Node::Struct(ref mut s) => {
+                s.custom["python"] = json!({ "name": ident(&s.name) });
+
+                for f in s.members.iter_mut() {
+                    let default = f.value
+                        .as_ref()
+                        .and_then(|v| self.value(&f.ty, v))
+                        .unwrap_or_else(|| self.default(&f.ty));
+
+                    f.custom["python"] = json!({
+                        "name": ident(&f.name),
+                        "type": self.ty(&f.ty),
+                        "default": initializer(default),
+                        "codec": self.codec(&f.ty),
+                    });
+                }
+
+                false
+            }
+            Node::Enum(ref mut e) => {
+                e.custom["python"] = json!({
+                    "name": ident(&e.name),
+                    "codec": self.codec(&e.utype),
+                });
+
+                for v in e.members.iter_mut() {
+                    v.custom["python"] = json!({ "name": ident(&v.name) });
+                }
+
+                false
+            }
+            Node::Union(ref mut u) => {
+                u.custom["python"] = json!({ "name": ident(&u.name) });
+
+                for v in u.members.iter_mut() {
+                    v.custom["python"] = json!({
+                        "class": variant(&ident(&u.name), &v.name),
+                        "type": v.ty.as_ref().map(|ty| self.ty(ty)),
+                        "default": v.ty.as_ref().map(|ty| initializer(self.default(ty))),
+                        "codec": v.ty.as_ref().map_or("None".into(), |ty| self.codec(ty)),
+                    });
+                }
+
+                false
+            }
+            Node::Interface(ref mut i) => {
+                i.custom["python"] = json!({ "client": ident(&format!("{}Client", i.name)) });
+
+                for f in i.funcs.iter_mut() {
+                    for a in f.args.iter_mut() {
+                        a.custom["python"] = json!({
+                            "name": ident(&a.name),
+                            "type": self.ty(&a.ty),
+                        });
+                    }
+
+                    let ret = f.ret.iter().map(|ty| self.ty(ty)).collect::<Vec<_>>();
+                    let ret = match ret.len() {
+                        0 => "None".into(),
+                        1 => ret[0].clone(),
+                        _ => format!("Tuple[{}]", ret.join(", ")),
+                    };
+
+                    f.custom["python"] = json!({
+                        "name": ident(&f.name),
+                        "ret": ret,
+                    });
+                }
+
+                true
+            }
+            Node::Module(ref mut m) => {
+                m.custom["python"] = json!({ "name": ident(&m.name) });
+
+                let mut clients = false;
+                for node in m.nodes.iter_mut() {
+                    clients |= self.node(node);
+                }
+
+                clients
+            }
+        }
+    }
+
+    /// Annotation of `ty`.
+    fn ty(&self, ty: &Type) -> String {
+        match *ty.info() {
+            TypeInfo::Primitive(ref p) => match p.tt {
+                Trait::Bool => "bool".into(),
+                Trait::Integer => "int".into(),
+                Trait::Float => "float".into(),
+                _ => "str".into(),
+            },
+            TypeInfo::Template(ref t) => {
+                let params = t.params.iter().map(|p| self.ty(p)).collect::<Vec<_>>();
+
+                match (t.name.as_str(), params.as_slice()) {
+                    ("vector", [p]) | ("array", [p]) => format!("List[{}]", p),
+                    ("set", [k]) => format!("Set[{}]", k),
+                    ("map", [k, v]) => format!("Dict[{}, {}]", k, v),
+                    ("optional", [p]) => format!("Optional[{}]", p),
+                    (name, _) => format!("{}[{}]", ident(name), params.join(", ")),
+                }
+            }
+            _ => self.path(&ty.key()),
+        }
+    }
+
+    /// Expression of the codec of `ty`.
+    ///
+    /// Codecs of generated classes are looked up when they're used, as the classes may be
+    /// defined later.
+    fn codec(&self, ty: &Type) -> String {
+        match *ty.info() {
+            TypeInfo::Primitive(ref p) => format!("rrpc.{}", p.name.to_uppercase()),
+            TypeInfo::Template(ref t) => {
+                let params = t.params.iter().map(|p| self.codec(p)).collect::<Vec<_>>();
+
+                match (t.name.as_str(), params.as_slice()) {
+                    ("vector", [p]) => format!("rrpc.Vector({})", p),
+                    ("array", [p]) => format!("rrpc.Array({}, {})", p, t.size.unwrap_or(0)),
+                    ("set", [k]) => format!("rrpc.Set({})", k),
+                    ("map", [k, v]) => format!("rrpc.Map({}, {})", k, v),
+                    ("optional", [p]) => format!("rrpc.Optional_({})", p),
+                    (name, _) => format!("rrpc.{}({})", ident(name), params.join(", ")),
+                }
+            }
+            _ => format!("rrpc.Ref(lambda: {})", self.path(&ty.key())),
+        }
+    }
+
+    /// Path of the type `key`, qualified with the import of its module if it's in another one.
+    ///
+    /// Types of inline modules are always qualified from the top of the module, as class
+    /// bodies don't see the names of the classes they're nested in.
+    fn path(&self, key: &str) -> String {
+        let (file, name) = locate(self.files, self.root, key);
+
+        let mut path = split(name).into_iter().map(ident).collect::<Vec<_>>();
+        if file != self.file {
+            path.insert(0, alias(file));
+        }
+        path.join(".")
+    }
+
+    /// Path of the class of a variant of the union `key`.
+    fn variant(&self, key: &str, name: &str) -> String {
+        let mut path = self.path(key);
+        let class = variant(path.rsplit('.').next().unwrap_or(""), name);
+
+        let len = path.rfind('.').map_or(0, |i| i + 1);
+        path.truncate(len);
+        path.push_str(&class);
+        path
+    }
+
+    /// Expression of the value of `ty` when none is given.
+    fn default(&self, ty: &Type) -> String {
+        match *ty.info() {
+            TypeInfo::Primitive(ref p) => match p.tt {
+                Trait::Bool => "False".into(),
+                Trait::Integer => "0".into(),
+                Trait::Float => "0.0".into(),
+                _ => "\"\"".into(),
+            },
+            TypeInfo::Struct(_) => format!("{}()", self.path(&ty.key())),
+            TypeInfo::Enum(ref e) => match e.members.first() {
+                Some(v) => format!("{}.{}", self.path(&ty.key()), ident(&v.name)),
+                None => "None".into(),
+            },
+            TypeInfo::Union(ref u) => match u.members.first() {
+                Some(v) => format!("{}()", self.variant(&ty.key(), &v.name)),
+                None => "None".into(),
+            },
+            TypeInfo::Template(ref t) => match (t.name.as_str(), t.params.as_slice()) {
+                ("vector", _) => "[]".into(),
+                ("set", _) => "set()".into(),
+                ("map", _) => "{}".into(),
+                ("array", [p]) => {
+                    let item = self.default(p);
+
+                    if immutable(&item) {
+                        format!("[{}] * {}", item, t.size.unwrap_or(0))
+                    } else {
+                        format!("[{} for _ in range({})]", item, t.size.unwrap_or(0))
+                    }
+                }
+                _ => "None".into(),
+            },
+            TypeInfo::Ref(_) => match self.types.get(&ty.key()) {
+                Some(def) if !def.is_ref() => self.default(def),
+                _ => format!("{}()", self.path(&ty.key())),
+            },
+        }
+    }
+
+    /// Expression of `value` of `ty`, or `None` if it doesn't fit the type.
+    fn value(&self, ty: &Type, value: &Value) -> Option<String> {
+        match *ty.info() {
+            TypeInfo::Primitive(ref p) => match p.tt {
+                Trait::Bool => value.as_bool().map(|b| if b { "True" } else { "False" }.into()),
+                Trait::Integer if value.is_i64() || value.is_u64() => Some(value.to_string()),
+                Trait::Float => value.as_f64().map(|f| format!("{:?}", f)),
+                // JSON strings are valid Python strings.
+                Trait::String if value.is_string() => Some(value.to_string()),
+                _ => None,
+            },
+            TypeInfo::Struct(ref s) => {
+                let fields = match *value {
+                    Value::Object(ref o) => s.members
+                        .iter()
+                        .filter_map(|m| o.get(&m.name).map(|v| (m, v)))
+                        .collect::<Vec<_>>(),
+                    Value::Array(ref a) => s.members.iter().zip(a.iter()).collect(),
+                    _ => return None,
+                };
+
+                let mut args = Vec::new();
+                for (m, v) in &fields {
+                    args.push(format!("{}={}", ident(&m.name), self.value(&m.ty, v)?));
+                }
+
+                Some(format!("{}({})", self.path(&ty.key()), args.join(", ")))
+            }
+            TypeInfo::Enum(ref e) => {
+                let name = value.as_str()?;
+                e.members.iter().find(|m| m.name == name)?;
+
+                Some(format!("{}.{}", self.path(&ty.key()), ident(name)))
+            }
+            TypeInfo::Union(ref u) => match *value {
+                Value::String(ref name) => {
+                    u.members.iter().find(|m| &m.name == name && m.ty.is_none())?;
+
+                    Some(format!("{}()", self.variant(&ty.key(), name)))
+                }
+                Value::Object(ref o) if o.len() == 1 => {
+                    let (name, payload) = o.iter().next()?;
+                    let member = u.members.iter().find(|m| &m.name == name)?;
+                    let payload = self.value(member.ty.as_ref()?, payload)?;
+
+                    Some(format!("{}({})", self.variant(&ty.key(), name), payload))
+                }
+                _ => None,
+            },
+            TypeInfo::Template(ref t) => self.template(t, value),
+            TypeInfo::Ref(_) => {
+                let def = self.types.get(&ty.key())?;
+
+                if def.is_ref() {
+                    return None;
+                }
+
+                self.value(def, value)
+            }
+        }
+    }
+
+    fn template(&self, t: &Template, value: &Value) -> Option<String> {
+        match (t.name.as_str(), t.params.as_slice()) {
+            ("vector", [p]) | ("set", [p]) | ("array", [p]) => {
+                let mut items = Vec::new();
+                for v in value.as_array()? {
+                    items.push(self.value(p, v)?);
+                }
+
+                Some(match t.name.as_str() {
+                    "set" if items.is_empty() => "set()".into(),
+                    "set" => format!("{{{}}}", items.join(", ")),
+                    _ => format!("[{}]", items.join(", ")),
+                })
+            }
+            ("map", [k, v]) => {
+                let mut items = Vec::new();
+                for (key, value) in value.as_object()? {
+                    let key = match k.tt() {
+                        Trait::Integer => serde_json::from_str(key).ok()?,
+                        Trait::Bool => Value::Bool(key.parse().ok()?),
+                        _ => Value::String(key.clone()),
+                    };
+
+                    items.push(format!("{}: {}", self.value(k, &key)?, self.value(v, value)?));
+                }
+
+                Some(format!("{{{}}}", items.join(", ")))
+            }
+            ("optional", _) if value.is_null() => Some("None".into()),
+            ("optional", [p]) => self.value(p, value),
+            _ => None,
+        }
+    }
+}
+
+/// Whether an expression is a literal which can be shared between instances.
+fn immutable(expr: &str) -> bool {
+    expr == "None"
+        || expr == "True"
+        || expr == "False"
+        || expr.starts_with('"')
+        || expr.parse::<f64>().is_ok()
+}
+
+/// Default of a field, which is made by a factory unless it's an immutable literal.
+///
+/// Other expressions may refer to classes not defined yet.
+fn initializer(expr: String) -> String {
+    if immutable(&expr) {
+        expr
+    } else {
+        format!("field(default_factory=lambda: {})", expr)
+    }
+}
+
+/// Name of the class of a variant of a union.
+fn variant(union: &str, name: &str) -> String {
+    format!("{}_{}", union, name)
+}
+
+/// Name a module is imported as.
+fn alias(ns: &str) -> String {
+    ident(&split(ns).join("_"))
+}
+
+/// Appends an underscore to Python keywords and `self`.
+fn ident(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.into()
+    }
+}
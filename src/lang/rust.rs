@@ -0,0 +1,475 @@
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+
+use super::{qualify, split, LangGenerator};
+use crate::Result;
+use crate::render::Output;
+use crate::types::*;
+
+/// Words which can't be used as identifiers without the `r#` prefix.
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+    "pub", "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use",
+    "where", "while", "abstract", "become", "box", "do", "final", "gen", "macro", "override",
+    "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+/// Words which can't be used as identifiers even with the `r#` prefix.
+const RESERVED: &[&str] = &["crate", "self", "Self", "super", "_"];
+
+/// Arrays up to this size implement `Default` and serde traits.
+const MAX_ARRAY: u64 = 32;
+
+/// Generates Rust code with serde derives.
+///
+/// Interfaces have a client and a server for the `rrpc-runtime` crate, which the
+/// generated code depends on.
+///
+/// Every type is annotated with a `rust` object, e.g. `rust.type` of a field is
+/// its Rust type as seen from the module the field is in.
+pub struct RustGenerator;
+
+impl LangGenerator for RustGenerator {
+    fn outputs(&self) -> Vec<Output> {
+        vec![Output::new("rust/root.rs", "{{namespace}}.rs", false)]
+    }
+
+    fn embedded_templates(&self) -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("rust/root.rs", include_str!("../../templates/rust/root.rs")),
+            ("rust/macros.rs", include_str!("../../templates/rust/macros.rs")),
+        ]
+    }
+
+    fn generate_model(&mut self, mut model: Model) -> Result<Model> {
+        let root = model.root.namespace.clone();
+
+        let mut types = HashMap::new();
+        for (ns, defs) in &model.modules {
+            let scope = if *ns == root { "" } else { ns.as_str() };
+            collect(scope, &defs.nodes, &mut types);
+        }
+
+        let annotator = Annotator::new(types);
+
+        for (ns, defs) in model.modules.iter_mut() {
+            // Items of the root module are at the top of the file, other modules are nested in it.
+            let scope = if *ns == root { "" } else { ns.as_str() };
+            let segments = split(scope);
+
+            defs.custom = json!({
+                "rust": {
+                    "mods": segments.iter().map(|s| ident(s)).collect::<Vec<_>>(),
+                    "root": supers(segments.len()),
+                }
+            });
+
+            for node in defs.nodes.iter_mut() {
+                annotator.node(scope, node);
+            }
+        }
+
+        if let Some(defs) = model.modules.get(&root) {
+            model.root = defs.clone();
+        }
+
+        Ok(model)
+    }
+}
+
+/// Collects the definitions of types by their keys, to look up `Ref`s.
+fn collect(scope: &str, nodes: &[Node], types: &mut HashMap<String, Type>) {
+    for node in nodes {
+        let (name, ty) = match *node {
+            Node::Struct(ref s) => (&s.name, Type::from(s.clone())),
+            Node::Enum(ref e) => (&e.name, Type::from(e.clone())),
+            Node::Union(ref u) => (&u.name, Type::from(u.clone())),
+            Node::Module(ref m) => {
+                collect(&m.namespace, &m.nodes, types);
+                continue;
+            }
+            Node::Interface(_) => continue,
+        };
+
+        let key = qualify(scope, name);
+        types.insert(key.clone(), ty.namespaced(&key));
+    }
+}
+
+struct Annotator {
+    types: HashMap<String, Type>,
+    /// Keys of the types which contain themselves without going through the heap.
+    cyclic: HashSet<String>,
+}
+
+impl Annotator {
+    fn new(types: HashMap<String, Type>) -> Self {
+        let cyclic = types
+            .keys()
+            .filter(|key| reaches(&types, key, key, &mut HashSet::new()))
+            .cloned()
+            .collect();
+
+        Self { types, cyclic }
+    }
+
+    /// Whether an `optional` of `ty` is boxed, so that a type can contain itself through it.
+    fn boxed(&self, ty: &Type) -> bool {
+        let mut keys = Vec::new();
+        inline(ty, &mut keys);
+
+        keys.iter().any(|key| self.cyclic.contains(key))
+    }
+
+    fn node(&self, scope: &str, node: &mut Node) {
+        match *node {
+            Node::Struct(ref mut s) => {
+                s.custom["rust"] = json!({ "name": ident(&s.name) });
+
+                for f in s.members.iter_mut() {
+                    let default = f.value
+                        .as_ref()
+                        .and_then(|v| self.value(scope, &f.ty, v))
+                        .unwrap_or_else(|| "Default::default()".into());
+
+                    f.custom["rust"] = json!({
+                        "name": ident(&f.name),
+                        "rename": rename(&f.name),
+                        "type": self.ty(scope, &f.ty),
+                        "default": default,
+                    });
+                }
+            }
+            Node::Enum(ref mut e) => {
+                e.custom["rust"] = json!({
+                    "name": ident(&e.name),
+                    "repr": self.ty(scope, &e.utype),
+                });
+
+                for v in e.members.iter_mut() {
+                    v.custom["rust"] = json!({
+                        "name": ident(&v.name),
+                        "rename": rename(&v.name),
+                    });
+                }
+            }
+            Node::Union(ref mut u) => {
+                u.custom["rust"] = json!({ "name": ident(&u.name) });
+
+                for v in u.members.iter_mut() {
+                    v.custom["rust"] = json!({
+                        "name": ident(&v.name),
+                        "rename": rename(&v.name),
+                        "type": v.ty.as_ref().map(|ty| self.ty(scope, ty)),
+                    });
+                }
+            }
+            Node::Interface(ref mut i) => {
+                i.custom["rust"] = json!({
+                    "name": ident(&i.name),
+                    "client": ident(&format!("{}Client", i.name)),
+                    "server": ident(&format!("{}Server", i.name)),
+                    "root": supers(split(scope).len()),
+                });
+
+                for f in i.funcs.iter_mut() {
+                    for a in f.args.iter_mut() {
+                        a.custom["rust"] = json!({
+                            "name": ident(&a.name),
+                            "type": self.arg(scope, &a.ty),
+                        });
+                    }
+
+                    let ret = f.ret.iter().map(|ty| self.arg(scope, ty)).collect::<Vec<_>>();
+                    let names = f.args
+                        .iter()
+                        .map(|a| ident(&a.name))
+                        .collect::<Vec<_>>();
+                    let params = f.args
+                        .iter()
+                        .map(|a| format!("{:?}", a.name))
+                        .collect::<Vec<_>>();
+                    let types = f.args
+                        .iter()
+                        .map(|a| self.arg(scope, &a.ty))
+                        .collect::<Vec<_>>();
+
+                    // Arguments are sent as a tuple, even if there's only one.
+                    f.custom["rust"] = json!({
+                        "name": ident(&f.name),
+                        "ret": tuple(ret),
+                        "args": args(&names),
+                        "types": args(&types),
+                        "names": names.join(", "),
+                        // Names of the arguments, for codecs which send them.
+                        "params": format!("&[{}]", params.join(", ")),
+                    });
+                }
+            }
+            Node::Module(ref mut m) => {
+                m.custom["rust"] = json!({
+                    "name": ident(&m.name),
+                    "root": supers(split(&m.namespace).len()),
+                });
+
+                let scope = m.namespace.clone();
+                for node in m.nodes.iter_mut() {
+                    self.node(&scope, node);
+                }
+            }
+        }
+    }
+
+    /// Rust type of `ty`, as seen from the module `scope`.
+    fn ty(&self, scope: &str, ty: &Type) -> String {
+        self.render(scope, ty, true)
+    }
+
+    /// Rust type of an argument or a returned value, which never needs to be boxed.
+    fn arg(&self, scope: &str, ty: &Type) -> String {
+        self.render(scope, ty, false)
+    }
+
+    fn render(&self, scope: &str, ty: &Type, boxing: bool) -> String {
+        match *ty.info() {
+            TypeInfo::Primitive(ref p) => match p.name.as_str() {
+                "string" => "String".into(),
+                name => name.into(),
+            },
+            TypeInfo::Template(ref t) => {
+                let params = t.params
+                    .iter()
+                    .map(|p| self.render(scope, p, boxing))
+                    .collect::<Vec<_>>();
+
+                match (t.name.as_str(), params.as_slice()) {
+                    ("vector", [p]) => format!("Vec<{}>", p),
+                    ("set", [k]) => format!("std::collections::HashSet<{}>", k),
+                    ("map", [k, v]) => format!("std::collections::HashMap<{}, {}>", k, v),
+                    ("optional", [p]) if boxing && self.boxed(&t.params[0]) => format!("Option<Box<{}>>", p),
+                    ("optional", [p]) => format!("Option<{}>", p),
+                    ("array", [p]) if small(t.size) => format!("[{}; {}]", p, t.size.unwrap()),
+                    ("array", [p]) => format!("rrpc_runtime::Array<{}, {}>", p, t.size.unwrap()),
+                    (name, _) => format!("{}<{}>", ident(name), params.join(", ")),
+                }
+            }
+            _ => path(scope, &ty.key()),
+        }
+    }
+
+    /// Rust expression of `value` of `ty`, or `None` if it doesn't fit the type.
+    fn value(&self, scope: &str, ty: &Type, value: &Value) -> Option<String> {
+        match *ty.info() {
+            TypeInfo::Primitive(ref p) => match p.tt {
+                Trait::Bool => value.as_bool().map(|b| b.to_string()),
+                Trait::Integer if value.is_i64() || value.is_u64() => Some(value.to_string()),
+                Trait::Float => value.as_f64().map(|f| format!("{:?}", f)),
+                Trait::String => value.as_str().map(|s| format!("{:?}.to_string()", s)),
+                _ => None,
+            },
+            TypeInfo::Struct(ref s) => {
+                let path = path(scope, &ty.key());
+                let fields = match *value {
+                    Value::Object(ref o) => s.members
+                        .iter()
+                        .filter_map(|m| o.get(&m.name).map(|v| (m, v)))
+                        .collect::<Vec<_>>(),
+                    Value::Array(ref a) => s.members.iter().zip(a.iter()).collect(),
+                    _ => return None,
+                };
+
+                let mut exprs = Vec::new();
+                for (m, v) in &fields {
+                    exprs.push(format!("{}: {}", ident(&m.name), self.value(scope, &m.ty, v)?));
+                }
+                if fields.len() < s.members.len() {
+                    exprs.push("..Default::default()".into());
+                }
+
+                Some(format!("{} {{ {} }}", path, exprs.join(", ")))
+            }
+            TypeInfo::Enum(ref e) => {
+                let name = value.as_str()?;
+                e.members.iter().find(|m| m.name == name)?;
+
+                Some(format!("{}::{}", path(scope, &ty.key()), ident(name)))
+            }
+            TypeInfo::Union(ref u) => {
+                let path = path(scope, &ty.key());
+
+                match *value {
+                    Value::String(ref name) => {
+                        u.members.iter().find(|m| &m.name == name && m.ty.is_none())?;
+
+                        Some(format!("{}::{}", path, ident(name)))
+                    }
+                    Value::Object(ref o) if o.len() == 1 => {
+                        let (name, payload) = o.iter().next()?;
+                        let member = u.members.iter().find(|m| &m.name == name)?;
+                        let payload = self.value(scope, member.ty.as_ref()?, payload)?;
+
+                        Some(format!("{}::{}({})", path, ident(name), payload))
+                    }
+                    _ => None,
+                }
+            }
+            TypeInfo::Template(ref t) => self.template(scope, t, value),
+            TypeInfo::Ref(_) => {
+                let def = self.types.get(&ty.key())?;
+
+                if def.is_ref() {
+                    return None;
+                }
+
+                self.value(scope, def, value)
+            }
+        }
+    }
+
+    fn template(&self, scope: &str, t: &Template, value: &Value) -> Option<String> {
+        match (t.name.as_str(), t.params.as_slice()) {
+            ("vector", [p]) | ("set", [p]) | ("array", [p]) => {
+                let mut items = Vec::new();
+                for v in value.as_array()? {
+                    items.push(self.value(scope, p, v)?);
+                }
+
+                Some(match t.name.as_str() {
+                    "set" => format!("vec![{}].into_iter().collect()", items.join(", ")),
+                    "array" if small(t.size) => {
+                        format!("[{}]", items.join(", "))
+                    }
+                    "array" => format!("rrpc_runtime::Array::from([{}])", items.join(", ")),
+                    _ => format!("vec![{}]", items.join(", ")),
+                })
+            }
+            ("map", [k, v]) => {
+                let mut items = Vec::new();
+                for (key, value) in value.as_object()? {
+                    let key = match k.tt() {
+                        Trait::Integer => serde_json::from_str(key).ok()?,
+                        Trait::Bool => Value::Bool(key.parse().ok()?),
+                        _ => Value::String(key.clone()),
+                    };
+
+                    items.push(format!(
+                        "({}, {})",
+                        self.value(scope, k, &key)?,
+                        self.value(scope, v, value)?
+                    ));
+                }
+
+                Some(format!("vec![{}].into_iter().collect()", items.join(", ")))
+            }
+            ("optional", [p]) => {
+                if value.is_null() {
+                    return Some("None".into());
+                }
+
+                let inner = self.value(scope, p, value)?;
+
+                if self.boxed(p) {
+                    Some(format!("Some(Box::new({}))", inner))
+                } else {
+                    Some(format!("Some({})", inner))
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Collects the keys of the types `ty` holds inline, rather than through the heap.
+fn inline(ty: &Type, keys: &mut Vec<String>) {
+    match *ty.info() {
+        TypeInfo::Primitive(_) => {}
+        TypeInfo::Template(ref t) => match t.name.as_str() {
+            "vector" | "set" | "map" => {}
+            _ => t.params.iter().for_each(|p| inline(p, keys)),
+        },
+        _ => keys.push(ty.key()),
+    }
+}
+
+/// Whether the type `from` holds the type `to` inline, through its fields or variants.
+fn reaches(types: &HashMap<String, Type>, from: &str, to: &str, seen: &mut HashSet<String>) -> bool {
+    let mut keys = Vec::new();
+    match types.get(from).map(|ty| ty.info()) {
+        Some(TypeInfo::Struct(s)) => s.members.iter().for_each(|f| inline(&f.ty, &mut keys)),
+        Some(TypeInfo::Union(u)) => {
+            u.members.iter().flat_map(|v| &v.ty).for_each(|ty| inline(ty, &mut keys))
+        }
+        _ => {}
+    }
+
+    keys.into_iter().any(|key| {
+        key == to || (seen.insert(key.clone()) && reaches(types, &key, to, seen))
+    })
+}
+
+/// Whether an array of the size is mapped to a Rust array, rather than `rrpc_runtime::Array`.
+fn small(size: Option<u64>) -> bool {
+    matches!(size, Some(n) if n <= MAX_ARRAY)
+}
+
+/// Path of the type `key` relative to the module `scope`.
+fn path(scope: &str, key: &str) -> String {
+    let from = split(scope);
+    let to = split(key);
+    let (name, ns) = to.split_last().expect("empty type key");
+
+    let common = from.iter()
+        .zip(ns.iter())
+        .take_while(|&(a, b)| a == b)
+        .count();
+
+    let mut path = supers(from.len() - common);
+    for s in &ns[common..] {
+        path.push_str(&ident(s));
+        path.push_str("::");
+    }
+    path.push_str(&ident(name));
+    path
+}
+
+fn supers(n: usize) -> String {
+    "super::".repeat(n)
+}
+
+/// Escapes a keyword with `r#`, or with a trailing underscore if it can't be a raw identifier.
+fn ident(name: &str) -> String {
+    if RESERVED.contains(&name) {
+        format!("{}_", name)
+    } else if KEYWORDS.contains(&name) {
+        format!("r#{}", name)
+    } else {
+        name.into()
+    }
+}
+
+/// The name to serialize with, if the identifier differs from it.
+fn rename(name: &str) -> Option<&str> {
+    if RESERVED.contains(&name) {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+/// Makes a tuple of the arguments, even if there's only one.
+fn args(items: &[String]) -> String {
+    match items.len() {
+        1 => format!("({},)", items[0]),
+        _ => format!("({})", items.join(", ")),
+    }
+}
+
+/// Makes a tuple of the items, or the item itself if there's only one.
+fn tuple(items: Vec<String>) -> String {
+    match items.len() {
+        1 => items.into_iter().next().unwrap(),
+        _ => format!("({})", items.join(", ")),
+    }
+}
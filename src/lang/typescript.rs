@@ -0,0 +1,385 @@
+use std::collections::{BTreeSet, HashMap};
+
+use serde_json::{Map, Value};
+
+use super::jsonschema::types;
+use super::vectors::Encoder;
+use super::{locate, split, uses, LangGenerator};
+use crate::Result;
+use crate::render::Output;
+use crate::types::*;
+
+/// Words which can't be used as names of types, namespaces or arguments.
+const KEYWORDS: &[&str] = &[
+    "any", "boolean", "break", "case", "catch", "class", "const", "continue", "debugger",
+    "default", "delete", "do", "else", "enum", "export", "extends", "false", "finally", "for",
+    "function", "if", "implements", "import", "in", "instanceof", "interface", "let", "never",
+    "new", "null", "number", "object", "package", "private", "protected", "public", "return",
+    "static", "string", "super", "switch", "symbol", "this", "throw", "true", "try", "typeof",
+    "undefined", "unknown", "var", "void", "while", "with", "yield",
+];
+
+/// Name of the module shared by the generated modules.
+const RUNTIME: &str = "rrpc";
+
+/// Generates TypeScript, an ES module for each module.
+///
+/// Types follow the JSON the other generators serialize to: enums are unions of
+/// their variant names, and unions are either a variant name or an object keyed by it.
+/// 64-bit integers are `bigint`s though, which keep all of their bits.
+/// Every type is annotated with a `typescript` object, e.g. `typescript.type` of a
+/// field is its TypeScript type as seen from the module the field is in.
+///
+/// Each struct, enum and union also has a constant of the same name, its codec of the
+/// binary encoding, of which `typescript.codec` is the expression.
+pub struct TypeScriptGenerator;
+
+impl LangGenerator for TypeScriptGenerator {
+    fn outputs(&self) -> Vec<Output> {
+        vec![
+            Output::new("typescript/module.ts", "{{namespace_path}}.ts", true),
+            Output::new("typescript/rrpc.ts", "rrpc.ts", false),
+        ]
+    }
+
+    fn embedded_templates(&self) -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("typescript/module.ts", include_str!("../../templates/typescript/module.ts")),
+            ("typescript/macros.ts", include_str!("../../templates/typescript/macros.ts")),
+            ("typescript/rrpc.ts", include_str!("../../templates/typescript/rrpc.ts")),
+        ]
+    }
+
+    fn generate_model(&mut self, mut model: Model) -> Result<Model> {
+        let root = model.root.namespace.clone();
+        let files = model.modules.keys().cloned().collect::<Vec<_>>();
+        let files = files.iter().map(|ns| ns.as_str()).collect::<Vec<_>>();
+        let values = Encoder::new(&model);
+        let types = types(&model);
+
+        for (ns, defs) in model.modules.iter_mut() {
+            let annotator = Annotator {
+                root: &root,
+                files: &files,
+                file: ns,
+                values: &values,
+                types: &types,
+            };
+
+            // Imports are relative to the directory of the module.
+            let up = match split(ns).len() {
+                0 | 1 => "./".to_string(),
+                n => "../".repeat(n - 1),
+            };
+
+            let mut namespaces = BTreeSet::new();
+            uses(&defs.uses, &defs.nodes, &mut namespaces);
+            let imports = namespaces
+                .iter()
+                .filter(|u| *u != ns)
+                .map(|u| {
+                    json!({
+                        "alias": alias(u),
+                        "path": format!("{}{}", up, u.replace("::", "/")),
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let mut clients = false;
+            for node in defs.nodes.iter_mut() {
+                clients |= annotator.node(node);
+            }
+
+            defs.custom["typescript"] = json!({
+                "imports": imports,
+                "runtime": format!("{}{}", up, RUNTIME),
+                "clients": clients,
+            });
+        }
+
+        if let Some(defs) = model.modules.get(&root) {
+            model.root = defs.clone();
+        }
+
+        Ok(model)
+    }
+}
+
+struct Annotator<'a> {
+    root: &'a str,
+    /// Modules with a `.ts` file of their own, which types are imported from.
+    files: &'a [&'a str],
+    /// Namespace of the module being annotated.
+    file: &'a str,
+    /// Completes the defaults of structs.
+    values: &'a Encoder,
+    types: &'a HashMap<String, Type>,
+}
+
+impl<'a> Annotator<'a> {
+    /// Annotates a node, and returns whether it has a client class.
+    fn node(&self, node: &mut Node) -> bool {
+        match *node {
+            Node::Struct(ref mut s) => {
+                let defaults = self.values.structure(s, &Map::new());
+                s.custom["typescript"] = json!({
+                    "name": ident(&s.name),
+                    "defaults": self.fields(s, &defaults),
+                });
+
+                for f in s.members.iter_mut() {
+                    f.custom["typescript"] = json!({
+                        "type": self.ty(&f.ty),
+                        "codec": self.codec(&f.ty),
+                    });
+                }
+
+                false
+            }
+            Node::Enum(ref mut e) => {
+                let names = e.members.iter().map(|v| literal(&v.name)).collect::<Vec<_>>();
+
+                e.custom["typescript"] = json!({
+                    "name": ident(&e.name),
+                    "type": alternatives(names),
+                    "codec": self.codec(&e.utype),
+                    "utype": self.ty(&e.utype),
+                });
+
+                let utype = &e.utype;
+                for v in e.members.iter_mut() {
+                    let value = v.value.as_ref().map_or("0".into(), |n| self.value(utype, n));
+                    v.custom["typescript"] = json!({ "value": value });
+                }
+
+                false
+            }
+            Node::Union(ref mut u) => {
+                let mut variants = Vec::new();
+                for v in u.members.iter() {
+                    variants.push(match v.ty {
+                        Some(ref ty) => format!("{{ {}: {} }}", v.name, self.ty(ty)),
+                        None => literal(&v.name),
+                    });
+                }
+
+                u.custom["typescript"] = json!({
+                    "name": ident(&u.name),
+                    "type": alternatives(variants),
+                });
+
+                for v in u.members.iter_mut() {
+                    let codec = v.ty.as_ref().map_or("null".into(), |ty| self.codec(ty));
+                    v.custom["typescript"] = json!({ "codec": codec });
+                }
+
+                false
+            }
+            Node::Interface(ref mut i) => {
+                i.custom["typescript"] = json!({
+                    "name": ident(&i.name),
+                    "client": ident(&format!("{}Client", i.name)),
+                });
+
+                for f in i.funcs.iter_mut() {
+                    for a in f.args.iter_mut() {
+                        a.custom["typescript"] = json!({
+                            "name": ident(&a.name),
+                            "type": self.ty(&a.ty),
+                        });
+                    }
+
+                    let ret = f.ret.iter().map(|ty| self.ty(ty)).collect::<Vec<_>>();
+                    let ret = match ret.len() {
+                        0 => "void".into(),
+                        1 => ret[0].clone(),
+                        _ => format!("[{}]", ret.join(", ")),
+                    };
+
+                    f.custom["typescript"] = json!({
+                        "name": method(&f.name),
+                        "ret": ret,
+                    });
+                }
+
+                true
+            }
+            Node::Module(ref mut m) => {
+                m.custom["typescript"] = json!({ "name": ident(&m.name) });
+
+                let mut clients = false;
+                for node in m.nodes.iter_mut() {
+                    clients |= self.node(node);
+                }
+
+                clients
+            }
+        }
+    }
+
+    /// TypeScript type of `ty`.
+    fn ty(&self, ty: &Type) -> String {
+        match *ty.info() {
+            TypeInfo::Primitive(ref p) => match p.tt {
+                Trait::Bool => "boolean".into(),
+                Trait::String => "string".into(),
+                _ if big(&p.name) => "bigint".into(),
+                _ => "number".into(),
+            },
+            TypeInfo::Template(ref t) => {
+                let params = t.params.iter().map(|p| self.ty(p)).collect::<Vec<_>>();
+
+                match (t.name.as_str(), params.as_slice()) {
+                    // Optionals are unions, which bind looser than `[]`.
+                    ("vector", [p]) | ("set", [p]) | ("array", [p]) if optional(&t.params[0]) => {
+                        format!("({})[]", p)
+                    }
+                    ("vector", [p]) | ("set", [p]) | ("array", [p]) => format!("{}[]", p),
+                    // Keys are strings in JSON, so only some of the keys may be there.
+                    ("map", [k, v]) => match t.params[0].tt() {
+                        Trait::Bool => format!("Partial<Record<\"true\" | \"false\", {}>>", v),
+                        Trait::Enum => format!("Partial<Record<{}, {}>>", k, v),
+                        // Records can't be keyed by bigints, but by their digits.
+                        _ if k == "bigint" => format!("Record<string, {}>", v),
+                        _ => format!("Record<{}, {}>", k, v),
+                    },
+                    ("optional", [p]) => format!("{} | null", p),
+                    (name, _) => format!("{}<{}>", ident(name), params.join(", ")),
+                }
+            }
+            _ => self.path(&ty.key()),
+        }
+    }
+
+    /// Expression of `value` of `ty`, which is its JSON but for 64-bit integers.
+    fn value(&self, ty: &Type, value: &Value) -> String {
+        match (ty.info(), value) {
+            (TypeInfo::Primitive(p), Value::Number(n)) if big(&p.name) => format!("{}n", n),
+            (TypeInfo::Struct(s), Value::Object(_)) => self.fields(s, value),
+            (TypeInfo::Union(u), Value::Object(o)) => {
+                let entries = o.iter().map(|(name, payload)| {
+                    match u.members.iter().find(|v| v.name == *name) {
+                        Some(UnionVariant { ty: Some(ty), .. }) => (name, self.value(ty, payload)),
+                        _ => (name, payload.to_string()),
+                    }
+                });
+                object(entries)
+            }
+            (TypeInfo::Template(t), _) => match (t.name.as_str(), t.params.as_slice(), value) {
+                ("vector", [p], Value::Array(a))
+                | ("set", [p], Value::Array(a))
+                | ("array", [p], Value::Array(a)) => {
+                    let items = a.iter().map(|v| self.value(p, v)).collect::<Vec<_>>();
+                    format!("[{}]", items.join(","))
+                }
+                ("map", [_, p], Value::Object(o)) => {
+                    object(o.iter().map(|(k, v)| (k, self.value(p, v))))
+                }
+                ("optional", [p], v) if !v.is_null() => self.value(p, v),
+                _ => value.to_string(),
+            },
+            (TypeInfo::Ref(_), _) => match self.types.get(&ty.key()) {
+                Some(def) if !def.is_ref() => self.value(def, value),
+                _ => value.to_string(),
+            },
+            _ => value.to_string(),
+        }
+    }
+
+    /// Expression of the value of a struct, whose fields are all in `value`.
+    fn fields(&self, s: &Struct, value: &Value) -> String {
+        let fields = s.members
+            .iter()
+            .filter_map(|f| Some((&f.name, self.value(&f.ty, value.get(&f.name)?))));
+        object(fields)
+    }
+
+    /// Expression of the codec of `ty`.
+    ///
+    /// Codecs of generated types are looked up when they're used, as they may be
+    /// defined later.
+    fn codec(&self, ty: &Type) -> String {
+        match *ty.info() {
+            // `string` is a type, so its codec is `str`.
+            TypeInfo::Primitive(ref p) if p.tt == Trait::String => format!("{}.str", RUNTIME),
+            TypeInfo::Primitive(ref p) => format!("{}.{}", RUNTIME, p.name),
+            TypeInfo::Template(ref t) => {
+                let params = t.params.iter().map(|p| self.codec(p)).collect::<Vec<_>>();
+
+                match (t.name.as_str(), params.as_slice()) {
+                    ("array", [p]) => format!("{}.array({}, {})", RUNTIME, p, t.size.unwrap_or(0)),
+                    (name, _) => format!("{}.{}({})", RUNTIME, ident(name), params.join(", ")),
+                }
+            }
+            _ => format!("{}.lazy(() => {})", RUNTIME, self.path(&ty.key())),
+        }
+    }
+
+    /// Path of the type `key`, qualified with the import of its module if it's in another one.
+    fn path(&self, key: &str) -> String {
+        let (file, name) = locate(self.files, self.root, key);
+
+        let mut path = split(name).into_iter().map(ident).collect::<Vec<_>>();
+        if file != self.file {
+            path.insert(0, alias(file));
+        }
+        path.join(".")
+    }
+}
+
+/// Whether a primitive is a 64-bit integer, which doesn't fit a number.
+fn big(name: &str) -> bool {
+    name == "i64" || name == "u64"
+}
+
+/// Object literal of the entries, which are expressions.
+fn object<'b, I: Iterator<Item = (&'b String, String)>>(entries: I) -> String {
+    let entries = entries
+        .map(|(k, v)| format!("{}:{}", Value::String(k.clone()), v))
+        .collect::<Vec<_>>();
+    format!("{{{}}}", entries.join(","))
+}
+
+fn optional(ty: &Type) -> bool {
+    match *ty.info() {
+        TypeInfo::Template(ref t) => t.name == "optional",
+        _ => false,
+    }
+}
+
+/// Name a module is imported as.
+fn alias(ns: &str) -> String {
+    ident(&split(ns).join("_"))
+}
+
+/// Union of the types, which is `never` if there are none.
+fn alternatives(types: Vec<String>) -> String {
+    if types.is_empty() {
+        "never".into()
+    } else {
+        types.join(" | ")
+    }
+}
+
+/// String literal of a name.
+fn literal(name: &str) -> String {
+    format!("{:?}", name)
+}
+
+/// Appends an underscore to the words TypeScript reserves.
+fn ident(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.into()
+    }
+}
+
+/// Makes a valid method name of a name.
+fn method(name: &str) -> String {
+    if name == "constructor" {
+        format!("{}_", name)
+    } else {
+        name.into()
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use serde_json::{Map, Value};
+
+use super::jsonschema::types;
+use super::{qualify, LangGenerator};
+use crate::Result;
+use crate::render::Output;
+use crate::types::*;
+use crate::value::{as_int, int_range};
+
+/// Version of the binary encoding the vectors are encoded with.
+const VERSION: u64 = 1;
+
+/// Generates golden vectors of the binary encoding described in `docs/binary.md`.
+///
+/// There's a vector of the default value of every struct, enum and union, and one of
+/// each variant of enums and unions. A vector has the key of its `type`, the `value` as
+/// JSON, the way the generated code serializes it, and the `hex` of its encoding. The
+/// document is in `vectors.document` of the root module.
+pub struct VectorsGenerator;
+
+impl LangGenerator for VectorsGenerator {
+    fn outputs(&self) -> Vec<Output> {
+        vec![Output::new("vectors/vectors.json", "vectors.json", false)]
+    }
+
+    fn embedded_templates(&self) -> Vec<(&'static str, &'static str)> {
+        vec![("vectors/vectors.json", include_str!("../../templates/vectors/vectors.json"))]
+    }
+
+    fn generate_model(&mut self, mut model: Model) -> Result<Model> {
+        let root = model.root.namespace.clone();
+        let encoder = Encoder::new(&model);
+
+        let mut vectors = Vec::new();
+        for (ns, defs) in &model.modules {
+            let scope = if *ns == root { "" } else { ns.as_str() };
+            encoder.nodes(scope, &defs.nodes, &mut vectors);
+        }
+
+        let document = json!({ "version": VERSION, "vectors": vectors });
+
+        if let Some(defs) = model.modules.get_mut(&root) {
+            defs.custom["vectors"] = json!({ "document": document });
+            model.root = defs.clone();
+        }
+
+        Ok(model)
+    }
+}
+
+/// Encodes values of the types of a model.
+///
+/// Values are JSON, as in the definitions: structs are objects of their fields, enums
+/// the names of their variants, and unions the name of a variant or an object of it.
+pub(super) struct Encoder {
+    types: HashMap<String, Type>,
+}
+
+impl Encoder {
+    pub(super) fn new(model: &Model) -> Self {
+        Self {
+            types: types(model),
+        }
+    }
+
+    /// Adds the vectors of the types of the module `scope`.
+    fn nodes(&self, scope: &str, nodes: &[Node], vectors: &mut Vec<Value>) {
+        for node in nodes {
+            let (name, values) = match *node {
+                Node::Struct(ref s) => (&s.name, vec![self.structure(s, &Map::new())]),
+                Node::Enum(ref e) => (&e.name, e.members.iter().map(|v| json!(v.name)).collect()),
+                Node::Union(ref u) => {
+                    let values = u.members.iter().map(|v| self.variant(v)).collect();
+                    (&u.name, values)
+                }
+                Node::Module(ref m) => {
+                    self.nodes(&m.namespace, &m.nodes, vectors);
+                    continue;
+                }
+                Node::Interface(_) => continue,
+            };
+
+            let key = qualify(scope, name);
+            let ty = match self.types.get(&key) {
+                Some(ty) => ty,
+                None => continue,
+            };
+
+            for value in values {
+                let mut bytes = Vec::new();
+                if self.encode(ty, &value, &mut bytes).is_some() {
+                    vectors.push(json!({ "type": key, "value": value, "hex": hex(&bytes) }));
+                }
+            }
+        }
+    }
+
+    /// Value of `ty` when none is given.
+    fn default(&self, ty: &Type) -> Value {
+        match *ty.info() {
+            TypeInfo::Primitive(ref p) => match p.tt {
+                Trait::Bool => json!(false),
+                Trait::Integer => json!(0),
+                Trait::Float => json!(0.0),
+                _ => json!(""),
+            },
+            TypeInfo::Struct(ref s) => self.structure(s, &Map::new()),
+            TypeInfo::Enum(ref e) => e.members.first().map_or(Value::Null, |v| json!(v.name)),
+            TypeInfo::Union(ref u) => u.members.first().map_or(Value::Null, |v| self.variant(v)),
+            TypeInfo::Template(ref t) => match (t.name.as_str(), t.params.as_slice()) {
+                ("array", [p]) => {
+                    let size = t.size.unwrap_or(0) as usize;
+                    Value::Array(vec![self.default(p); size])
+                }
+                ("vector", _) | ("set", _) => json!([]),
+                ("map", _) => json!({}),
+                _ => Value::Null,
+            },
+            TypeInfo::Ref(_) => match self.types.get(&ty.key()) {
+                Some(def) if !def.is_ref() => self.default(def),
+                _ => Value::Null,
+            },
+        }
+    }
+
+    /// Value of a struct with the fields given in `fields`, and the defaults of the others.
+    pub(super) fn structure(&self, s: &Struct, fields: &Map<String, Value>) -> Value {
+        let mut value = Map::new();
+
+        for f in &s.members {
+            let v = match fields.get(&f.name).or(f.value.as_ref()) {
+                Some(v) => self.complete(&f.ty, v),
+                None => self.default(&f.ty),
+            };
+            value.insert(f.name.clone(), v);
+        }
+
+        Value::Object(value)
+    }
+
+    /// Value of a variant of a union, with the default of its type.
+    fn variant(&self, v: &UnionVariant) -> Value {
+        match v.ty {
+            Some(ref ty) => json!({ v.name.clone(): self.default(ty) }),
+            None => json!(v.name),
+        }
+    }
+
+    /// Completes a value given in the definitions with the defaults of what it leaves out.
+    fn complete(&self, ty: &Type, value: &Value) -> Value {
+        match (ty.info(), value) {
+            (TypeInfo::Struct(s), Value::Object(o)) => self.structure(s, o),
+            (TypeInfo::Struct(s), Value::Array(a)) => {
+                let fields = s.members
+                    .iter()
+                    .zip(a.iter())
+                    .map(|(f, v)| (f.name.clone(), v.clone()))
+                    .collect();
+
+                self.structure(s, &fields)
+            }
+            (TypeInfo::Union(u), Value::Object(o)) => {
+                let mut value = Map::new();
+                for (name, payload) in o {
+                    let payload = match u.members.iter().find(|v| v.name == *name) {
+                        Some(UnionVariant { ty: Some(ty), .. }) => self.complete(ty, payload),
+                        _ => payload.clone(),
+                    };
+                    value.insert(name.clone(), payload);
+                }
+
+                Value::Object(value)
+            }
+            (TypeInfo::Template(t), _) => match (t.name.as_str(), t.params.as_slice(), value) {
+                ("vector", [p], Value::Array(a))
+                | ("set", [p], Value::Array(a))
+                | ("array", [p], Value::Array(a)) => {
+                    Value::Array(a.iter().map(|v| self.complete(p, v)).collect())
+                }
+                ("map", [_, p], Value::Object(o)) => Value::Object(
+                    o.iter()
+                        .map(|(k, v)| (k.clone(), self.complete(p, v)))
+                        .collect(),
+                ),
+                ("optional", [p], v) if !v.is_null() => self.complete(p, v),
+                _ => value.clone(),
+            },
+            (TypeInfo::Ref(_), _) => match self.types.get(&ty.key()) {
+                Some(def) if !def.is_ref() => self.complete(def, value),
+                _ => value.clone(),
+            },
+            _ => value.clone(),
+        }
+    }
+
+    /// Encodes `value` of `ty`, or returns `None` if it doesn't fit the type.
+    fn encode(&self, ty: &Type, value: &Value, w: &mut Vec<u8>) -> Option<()> {
+        match *ty.info() {
+            TypeInfo::Primitive(ref p) => primitive(&p.name, value, w),
+            TypeInfo::Enum(ref e) => {
+                let name = value.as_str()?;
+                let v = e.members.iter().find(|v| v.name == name)?;
+
+                self.encode(&e.utype, v.value.as_ref()?, w)
+            }
+            TypeInfo::Struct(ref s) => {
+                let o = value.as_object()?;
+
+                varint(w, s.members.len() as u64);
+                for f in &s.members {
+                    let mut field = Vec::new();
+                    self.encode(&f.ty, o.get(&f.name)?, &mut field)?;
+
+                    varint(w, u64::from(f.id));
+                    varint(w, field.len() as u64);
+                    w.extend_from_slice(&field);
+                }
+
+                Some(())
+            }
+            TypeInfo::Union(ref u) => {
+                let (name, payload) = match *value {
+                    Value::String(ref name) => (name, None),
+                    Value::Object(ref o) if o.len() == 1 => {
+                        let (name, payload) = o.iter().next()?;
+                        (name, Some(payload))
+                    }
+                    _ => return None,
+                };
+                let i = u.members.iter().position(|v| v.name == *name)?;
+
+                varint(w, u64::from(u.members[i].id));
+                match (&u.members[i].ty, payload) {
+                    (Some(ty), Some(payload)) => self.encode(ty, payload, w),
+                    (None, None) => Some(()),
+                    _ => None,
+                }
+            }
+            TypeInfo::Template(ref t) => self.template(t, value, w),
+            TypeInfo::Ref(_) => {
+                let def = self.types.get(&ty.key())?;
+
+                if def.is_ref() {
+                    return None;
+                }
+
+                self.encode(def, value, w)
+            }
+        }
+    }
+
+    fn template(&self, t: &Template, value: &Value, w: &mut Vec<u8>) -> Option<()> {
+        match (t.name.as_str(), t.params.as_slice()) {
+            ("vector", [p]) | ("array", [p]) => {
+                let items = value.as_array()?;
+                if t.size.is_some_and(|n| n != items.len() as u64) {
+                    return None;
+                }
+
+                varint(w, items.len() as u64);
+                for item in items {
+                    self.encode(p, item, w)?;
+                }
+
+                Some(())
+            }
+            ("set", [p]) => {
+                let mut items = Vec::new();
+                for item in value.as_array()? {
+                    let mut bytes = Vec::new();
+                    self.encode(p, item, &mut bytes)?;
+                    items.push(bytes);
+                }
+                items.sort();
+
+                varint(w, items.len() as u64);
+                for item in items {
+                    w.extend_from_slice(&item);
+                }
+
+                Some(())
+            }
+            ("map", [k, v]) => {
+                let mut entries = Vec::new();
+                for (key, value) in value.as_object()? {
+                    // Keys are strings in JSON, whatever their type.
+                    let key = match k.tt() {
+                        Trait::Integer => serde_json::from_str(key).ok()?,
+                        Trait::Bool => Value::Bool(key.parse().ok()?),
+                        _ => Value::String(key.clone()),
+                    };
+
+                    let mut bytes = Vec::new();
+                    self.encode(k, &key, &mut bytes)?;
+                    entries.push((bytes, value));
+                }
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+                varint(w, entries.len() as u64);
+                for (key, value) in entries {
+                    w.extend_from_slice(&key);
+                    self.encode(v, value, w)?;
+                }
+
+                Some(())
+            }
+            ("optional", [p]) => {
+                if value.is_null() {
+                    w.push(0);
+                    return Some(());
+                }
+
+                w.push(1);
+                self.encode(p, value, w)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn primitive(name: &str, value: &Value, w: &mut Vec<u8>) -> Option<()> {
+    match name {
+        "bool" => w.push(value.as_bool()? as u8),
+        "u8" => w.push(u8::try_from(as_int(value)?).ok()?),
+        "i8" => w.push(i8::try_from(as_int(value)?).ok()? as u8),
+        "u16" | "u32" | "u64" => {
+            let n = as_int(value)?;
+            let (_, max) = int_range(name)?;
+            if n < 0 || n > max {
+                return None;
+            }
+
+            varint(w, n as u64);
+        }
+        "i16" | "i32" | "i64" => {
+            let n = as_int(value)?;
+            let (min, max) = int_range(name)?;
+            if n < min || n > max {
+                return None;
+            }
+
+            let n = n as i64;
+            varint(w, ((n << 1) ^ (n >> 63)) as u64);
+        }
+        "f32" => w.extend_from_slice(&(value.as_f64()? as f32).to_le_bytes()),
+        "f64" => w.extend_from_slice(&value.as_f64()?.to_le_bytes()),
+        "string" => {
+            let s = value.as_str()?;
+            varint(w, s.len() as u64);
+            w.extend_from_slice(s.as_bytes());
+        }
+        _ => return None,
+    }
+
+    Some(())
+}
+
+/// Writes an unsigned LEB128 varint.
+fn varint(w: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        w.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    w.push(n as u8);
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
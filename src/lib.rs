@@ -10,7 +10,6 @@ extern crate serde_derive;
 extern crate error_chain;
 #[macro_use]
 extern crate log;
-#[macro_use]
 extern crate tera;
 #[macro_use]
 extern crate lazy_static;
@@ -19,34 +18,149 @@ mod types;
 mod loader;
 mod error;
 mod resolver;
+mod order;
 mod parser;
+mod proto;
 mod generator;
 mod lang;
 mod render;
 mod utils;
+mod value;
+mod diagnostics;
+mod fmt;
+mod compat;
+
+use std::fs;
+use std::path::Path;
 
 use serde_json::to_string_pretty;
 
-use crate::error::{Result, ResultExt};
+use crate::error::ResultExt;
 use crate::generator::Generator;
 
 pub use crate::types::*;
-pub use crate::lang::{register_generator, LangGenerator};
+pub use crate::error::{Error, ErrorKind, Result};
+pub use crate::diagnostics::{Diagnostic, Location, Severity};
+pub use crate::lang::{register_generator, DocFormat, LangGenerator};
+pub use crate::fmt::format;
+pub use crate::compat::{Change, Compat, Report};
+pub use crate::render::{File, Manifest, ManifestEntry, Output};
 use crate::lang::get_generator;
 
-pub fn compile(gen: &str, path: &str, tpath: &str) -> Result<String> {
+#[derive(Clone)]
+pub struct Options {
+    /// The compile stops after this number of errors.
+    pub max_errors: usize,
+    /// Directories to look for imported modules in, if not found next to the importer.
+    pub include: Vec<String>,
+    /// Directory the rendered files are written to. Nothing is written without one.
+    pub out_dir: Option<String>,
+    /// Files to render. The generator's defaults are used if empty.
+    pub outputs: Vec<Output>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            max_errors: diagnostics::DEFAULT_MAX_ERRORS,
+            include: Vec::new(),
+            out_dir: None,
+            outputs: Vec::new(),
+        }
+    }
+}
+
+/// Names of the registered generators, sorted.
+pub fn generators() -> Vec<String> {
+    lang::generator_names()
+}
+
+/// Loads a module with everything it imports, and returns the model without rendering it.
+pub fn generate(gen: &str, path: &str, opts: &Options) -> Result<Model> {
     let langgen = get_generator(gen)?;
     let mut langgen = langgen.lock().unwrap();
-    let mut gen = Generator::new(&mut *langgen);
+    let mut gen = Generator::new(&mut *langgen, opts);
 
     let fullpath = utils::fullpath(path)?;
-    let model = gen.generate(path).chain_err(|| error::error(&fullpath))?;
-    let model = serde_json::to_value(model).map_err(|e| error::pack_error(e))?;
+    gen.generate(path).chain_err(|| error::error(&fullpath))
+}
+
+/// Renders the files for a module without writing them.
+///
+/// Templates are looked up in `tpath` first, then among the ones bundled with the generator.
+pub fn render_files(gen: &str, path: &str, tpath: &str, opts: &Options) -> Result<Vec<File>> {
+    let model = generate(gen, path, opts)?;
+    let model = serde_json::to_value(model).map_err(error::pack_error)?;
 
     debug!(
         "model: {}",
         to_string_pretty(&model).unwrap_or("".to_string())
     );
 
-    render::render(path, tpath, model)
+    let langgen = get_generator(gen)?;
+    let langgen = langgen.lock().unwrap();
+    let embedded = langgen.embedded_templates();
+    let outputs = if opts.outputs.is_empty() {
+        langgen.outputs()
+    } else {
+        opts.outputs.clone()
+    };
+
+    render::render(tpath, &embedded, &model, &outputs)
+}
+
+/// Renders the files for a module with the default options, which write nothing.
+pub fn compile(gen: &str, path: &str, tpath: &str) -> Result<Manifest> {
+    compile_with(gen, path, tpath, &Options::default())
+}
+
+/// Renders the files for a module, and writes them into the output directory if there's one.
+pub fn compile_with(gen: &str, path: &str, tpath: &str, opts: &Options) -> Result<Manifest> {
+    let files = render_files(gen, path, tpath, opts)?;
+
+    match opts.out_dir {
+        Some(ref out_dir) => render::write(out_dir, files),
+        None => Ok(render::list(files)),
+    }
+}
+
+/// Writes the documentation of a module and the modules it imports: a page for each
+/// module, and an index page.
+pub fn document(path: &str, tpath: &str, format: DocFormat, opts: &Options) -> Result<Manifest> {
+    let opts = Options {
+        outputs: format.outputs(),
+        ..opts.clone()
+    };
+
+    compile_with("doc", path, tpath, &opts)
+}
+
+/// Compares two versions of definitions, each a file or a directory of files, and
+/// tells which changes break peers or code built against the old version.
+pub fn compat(old: &str, new: &str, opts: &Options) -> Result<Report> {
+    let old = compat::Tree::new(&load(old, opts)?);
+    let new = compat::Tree::new(&load(new, opts)?);
+
+    Ok(compat::compare(&old, &new))
+}
+
+/// Models of a file, or of each `.rpc` file of a directory.
+fn load(path: &str, opts: &Options) -> Result<Vec<Model>> {
+    if !Path::new(path).is_dir() {
+        return Ok(vec![generate("null", path, opts)?]);
+    }
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(path).map_err(error::file_error)? {
+        let file = entry.map_err(error::file_error)?.path();
+        if file.extension().is_some_and(|ext| ext == "rpc") {
+            files.push(file);
+        }
+    }
+    files.sort();
+
+    files
+        .iter()
+        .map(|file| generate("null", &file.to_string_lossy(), opts))
+        .collect()
 }
@@ -7,34 +7,58 @@ use std::path::{Path, PathBuf};
 
 pub struct Loader {
     directory: Vec<PathBuf>,
+    include: Vec<PathBuf>,
 }
 
 impl Loader {
-    pub fn new() -> Self {
+    pub fn new(include: &[String]) -> Self {
         Self {
             directory: Vec::new(),
+            include: include.iter().map(PathBuf::from).collect(),
         }
     }
 
+    /// Finds a file relative to the current directory, or else to the include directories.
+    fn find(&self, path: &str) -> PathBuf {
+        let local = Path::new(&self.current_dir()).join(path);
+
+        if local.exists() || self.directory.is_empty() {
+            return local;
+        }
+
+        self.include
+            .iter()
+            .map(|dir| dir.join(path))
+            .find(|p| p.exists())
+            .unwrap_or(local)
+    }
+
+    pub fn exists(&self, path: &str) -> bool {
+        self.find(path).exists()
+    }
+
     pub fn load(&self, path: &str) -> Result<String> {
         debug!("Loading file: {}", path);
 
-        let path = Path::new(&self.current_dir()).join(path);
+        let path = self.find(path);
 
         debug!("Loading path: {}", path.to_string_lossy());
 
-        let mut file = File::open(path).map_err(|e| error::file_error(e))?;
+        let mut file = File::open(path).map_err(error::file_error)?;
         let mut contents = String::new();
         file.read_to_string(&mut contents)
-            .map_err(|e| error::file_error(e))?;
+            .map_err(error::file_error)?;
 
         Ok(contents)
     }
 
-    pub fn enter_dir(&mut self, dir: &str) -> Result<()> {
-        let path = Path::new(&self.current_dir()).join(dir);
+    /// Canonical path of the file `path` is found at.
+    pub fn canonical(&self, path: &str) -> Result<PathBuf> {
+        self.find(path).canonicalize().map_err(error::file_error)
+    }
 
-        let path = path.canonicalize().map_err(|e| error::file_error(e))?;
+    pub fn enter_dir(&mut self, dir: &str) -> Result<()> {
+        let path = self.canonical(dir)?;
 
         self.directory.push(path);
 
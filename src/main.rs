@@ -1,19 +1,476 @@
-#[macro_use]
-extern crate log;
+extern crate clap;
 extern crate env_logger;
 extern crate error_chain;
 extern crate rrpc;
 extern crate serde_json;
 
+use std::fs;
+use std::process;
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use error_chain::ChainedError;
 
+/// The input compiled, and the output written.
+const EXIT_OK: i32 = 0;
+/// The input has errors, the output couldn't be written, or a check failed.
+const EXIT_ERROR: i32 = 1;
+/// The command line is invalid.
+const EXIT_USAGE: i32 = 2;
+
+fn files<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("FILES")
+        .help("Input .rpc files")
+        .required(true)
+        .multiple(true)
+}
+
+fn generator<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("generator")
+        .short("g")
+        .long("generator")
+        .value_name("NAME")
+        .default_value("null")
+        .help("Language generator to use (see list-generators)")
+}
+
+fn include<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("include")
+        .short("I")
+        .long("include")
+        .value_name("DIR")
+        .multiple(true)
+        .number_of_values(1)
+        .help("Directory to search imported modules in")
+}
+
+fn max_errors<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("max-errors")
+        .long("max-errors")
+        .value_name("N")
+        .help("Stops after this number of errors")
+}
+
+fn output<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("output")
+        .short("o")
+        .long("output")
+        .value_name("PATH")
+        .help("File to write to, instead of stdout")
+}
+
+fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new("rrpc")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Generates code from rpc definitions")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("log-level")
+                .long("log-level")
+                .value_name("LEVEL")
+                .global(true)
+                .possible_values(&["off", "error", "warn", "info", "debug", "trace"])
+                .help("Log level, overriding RUST_LOG"),
+        )
+        .subcommand(
+            SubCommand::with_name("compile")
+                .about("Renders templates for the input files")
+                .arg(files())
+                .arg(generator())
+                .arg(
+                    Arg::with_name("templates")
+                        .short("t")
+                        .long("templates")
+                        .value_name("DIR")
+                        .default_value("templates")
+                        .help("Directory of the templates"),
+                )
+                .arg(
+                    Arg::with_name("out-dir")
+                        .short("o")
+                        .long("out-dir")
+                        .value_name("DIR")
+                        .help("Directory to write the files to, instead of printing them"),
+                )
+                .arg(
+                    Arg::with_name("entry")
+                        .short("e")
+                        .long("entry")
+                        .value_name("TEMPLATE=PATTERN")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Renders a file named by PATTERN from TEMPLATE for the input"),
+                )
+                .arg(
+                    Arg::with_name("module-entry")
+                        .short("m")
+                        .long("module-entry")
+                        .value_name("TEMPLATE=PATTERN")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Renders a file named by PATTERN from TEMPLATE for each module"),
+                )
+                .arg(include())
+                .arg(max_errors()),
+        )
+        .subcommand(
+            SubCommand::with_name("check")
+                .about("Checks the input files without rendering anything")
+                .arg(files())
+                .arg(generator())
+                .arg(include())
+                .arg(max_errors()),
+        )
+        .subcommand(
+            SubCommand::with_name("dump-model")
+                .about("Prints the model passed to templates as json")
+                .arg(files())
+                .arg(generator())
+                .arg(output())
+                .arg(include())
+                .arg(max_errors()),
+        )
+        .subcommand(
+            SubCommand::with_name("doc")
+                .about("Writes the documentation of the input files")
+                .arg(files())
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .possible_values(&["html", "markdown"])
+                        .default_value("html")
+                        .help("Format of the pages"),
+                )
+                .arg(
+                    Arg::with_name("templates")
+                        .short("t")
+                        .long("templates")
+                        .value_name("DIR")
+                        .help("Directory of templates overriding the bundled ones"),
+                )
+                .arg(
+                    Arg::with_name("out-dir")
+                        .short("o")
+                        .long("out-dir")
+                        .value_name("DIR")
+                        .default_value("doc")
+                        .help("Directory to write the pages to"),
+                )
+                .arg(include())
+                .arg(max_errors()),
+        )
+        .subcommand(
+            SubCommand::with_name("fmt")
+                .about("Formats the input files in place")
+                .arg(files())
+                .arg(
+                    Arg::with_name("check")
+                        .long("check")
+                        .help("Only reports the files which aren't formatted"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("compat")
+                .about("Reports the changes between two versions of definitions")
+                .arg(
+                    Arg::with_name("OLD")
+                        .help("Old .rpc file, or directory of them")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("NEW")
+                        .help("New .rpc file, or directory of them")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .value_name("PATH")
+                        .help("File to also write the report to as json"),
+                )
+                .arg(include())
+                .arg(max_errors()),
+        )
+        .subcommand(
+            SubCommand::with_name("list-generators").about("Lists the available generators"),
+        )
+}
+
+fn options(m: &ArgMatches) -> Result<rrpc::Options, String> {
+    let mut opts = rrpc::Options::default();
+
+    if let Some(n) = m.value_of("max-errors") {
+        opts.max_errors = n.parse()
+            .map_err(|e| format!("invalid --max-errors: {}", e))?;
+    }
+
+    if let Some(dirs) = m.values_of("include") {
+        opts.include = dirs.map(|d| d.to_string()).collect();
+    }
+
+    if let Some(dir) = m.value_of("out-dir") {
+        opts.out_dir = Some(dir.to_string());
+    }
+
+    for (arg, per_module) in &[("entry", false), ("module-entry", true)] {
+        for entry in m.values_of(arg).into_iter().flatten() {
+            let mut parts = entry.splitn(2, '=');
+
+            match (parts.next(), parts.next()) {
+                (Some(template), Some(pattern)) if !template.is_empty() && !pattern.is_empty() => {
+                    opts.outputs
+                        .push(rrpc::Output::new(template, pattern, *per_module));
+                }
+                _ => {
+                    return Err(format!(
+                        "invalid --{}: {} (expected TEMPLATE=PATTERN)",
+                        arg, entry
+                    ))
+                }
+            }
+        }
+    }
+
+    Ok(opts)
+}
+
+fn write(m: &ArgMatches, text: &str) -> i32 {
+    match m.value_of("output") {
+        Some(path) => match fs::write(path, text) {
+            Ok(()) => EXIT_OK,
+            Err(e) => {
+                eprintln!("error: couldn't write {}: {}", path, e);
+                EXIT_ERROR
+            }
+        },
+        None => {
+            print!("{}", text);
+            EXIT_OK
+        }
+    }
+}
+
+fn compile(m: &ArgMatches, opts: &rrpc::Options) -> i32 {
+    let gen = m.value_of("generator").unwrap();
+    let tpath = format!("{}/**/*", m.value_of("templates").unwrap());
+    let mut code = EXIT_OK;
+
+    for path in m.values_of("FILES").unwrap() {
+        let result = if m.is_present("out-dir") {
+            rrpc::compile_with(gen, path, &tpath, opts).map(|manifest| {
+                for file in manifest.files {
+                    println!("{}", file.path);
+                }
+            })
+        } else {
+            rrpc::render_files(gen, path, &tpath, opts).map(|files| {
+                for file in files {
+                    print!("{}", file.contents);
+                }
+            })
+        };
+
+        if let Err(e) = result {
+            eprintln!("{}", e.display_chain());
+            code = EXIT_ERROR;
+        }
+    }
+
+    code
+}
+
+fn check(m: &ArgMatches, opts: &rrpc::Options) -> i32 {
+    let gen = m.value_of("generator").unwrap();
+    let mut code = EXIT_OK;
+
+    for path in m.values_of("FILES").unwrap() {
+        if let Err(e) = rrpc::generate(gen, path, opts) {
+            eprintln!("{}", e.display_chain());
+            code = EXIT_ERROR;
+        }
+    }
+
+    code
+}
+
+fn dump_model(m: &ArgMatches, opts: &rrpc::Options) -> i32 {
+    let gen = m.value_of("generator").unwrap();
+    let mut models = Vec::new();
+
+    for path in m.values_of("FILES").unwrap() {
+        match rrpc::generate(gen, path, opts) {
+            Ok(model) => models.push(model),
+            Err(e) => {
+                eprintln!("{}", e.display_chain());
+                return EXIT_ERROR;
+            }
+        }
+    }
+
+    let text = if models.len() == 1 {
+        serde_json::to_string_pretty(&models[0])
+    } else {
+        serde_json::to_string_pretty(&models)
+    };
+
+    match text {
+        Ok(text) => write(m, &format!("{}\n", text)),
+        Err(e) => {
+            eprintln!("error: couldn't serialize the model: {}", e);
+            EXIT_ERROR
+        }
+    }
+}
+
+fn doc(m: &ArgMatches, opts: &rrpc::Options) -> i32 {
+    let format = match m.value_of("format") {
+        Some("markdown") => rrpc::DocFormat::Markdown,
+        _ => rrpc::DocFormat::Html,
+    };
+    let tpath = m.value_of("templates")
+        .map(|dir| format!("{}/**/*", dir))
+        .unwrap_or_default();
+    let mut code = EXIT_OK;
+
+    for path in m.values_of("FILES").unwrap() {
+        match rrpc::document(path, &tpath, format, opts) {
+            Ok(manifest) => {
+                for file in manifest.files {
+                    println!("{}", file.path);
+                }
+            }
+            Err(e) => {
+                eprintln!("{}", e.display_chain());
+                code = EXIT_ERROR;
+            }
+        }
+    }
+
+    code
+}
+
+fn fmt(m: &ArgMatches) -> i32 {
+    let mut code = EXIT_OK;
+
+    for path in m.values_of("FILES").unwrap() {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("error: couldn't read {}: {}", path, e);
+                code = EXIT_ERROR;
+                continue;
+            }
+        };
+
+        let formatted = match rrpc::format(&source) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                eprintln!("{}: {}", path, e.display_chain());
+                code = EXIT_ERROR;
+                continue;
+            }
+        };
+
+        if formatted == source {
+            continue;
+        }
+
+        if m.is_present("check") {
+            println!("{}", path);
+            code = EXIT_ERROR;
+        } else if let Err(e) = fs::write(path, formatted) {
+            eprintln!("error: couldn't write {}: {}", path, e);
+            code = EXIT_ERROR;
+        }
+    }
+
+    code
+}
+
+/// Prints the changes, and fails if any of them is breaking.
+fn compat(m: &ArgMatches, opts: &rrpc::Options) -> i32 {
+    let old = m.value_of("OLD").unwrap();
+    let new = m.value_of("NEW").unwrap();
+
+    let report = match rrpc::compat(old, new, opts) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("{}", e.display_chain());
+            return EXIT_ERROR;
+        }
+    };
+
+    print!("{}", report);
+
+    if let Some(path) = m.value_of("json") {
+        let text = match serde_json::to_string_pretty(&report) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("error: couldn't serialize the report: {}", e);
+                return EXIT_ERROR;
+            }
+        };
+
+        if let Err(e) = fs::write(path, format!("{}\n", text)) {
+            eprintln!("error: couldn't write {}: {}", path, e);
+            return EXIT_ERROR;
+        }
+    }
+
+    if report.breaking {
+        EXIT_ERROR
+    } else {
+        EXIT_OK
+    }
+}
+
 fn main() {
-    env_logger::init();
+    let matches = match app().get_matches_safe() {
+        Ok(matches) => matches,
+        Err(e) => match e.kind {
+            clap::ErrorKind::HelpDisplayed | clap::ErrorKind::VersionDisplayed => {
+                println!("{}", e.message);
+                process::exit(EXIT_OK);
+            }
+            _ => {
+                eprintln!("{}", e.message);
+                process::exit(EXIT_USAGE);
+            }
+        },
+    };
+
+    let (name, m) = matches.subcommand();
+    let m = m.unwrap();
+
+    match m.value_of("log-level") {
+        Some(level) => env_logger::Builder::new().parse(level).init(),
+        None => env_logger::init(),
+    }
+
+    let opts = match options(m) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(EXIT_USAGE);
+        }
+    };
 
-    let text = match rrpc::compile("null", "examples/init.rpc", "examples/templates/**/*") {
-        Ok(text) => text,
-        Err(e) => return error!("{}", e.display_chain().to_string()),
+    let code = match name {
+        "compile" => compile(m, &opts),
+        "check" => check(m, &opts),
+        "dump-model" => dump_model(m, &opts),
+        "doc" => doc(m, &opts),
+        "fmt" => fmt(m),
+        "compat" => compat(m, &opts),
+        "list-generators" => {
+            for name in rrpc::generators() {
+                println!("{}", name);
+            }
+            EXIT_OK
+        }
+        _ => unreachable!("unknown subcommand {}", name),
     };
 
-    println!("{}", text);
+    process::exit(code);
 }
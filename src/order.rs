@@ -0,0 +1,261 @@
+use pest::iterators::Pair;
+
+use super::parser::{get, Rule};
+use super::error::{self, Result};
+use super::resolver::Resolver;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mark {
+    New,
+    Visiting,
+    Done,
+}
+
+/// A type declared in one of the items, or in a module nested in them.
+struct Leaf<'a> {
+    key: String,
+    /// Index of the item it's declared in.
+    top: usize,
+    pair: Pair<'a, Rule>,
+}
+
+/// Returns the indices of `items` in the order they need to be generated.
+///
+/// Every item is placed after the items it refers to, except where the
+/// references form a cycle. Such a cycle is only allowed if it goes through
+/// indirection, e.g. `vector` or `optional`; otherwise the type would have
+/// infinite size.
+pub fn sort<'a>(resolver: &Resolver, items: &[Pair<'a, Rule>]) -> Result<Vec<usize>> {
+    let mut keys = Vec::new();
+
+    for p in items {
+        keys.push(resolver.qualified(get(p, Rule::Identifier)?.as_str()));
+    }
+
+    let mut deps = Vec::new();
+
+    for (i, p) in items.iter().enumerate() {
+        let scope = if p.as_rule() == Rule::Module {
+            keys[i].clone()
+        } else {
+            resolver.current_ns()
+        };
+
+        let mut refs = Vec::new();
+        collect(resolver, p, &scope, false, &mut refs)?;
+
+        deps.push(
+            refs.into_iter()
+                .filter_map(|(_, key, _)| {
+                    let to = owner(items, &keys, &key)?;
+
+                    // References within a module are ordered when the module is generated.
+                    if to == i && is_module(&items[i]) {
+                        return None;
+                    }
+
+                    Some(to)
+                })
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    check(resolver, items, &keys)?;
+
+    let mut marks = vec![Mark::New; items.len()];
+    let mut order = Vec::new();
+
+    for i in 0..items.len() {
+        if marks[i] == Mark::New {
+            visit(i, &deps, &mut marks, &mut order);
+        }
+    }
+
+    Ok(order)
+}
+
+/// Places `i` after the items it refers to, leaving cycles in the order they're found.
+fn visit(i: usize, deps: &[Vec<usize>], marks: &mut Vec<Mark>, order: &mut Vec<usize>) {
+    marks[i] = Mark::Visiting;
+
+    for &to in &deps[i] {
+        if marks[to] == Mark::New {
+            visit(to, deps, marks, order);
+        }
+    }
+
+    marks[i] = Mark::Done;
+    order.push(i);
+}
+
+/// Fails on a cycle of references without indirection, whatever the order of the items.
+///
+/// The types declared in the items, and in the modules nested in them, are split into
+/// strongly connected components by their direct references, and every reference within
+/// a component is part of such a cycle. A cycle which stays inside a module is left to
+/// the check of the module, so that it's only reported once.
+fn check(resolver: &Resolver, items: &[Pair<Rule>], keys: &[String]) -> Result<()> {
+    let mut leaves = Vec::new();
+
+    for (i, p) in items.iter().enumerate() {
+        declared(p, &keys[i], i, &mut leaves);
+    }
+
+    let mut direct = Vec::new();
+
+    for leaf in &leaves {
+        let scope = leaf.key.rfind("::").map_or("", |at| &leaf.key[..at]);
+
+        let mut refs = Vec::new();
+        collect(resolver, &leaf.pair, scope, false, &mut refs)?;
+
+        direct.push(
+            refs.into_iter()
+                .filter(|&(_, _, indirect)| !indirect)
+                .filter_map(|(pair, key, _)| {
+                    let to = leaves.iter().position(|l| l.key == key)?;
+                    Some((to, pair))
+                })
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    let mut components = Components {
+        index: vec![None; leaves.len()],
+        low: vec![0; leaves.len()],
+        stack: Vec::new(),
+        next: 0,
+        of: vec![0; leaves.len()],
+        count: 0,
+    };
+
+    for i in 0..leaves.len() {
+        if components.index[i].is_none() {
+            components.visit(i, &direct);
+        }
+    }
+
+    for (i, leaf) in leaves.iter().enumerate() {
+        let cycle = direct[i].iter().find(|&&(to, _)| {
+            components.of[to] == components.of[i]
+                && (leaves[to].top != leaf.top || !is_module(&items[leaf.top]))
+        });
+
+        if let Some((_, pair)) = cycle {
+            return Err(error::infinite_type(pair));
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds the types declared by `p`, whose path is `key`, with those of the modules in it.
+///
+/// Items without a name were already reported when they were declared, so they're skipped.
+fn declared<'a>(p: &Pair<'a, Rule>, key: &str, top: usize, out: &mut Vec<Leaf<'a>>) {
+    if !is_module(p) {
+        out.push(Leaf { key: key.into(), top, pair: p.clone() });
+        return;
+    }
+
+    for child in p.clone().into_inner() {
+        match child.as_rule() {
+            Rule::Struct | Rule::Enum | Rule::Union | Rule::Interface | Rule::Module => {
+                if let Ok(ident) = get(&child, Rule::Identifier) {
+                    declared(&child, &format!("{}::{}", key, ident.as_str()), top, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Tarjan's algorithm, numbering the strongly connected components of the items.
+struct Components {
+    index: Vec<Option<usize>>,
+    low: Vec<usize>,
+    stack: Vec<usize>,
+    next: usize,
+    /// Component of each item.
+    of: Vec<usize>,
+    count: usize,
+}
+
+impl Components {
+    fn visit(&mut self, i: usize, direct: &[Vec<(usize, Pair<Rule>)>]) {
+        self.index[i] = Some(self.next);
+        self.low[i] = self.next;
+        self.next += 1;
+        self.stack.push(i);
+
+        for &(to, _) in &direct[i] {
+            match self.index[to] {
+                None => {
+                    self.visit(to, direct);
+                    self.low[i] = self.low[i].min(self.low[to]);
+                }
+                Some(index) if self.stack.contains(&to) => {
+                    self.low[i] = self.low[i].min(index);
+                }
+                Some(_) => {}
+            }
+        }
+
+        if Some(self.low[i]) == self.index[i] {
+            while let Some(j) = self.stack.pop() {
+                self.of[j] = self.count;
+                if j == i {
+                    break;
+                }
+            }
+            self.count += 1;
+        }
+    }
+}
+
+fn collect<'a>(
+    resolver: &Resolver,
+    p: &Pair<'a, Rule>,
+    scope: &str,
+    indirect: bool,
+    refs: &mut Vec<(Pair<'a, Rule>, String, bool)>,
+) -> Result<()> {
+    for child in p.clone().into_inner() {
+        match child.as_rule() {
+            Rule::Module => {
+                let ident = get(&child, Rule::Identifier)?;
+                let scope = if scope.is_empty() {
+                    ident.as_str().to_string()
+                } else {
+                    format!("{}::{}", scope, ident.as_str())
+                };
+
+                collect(resolver, &child, &scope, indirect, refs)?;
+            }
+            Rule::Template => {
+                let ident = get(&child, Rule::Identifier)?;
+                let indirect = indirect || resolver.is_indirect(ident.as_str());
+
+                collect(resolver, &child, scope, indirect, refs)?;
+            }
+            Rule::Type => {
+                if let Some(key) = resolver.lookup(scope, child.as_str()) {
+                    refs.push((child, key, indirect));
+                }
+            }
+            _ => collect(resolver, &child, scope, indirect, refs)?,
+        }
+    }
+
+    Ok(())
+}
+
+fn owner(items: &[Pair<Rule>], keys: &[String], key: &str) -> Option<usize> {
+    keys.iter().enumerate().position(|(i, k)| {
+        k == key || (is_module(&items[i]) && key.starts_with(&format!("{}::", k)))
+    })
+}
+
+fn is_module(p: &Pair<Rule>) -> bool {
+    p.as_rule() == Rule::Module
+}
@@ -9,11 +9,8 @@ use super::error::{self, Result};
 #[grammar = "rpc.pest"]
 struct RpcParser;
 
-pub fn get<'a>(p: &Pair<'a, Rule>, rule: Rule) -> Pair<'a, Rule> {
-    match get_opt(p, rule) {
-        Some(p) => p,
-        None => panic!("missing {:?} in {}", rule, p.as_str()),
-    }
+pub fn get<'a>(p: &Pair<'a, Rule>, rule: Rule) -> Result<Pair<'a, Rule>> {
+    get_opt(p, rule).ok_or_else(|| error::missing_token(p, rule))
 }
 
 pub fn get_opt<'a>(p: &Pair<'a, Rule>, rule: Rule) -> Option<Pair<'a, Rule>> {
@@ -41,7 +38,7 @@ pub fn get_comment<'a>(p: &'a Pair<Rule>) -> Option<&'a str> {
 }
 
 pub fn parse<'a>(s: &'a str) -> Result<Pairs<'a, Rule>> {
-    RpcParser::parse(Rule::File, s).map_err(|e| error::parse_error(e))
+    RpcParser::parse(Rule::File, s).map_err(error::parse_error)
 }
 
 pub fn parse_value(p: &Pair<Rule>) -> Result<Option<Value>> {
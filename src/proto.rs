@@ -0,0 +1,714 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use pest::Parser;
+use pest::iterators::Pair;
+
+use super::error::{self, Error, Result};
+
+#[derive(Parser)]
+#[grammar = "proto.pest"]
+struct ProtoParser;
+
+/// The file `google.protobuf.Empty` is imported from.
+const EMPTY_PROTO: &str = "google/protobuf/empty.proto";
+const EMPTY: &str = "google.protobuf.Empty";
+
+/// Translates a proto3 file into the definitions of an rpc file.
+///
+/// Messages become structs and enums enums with an `i32` underlying type. Nested types
+/// are moved after the other definitions, named after the messages they're in, e.g.
+/// `Outer_Inner`, and each `oneof` becomes a union held by an optional field, with the
+/// field numbers as the ordinals of its variants. Services become interfaces with a
+/// function taking the request for each rpc, and `google.protobuf.Empty` is a unit
+/// variant in a `oneof`, and no arguments or no return value in an rpc.
+///
+/// Definitions are kept on the lines they're on in the proto file where possible, so
+/// that the errors found in the translation point to the proto file.
+///
+/// Imported files are read with `load`, by their paths in the `import` statements,
+/// to find the types they define.
+pub fn translate<F>(source: &str, load: F) -> Result<Translation>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let pairs = ProtoParser::parse(Rule::Proto, source).map_err(error::proto_parse_error)?;
+
+    let mut translator = Translator {
+        source,
+        load,
+        package: String::new(),
+        types: HashMap::new(),
+        deferred: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    let items = pairs.collect::<Vec<_>>();
+    translator.syntax(&items);
+
+    // Names are resolved relative to the package.
+    for p in &items {
+        if p.as_rule() == Rule::Package {
+            translator.package = inner(p, Rule::FullIdent)
+                .map(|p| p.as_str().to_string())
+                .unwrap_or_default();
+        }
+    }
+
+    let prefix = translator.package.clone();
+    collect(&items, &prefix, "", "", &mut translator.types);
+
+    let mut lines = Vec::new();
+    for p in &items {
+        match p.as_rule() {
+            Rule::Import => translator.import(p, &mut lines),
+            Rule::Message => {
+                let scope = prefix.clone();
+                translator.message(p, &scope, "", &mut lines);
+            }
+            Rule::Enum => translator.enumeration(p, "", &mut lines),
+            Rule::Service => translator.service(p, &mut lines),
+            Rule::Extend => translator.unsupported(p, "extensions aren't supported"),
+            _ => {}
+        }
+    }
+
+    let mut writer = Writer::new();
+    writer.write(lines, true);
+    for lines in std::mem::take(&mut translator.deferred) {
+        writer.write(lines, false);
+    }
+
+    Ok(Translation {
+        source: writer.out,
+        package: translator.package,
+        errors: translator.errors,
+    })
+}
+
+/// A proto file translated into the definitions of an rpc file.
+pub struct Translation {
+    pub source: String,
+    /// Package of the proto file, which the proto exports of the definitions keep.
+    pub package: String,
+    /// Errors found in constructs which can't be translated, which are left out.
+    pub errors: Vec<Error>,
+}
+
+/// Lines of the translation, with the lines of the proto file they're for.
+type Lines = Vec<(usize, String)>;
+
+struct Translator<'s, F> {
+    source: &'s str,
+    load: F,
+    package: String,
+    /// Paths in the translation of the types this file can refer to, by their full names.
+    types: HashMap<String, String>,
+    /// Definitions of nested types and `oneof`s, which are written after the others.
+    deferred: Vec<Lines>,
+    errors: Vec<Error>,
+}
+
+impl<'s, F> Translator<'s, F>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    fn unsupported<T: ToString>(&mut self, p: &Pair<Rule>, message: T) {
+        self.errors.push(error::proto_error(p, message));
+    }
+
+    /// Checks the file is a proto3 one.
+    fn syntax(&mut self, items: &[Pair<'s, Rule>]) {
+        let first = match items.first() {
+            Some(p) => p,
+            None => return,
+        };
+
+        if first.as_rule() != Rule::Syntax {
+            return self.unsupported(
+                first,
+                "missing `syntax = \"proto3\";`, which makes it a proto2 file, \
+                 only proto3 is supported",
+            );
+        }
+
+        let value = inner(first, Rule::StrLit).map(|p| unquote(p.as_str()));
+        if first.as_str().starts_with("edition") {
+            self.unsupported(first, "editions aren't supported, only proto3 is");
+        } else if value.as_deref() != Some("proto3") {
+            self.unsupported(
+                first,
+                format!("{} isn't supported, only proto3 is", value.unwrap_or_default()),
+            );
+        }
+    }
+
+    fn import(&mut self, p: &Pair<'s, Rule>, lines: &mut Lines) {
+        if let Some(kind) = inner(p, Rule::ImportKind) {
+            return self.unsupported(&kind, format!("{} imports aren't supported", kind.as_str()));
+        }
+
+        let path = match inner(p, Rule::StrLit) {
+            Some(s) => unquote(s.as_str()),
+            None => return,
+        };
+
+        if path == EMPTY_PROTO {
+            return;
+        }
+        if path.starts_with("google/protobuf/") {
+            return self.unsupported(p, format!("well-known types aren't supported: {}", path));
+        }
+
+        let ns = match namespace(&path) {
+            Some(ns) => ns,
+            None => {
+                return self.unsupported(
+                    p,
+                    format!("import needs to be a path of identifiers ending in .proto: {}", path),
+                )
+            }
+        };
+
+        // A file which can't be read or parsed is reported when it's loaded as a module.
+        if let Some(contents) = (self.load)(&path) {
+            if let Ok(pairs) = ProtoParser::parse(Rule::Proto, &contents) {
+                let items = pairs.collect::<Vec<_>>();
+                let package = items
+                    .iter()
+                    .find(|p| p.as_rule() == Rule::Package)
+                    .and_then(|p| inner(p, Rule::FullIdent))
+                    .map(|p| p.as_str().to_string())
+                    .unwrap_or_default();
+
+                collect(&items, &package, "", &ns, &mut self.types);
+            }
+        }
+
+        lines.push((line(p), format!("use {};", ns)));
+    }
+
+    /// Translates a message of the scope `scope`, named `prefix` followed by its name.
+    fn message(&mut self, p: &Pair<'s, Rule>, scope: &str, prefix: &str, lines: &mut Lines) {
+        let ident = match inner(p, Rule::Ident) {
+            Some(ident) => ident,
+            None => return,
+        };
+        let name = format!("{}{}", prefix, ident.as_str());
+        let scope = join(scope, ident.as_str());
+        let nested = format!("{}_", name);
+
+        self.comment(p, "", lines);
+        lines.push((line(p), format!("struct {} {{", name)));
+
+        for item in p.clone().into_inner() {
+            match item.as_rule() {
+                Rule::Field => self.field(&item, &scope, lines),
+                Rule::MapField => self.map_field(&item, &scope, lines),
+                Rule::Reserved => self.reserved(&item, lines),
+                Rule::OneOf => self.oneof(&item, &scope, &name, lines),
+                Rule::Message => {
+                    let mut def = Vec::new();
+                    self.message(&item, &scope, &nested, &mut def);
+                    self.deferred.push(def);
+                }
+                Rule::Enum => {
+                    let mut def = Vec::new();
+                    self.enumeration(&item, &nested, &mut def);
+                    self.deferred.push(def);
+                }
+                Rule::Group => self.unsupported(&item, "groups are proto2, which isn't supported"),
+                Rule::Extensions | Rule::Extend => {
+                    self.unsupported(&item, "extensions aren't supported")
+                }
+                _ => {}
+            }
+        }
+
+        lines.push((end_line(p), "}".into()));
+    }
+
+    fn field(&mut self, p: &Pair<'s, Rule>, scope: &str, lines: &mut Lines) {
+        let label = inner(p, Rule::Label).map(|l| l.as_str());
+        let ty = match self.field_type(p, scope) {
+            Some(ty) => ty,
+            None => return,
+        };
+
+        let ty = match label {
+            Some("repeated") => format!("vector<{}>", ty),
+            Some("optional") => format!("optional<{}>", ty),
+            Some(_) => {
+                return self.unsupported(p, "required fields are proto2, which isn't supported")
+            }
+            None => ty,
+        };
+
+        self.member(p, ty, lines);
+    }
+
+    fn map_field(&mut self, p: &Pair<'s, Rule>, scope: &str, lines: &mut Lines) {
+        let types = p.clone()
+            .into_inner()
+            .filter(|p| p.as_rule() == Rule::TypeName)
+            .collect::<Vec<_>>();
+
+        let mut params = Vec::new();
+        for ty in &types {
+            match self.resolve(ty, scope) {
+                Some(Some(ty)) => params.push(ty),
+                Some(None) => return self.unsupported(ty, empty_usage()),
+                None => return,
+            }
+        }
+
+        self.member(p, format!("map<{}>", params.join(", ")), lines);
+    }
+
+    /// Translates the reserved numbers or names of a message.
+    fn reserved(&mut self, p: &Pair<'s, Rule>, lines: &mut Lines) {
+        let mut items = Vec::new();
+
+        for item in p.clone().into_inner() {
+            match item.as_rule() {
+                Rule::Range => {
+                    let bounds = item.clone()
+                        .into_inner()
+                        .map(|n| integer(n.as_str()))
+                        .collect::<Option<Vec<_>>>();
+                    let (start, end) = match bounds.as_deref() {
+                        Some(&[start]) if item.as_str().ends_with("max") => (start, MAX_NUMBER),
+                        Some(&[start]) => (start, start),
+                        Some(&[start, end]) => (start, end),
+                        _ => return self.unsupported(&item, "number doesn't fit in an int32"),
+                    };
+
+                    items.push(if start == end {
+                        start.to_string()
+                    } else {
+                        format!("{} to {}", start, end)
+                    });
+                }
+                Rule::StrLit => items.push(unquote(item.as_str())),
+                _ => {}
+            }
+        }
+
+        lines.push((line(p), format!("    reserved {};", items.join(", "))));
+    }
+
+    /// Translates a `oneof` into a field holding a union, which is deferred.
+    fn oneof(&mut self, p: &Pair<'s, Rule>, scope: &str, message: &str, lines: &mut Lines) {
+        let ident = match inner(p, Rule::Ident) {
+            Some(ident) => ident,
+            None => return,
+        };
+        let name = format!("{}_{}", message, ident.as_str());
+        // The field takes the lowest number of the oneof, which no other field has.
+        let mut number = None;
+
+        let mut def = Vec::new();
+        self.comment(p, "", &mut def);
+        def.push((line(p), format!("union {} {{", name)));
+
+        for item in p.clone().into_inner() {
+            match item.as_rule() {
+                Rule::OneOfField => {
+                    let ident = match inner(&item, Rule::Ident) {
+                        Some(ident) => ident,
+                        None => continue,
+                    };
+                    let ty = match inner(&item, Rule::TypeName) {
+                        Some(ty) => self.resolve(&ty, scope),
+                        None => continue,
+                    };
+                    let n = inner(&item, Rule::IntLit).and_then(|n| integer(n.as_str()));
+                    if let Some(n) = n {
+                        number = Some(number.map_or(n, |m: i32| m.min(n)));
+                    }
+
+                    // Variants keep the numbers of the fields, which are their tags.
+                    let variant = format!("    {}{}", ordinal(n), ident.as_str());
+                    self.comment(&item, "    ", &mut def);
+                    match ty {
+                        Some(Some(ty)) => def.push((line(&item), format!("{}: {},", variant, ty))),
+                        Some(None) => def.push((line(&item), format!("{},", variant))),
+                        None => {}
+                    }
+                }
+                Rule::Group => self.unsupported(&item, "groups are proto2, which isn't supported"),
+                _ => {}
+            }
+        }
+
+        def.push((end_line(p), "}".into()));
+        self.deferred.push(def);
+
+        self.comment(p, "    ", lines);
+        lines.push((
+            line(p),
+            format!("    {}{}: optional<{}>,", ordinal(number), ident.as_str(), name),
+        ));
+    }
+
+    /// Adds a member of the type `ty` to a struct.
+    fn member(&mut self, p: &Pair<'s, Rule>, ty: String, lines: &mut Lines) {
+        if let Some(ident) = inner(p, Rule::Ident) {
+            let number = inner(p, Rule::IntLit).and_then(|n| integer(n.as_str()));
+
+            self.comment(p, "    ", lines);
+            lines.push((line(p), format!("    {}{}: {},", ordinal(number), ident.as_str(), ty)));
+        }
+    }
+
+    /// Type of a field, which is reported if it can't be translated.
+    fn field_type(&mut self, p: &Pair<'s, Rule>, scope: &str) -> Option<String> {
+        let ty = inner(p, Rule::TypeName)?;
+
+        match self.resolve(&ty, scope)? {
+            Some(ty) => Some(ty),
+            None => {
+                self.unsupported(&ty, empty_usage());
+                None
+            }
+        }
+    }
+
+    /// Translates a type name as seen from `scope`.
+    ///
+    /// Returns `Some(None)` for `google.protobuf.Empty`, and `None` if the type is reported.
+    fn resolve(&mut self, p: &Pair<'s, Rule>, scope: &str) -> Option<Option<String>> {
+        let name = p.as_str();
+
+        if let Some(ty) = scalar(name) {
+            return Some(Some(ty.into()));
+        }
+        if ENCODED.contains(&name) {
+            let message = format!("{} has an encoding of its own, which isn't supported", name);
+            self.unsupported(p, message);
+            return None;
+        }
+        if name.trim_start_matches('.') == EMPTY {
+            return Some(None);
+        }
+
+        // Names are looked up in the scope they're used in first, and then in the enclosing ones.
+        let found = if let Some(full) = name.strip_prefix('.') {
+            self.types.get(full)
+        } else {
+            let mut scope = scope.to_string();
+            loop {
+                if let Some(ty) = self.types.get(&join(&scope, name)) {
+                    break Some(ty);
+                }
+                if scope.is_empty() {
+                    break None;
+                }
+                scope = match scope.rfind('.') {
+                    Some(i) => scope[..i].to_string(),
+                    None => String::new(),
+                };
+            }
+        };
+
+        match found {
+            Some(ty) => Some(Some(ty.clone())),
+            None if name.starts_with("google.protobuf.") || name.starts_with(".google.protobuf.") => {
+                self.unsupported(p, format!("well-known types aren't supported: {}", name));
+                None
+            }
+            None => {
+                self.unsupported(p, format!("type not found: {}", name));
+                None
+            }
+        }
+    }
+
+    /// Translates an enum, named `prefix` followed by its name.
+    fn enumeration(&mut self, p: &Pair<'s, Rule>, prefix: &str, lines: &mut Lines) {
+        let ident = match inner(p, Rule::Ident) {
+            Some(ident) => ident,
+            None => return,
+        };
+
+        self.comment(p, "", lines);
+        lines.push((line(p), format!("enum {}{}(i32) {{", prefix, ident.as_str())));
+
+        for item in p.clone().into_inner() {
+            match item.as_rule() {
+                Rule::EnumValue => {
+                    let name = inner(&item, Rule::Ident);
+                    let value = inner(&item, Rule::SignedInt);
+
+                    if let (Some(name), Some(value)) = (name, value) {
+                        match integer(value.as_str()) {
+                            Some(n) => {
+                                self.comment(&item, "    ", lines);
+                                lines.push((line(&item), format!("    {} {{{}}},", name.as_str(), n)));
+                            }
+                            None => self.unsupported(&value, "value doesn't fit in an int32"),
+                        }
+                    }
+                }
+                Rule::Option if option_name(&item) == Some("allow_alias") => {
+                    self.unsupported(&item, "aliases aren't supported, as values need to be unique")
+                }
+                _ => {}
+            }
+        }
+
+        lines.push((end_line(p), "}".into()));
+    }
+
+    fn service(&mut self, p: &Pair<'s, Rule>, lines: &mut Lines) {
+        let ident = match inner(p, Rule::Ident) {
+            Some(ident) => ident,
+            None => return,
+        };
+        let scope = self.package.clone();
+
+        self.comment(p, "", lines);
+        lines.push((line(p), format!("interface {}(rpc) {{", ident.as_str())));
+
+        for rpc in p.clone().into_inner().filter(|p| p.as_rule() == Rule::Rpc) {
+            if let Some(stream) = inner(&rpc, Rule::Stream) {
+                self.unsupported(&stream, "streaming rpcs aren't supported");
+                continue;
+            }
+
+            let name = inner(&rpc, Rule::Ident);
+            let types = rpc.clone()
+                .into_inner()
+                .filter(|p| p.as_rule() == Rule::TypeName)
+                .map(|ty| self.resolve(&ty, &scope))
+                .collect::<Vec<_>>();
+
+            let (name, request, response) = match (name, types.as_slice()) {
+                (Some(name), [Some(request), Some(response)]) => (name, request, response),
+                _ => continue,
+            };
+
+            let args = match *request {
+                Some(ref ty) => format!("request: {}", ty),
+                None => String::new(),
+            };
+            let ret = match *response {
+                Some(ref ty) => format!(" -> {}", ty),
+                None => String::new(),
+            };
+
+            self.comment(&rpc, "    ", lines);
+            lines.push((line(&rpc), format!("    fn {}({}){};", name.as_str(), args, ret)));
+        }
+
+        lines.push((end_line(p), "}".into()));
+    }
+
+    /// Adds the `//` comments right above a declaration as its doc comment.
+    fn comment(&self, p: &Pair<'s, Rule>, indent: &str, lines: &mut Lines) {
+        let start = p.as_span().start();
+        let before = &self.source[..start];
+        let mut before = before.lines().rev();
+
+        // The declaration may not be the first on its line.
+        if !self.source[..start].ends_with('\n') {
+            match before.next() {
+                Some(l) if l.trim().is_empty() => {}
+                _ => return,
+            }
+        }
+
+        let mut comment = Vec::new();
+        for l in before {
+            let l = l.trim();
+            if !l.starts_with("//") {
+                break;
+            }
+            comment.push(l.trim_start_matches('/').trim());
+        }
+
+        if comment.iter().all(|c| c.is_empty()) {
+            return;
+        }
+
+        comment.reverse();
+        let comment = comment.into_iter().filter(|c| !c.is_empty()).collect::<Vec<_>>();
+        lines.push((
+            line(p).saturating_sub(1),
+            format!("{}/// {}", indent, comment.join(" ")),
+        ));
+    }
+}
+
+/// Collects the messages and enums of a file, by their full names in `scope`, with their
+/// paths in the module `ns` translated from the file.
+fn collect(
+    items: &[Pair<Rule>],
+    scope: &str,
+    prefix: &str,
+    ns: &str,
+    types: &mut HashMap<String, String>,
+) {
+    for p in items {
+        if p.as_rule() != Rule::Message && p.as_rule() != Rule::Enum {
+            continue;
+        }
+
+        let ident = match inner(p, Rule::Ident) {
+            Some(ident) => ident,
+            None => continue,
+        };
+        let name = format!("{}{}", prefix, ident.as_str());
+        let full = join(scope, ident.as_str());
+
+        let path = if ns.is_empty() {
+            name.clone()
+        } else {
+            format!("{}::{}", ns, name)
+        };
+        types.insert(full.clone(), path);
+
+        if p.as_rule() == Rule::Message {
+            let nested = p.clone().into_inner().collect::<Vec<_>>();
+            collect(&nested, &full, &format!("{}_", name), ns, types);
+        }
+    }
+}
+
+/// Writes the lines of the translation, keeping them on the lines they're for if `aligned`.
+struct Writer {
+    out: String,
+    /// Line the end of the output is on.
+    line: usize,
+}
+
+impl Writer {
+    fn new() -> Self {
+        Self {
+            out: String::new(),
+            line: 1,
+        }
+    }
+
+    fn write(&mut self, lines: Lines, aligned: bool) {
+        for (at, text) in lines {
+            if !self.out.is_empty() && !self.out.ends_with('\n') {
+                self.newline();
+            }
+            while aligned && self.line < at {
+                self.newline();
+            }
+            self.out.push_str(&text);
+        }
+    }
+
+    fn newline(&mut self) {
+        self.out.push('\n');
+        self.line += 1;
+    }
+}
+
+fn inner<'s>(p: &Pair<'s, Rule>, rule: Rule) -> Option<Pair<'s, Rule>> {
+    p.clone().into_inner().find(|p| p.as_rule() == rule)
+}
+
+fn option_name<'s>(p: &Pair<'s, Rule>) -> Option<&'s str> {
+    inner(p, Rule::OptionName).map(|p| p.as_str())
+}
+
+fn line(p: &Pair<Rule>) -> usize {
+    p.as_span().start_pos().line_col().0
+}
+
+fn end_line(p: &Pair<Rule>) -> usize {
+    p.as_span().end_pos().line_col().0
+}
+
+fn join(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.into()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+fn unquote(s: &str) -> String {
+    s[1..s.len() - 1].into()
+}
+
+fn empty_usage() -> &'static str {
+    "google.protobuf.Empty is only supported as a oneof field, a request or a response"
+}
+
+/// Namespace of the module translated from the file `path`, e.g. `a::b` for `a/b.proto`.
+fn namespace(path: &str) -> Option<String> {
+    let stem = path.trim_end_matches(".proto");
+    if stem.len() == path.len() {
+        return None;
+    }
+
+    let parts = stem.split('/').collect::<Vec<_>>();
+
+    if parts.iter().all(|s| identifier(s)) {
+        Some(parts.join("::"))
+    } else {
+        None
+    }
+}
+
+/// Whether a name is an identifier in rpc files.
+fn identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    let first = match chars.next() {
+        Some(c) => c,
+        None => return false,
+    };
+
+    (first.is_ascii_alphabetic() || (first == '_' && s.len() > 1))
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Integer types of protobuf with an encoding of their own, which have no counterpart.
+const ENCODED: &[&str] = &["sint32", "sint64", "fixed32", "fixed64", "sfixed32", "sfixed64"];
+
+fn scalar(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "double" => "f64",
+        "float" => "f32",
+        "int32" => "i32",
+        "int64" => "i64",
+        "uint32" => "u32",
+        "uint64" => "u64",
+        "bool" => "bool",
+        "string" => "string",
+        "bytes" => "vector<u8>",
+        _ => return None,
+    })
+}
+
+/// Highest field number of protobuf, which `max` stands for in ranges.
+const MAX_NUMBER: i32 = (1 << 29) - 1;
+
+/// Ordinal of a member with the field number `number`, if it has one.
+fn ordinal(number: Option<i32>) -> String {
+    number.map_or(String::new(), |n| format!("{}: ", n))
+}
+
+/// Value of an integer literal, if it fits in an `i32`.
+fn integer(s: &str) -> Option<i32> {
+    let (negative, s) = match s.chars().next() {
+        Some('-') => (true, &s[1..]),
+        Some('+') => (false, &s[1..]),
+        _ => (false, s),
+    };
+
+    let n = if s.starts_with("0x") || s.starts_with("0X") {
+        i64::from_str_radix(&s[2..], 16).ok()?
+    } else if s.len() > 1 && s.starts_with('0') {
+        i64::from_str_radix(&s[1..], 8).ok()?
+    } else {
+        s.parse::<i64>().ok()?
+    };
+
+    i32::try_from(if negative { -n } else { n }).ok()
+}
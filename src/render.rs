@@ -1,23 +1,221 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path};
+
 use tera::{Context, Tera};
 use serde_json::Value;
 
-use crate::utils;
 use crate::error::{self, Result};
 
-fn tera(path: &str) -> Tera {
-    let mut tera = compile_templates!(path);
-    tera.autoescape_on(vec![""]);
-    tera
+/// A file to render from an entry template.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Output {
+    /// Name of the entry template, relative to the template directory.
+    pub template: String,
+    /// Pattern of the file name, itself a template, e.g. `{{namespace}}.hpp`.
+    pub path: String,
+    /// Renders a file for every loaded module instead of one for the root module.
+    pub per_module: bool,
+}
+
+impl Output {
+    pub fn new(template: &str, path: &str, per_module: bool) -> Self {
+        Self {
+            template: template.into(),
+            path: path.into(),
+            per_module,
+        }
+    }
+}
+
+/// A rendered file, not written yet.
+#[derive(Debug, Clone, Serialize)]
+pub struct File {
+    /// Path relative to the output directory.
+    pub path: String,
+    pub template: String,
+    pub namespace: String,
+    pub contents: String,
+}
+
+/// The files of a compile, written into `out_dir` if there's one.
+#[derive(Debug, Clone, Serialize)]
+pub struct Manifest {
+    pub out_dir: Option<String>,
+    pub files: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestEntry {
+    /// Path of the file, including the output directory if there's one.
+    pub path: String,
+    pub template: String,
+    pub namespace: String,
+    pub size: usize,
+}
+
+fn tera(path: &str, embedded: &[(&str, &str)]) -> Result<Tera> {
+    let mut tera = if path.is_empty() {
+        Tera::default()
+    } else {
+        Tera::new(path).map_err(error::render_error)?
+    };
+
+    let mut bundled = Tera::default();
+    bundled
+        .add_raw_templates(embedded.to_vec())
+        .map_err(error::render_error)?;
+    tera.extend(&bundled).map_err(error::render_error)?;
+
+    // The output is source code, not html.
+    tera.autoescape_on(vec![]);
+    Ok(tera)
 }
 
-pub fn render(path: &str, tpath: &str, model: Value) -> Result<String> {
-    let namespace = utils::namespace(path)?;
-    let tera = tera(tpath);
+fn context(model: &Value, ns: &str) -> Context {
     let mut context = Context::new();
 
-    context.insert("ast", &model);
-    context.insert("namespace", namespace);
+    context.insert("ast", &model["modules"][ns]);
+    context.insert("modules", &model["modules"]);
+    context.insert("order", &model["order"]);
+    context.insert("root", &model["root"]["namespace"]);
+    context.insert("namespace", ns);
+    context.insert("namespace_path", &ns.replace("::", "/"));
+    context.insert("source", &source(model, ns));
+    context
+}
+
+/// Path of the file of the module `ns`, relative to the directory of the root file.
+fn source(model: &Value, ns: &str) -> String {
+    let path = Path::new(model["modules"][ns]["path"].as_str().unwrap_or(""));
+    let root = Path::new(model["root"]["path"].as_str().unwrap_or(""));
+
+    root.parent()
+        .and_then(|dir| path.strip_prefix(dir).ok())
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Renders the outputs for the model.
+///
+/// Templates are loaded from `tpath` if not empty, and then from `embedded`.
+/// The templates see the module being rendered as `ast`, its name as `namespace` and its
+/// file as `source`, along with every module of the compile in `modules`, dependencies
+/// first in `order`.
+pub fn render(
+    tpath: &str,
+    embedded: &[(&str, &str)],
+    model: &Value,
+    outputs: &[Output],
+) -> Result<Vec<File>> {
+    let tera = tera(tpath, embedded)?;
+    let root = model["root"]["namespace"].as_str().unwrap_or("");
+    let mut files = Vec::new();
+    let mut paths = HashSet::new();
+
+    for output in outputs {
+        let namespaces = if output.per_module {
+            model["order"]
+                .as_array()
+                .map(|order| order.iter().filter_map(|ns| ns.as_str()).collect())
+                .unwrap_or_default()
+        } else {
+            vec![root]
+        };
+
+        for ns in namespaces {
+            let context = context(model, ns);
+
+            let path = Tera::one_off(&output.path, &context, false)
+                .map_err(error::render_error)?;
+            check_path(&path)?;
+
+            if !paths.insert(path.clone()) {
+                return Err(error::output_error(format!(
+                    "{} is rendered more than once (from {})",
+                    path, output.template
+                )));
+            }
+
+            debug!("Rendering {} from {} ({})", path, output.template, ns);
+
+            let contents = tera.render(&output.template, &context)
+                .map_err(error::render_error)?;
+
+            files.push(File {
+                path,
+                template: output.template.clone(),
+                namespace: ns.into(),
+                contents,
+            });
+        }
+    }
+
+    Ok(files)
+}
+
+/// Checks a rendered path stays in the output directory, i.e. it's relative and has no
+/// `..` components.
+fn check_path(path: &str) -> Result<()> {
+    let inside = Path::new(path)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+
+    if path.is_empty() || !inside {
+        return Err(error::output_error(format!(
+            "{:?} isn't a relative path inside the output directory",
+            path
+        )));
+    }
+
+    Ok(())
+}
+
+/// Lists rendered files without writing them.
+pub fn list(files: Vec<File>) -> Manifest {
+    let files = files
+        .into_iter()
+        .map(|file| ManifestEntry {
+            size: file.contents.len(),
+            path: file.path,
+            template: file.template,
+            namespace: file.namespace,
+        })
+        .collect();
+
+    Manifest {
+        out_dir: None,
+        files,
+    }
+}
+
+/// Writes rendered files into `out_dir`, creating directories as needed.
+pub fn write(out_dir: &str, files: Vec<File>) -> Result<Manifest> {
+    let mut entries = Vec::new();
+
+    for file in files {
+        check_path(&file.path)?;
+        let path = Path::new(out_dir).join(&file.path);
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(error::file_error)?;
+        }
+        fs::write(&path, &file.contents).map_err(error::file_error)?;
+
+        let path = path.to_string_lossy().to_string();
+        debug!("Wrote {}", path);
+
+        entries.push(ManifestEntry {
+            path,
+            template: file.template,
+            namespace: file.namespace,
+            size: file.contents.len(),
+        });
+    }
 
-    tera.render("root.cpp", &context)
-        .map_err(|e| error::render_error(e))
+    Ok(Manifest {
+        out_dir: Some(out_dir.into()),
+        files: entries,
+    })
 }
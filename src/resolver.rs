@@ -2,12 +2,13 @@ use std::collections::HashMap;
 
 use pest::iterators::Pair;
 
-use super::parser::{get, get_all, get_opt, Rule};
+use super::parser::{get, get_opt, Rule};
 use super::error::{self, Result};
 use super::types::*;
 
 pub struct Resolver {
     types: HashMap<String, Type>,
+    templates: HashMap<String, TemplateDef>,
     namespace: Vec<String>,
 }
 
@@ -15,6 +16,7 @@ impl Resolver {
     pub fn new() -> Self {
         Self {
             types: HashMap::new(),
+            templates: HashMap::new(),
             namespace: Vec::new(),
         }
     }
@@ -22,50 +24,161 @@ impl Resolver {
     pub fn resolve_type(&self, path: &Pair<Rule>) -> Result<Type> {
         debug!("Lookup type: {}", path.as_str());
 
-        self.types
-            .get(path.as_str())
-            .map(|p| p.namespaced(path.as_str()))
+        self.lookup(&self.current_ns(), path.as_str())
+            .map(|key| self.types[&key].namespaced(&key))
             .ok_or(error::type_not_found(path))
     }
 
-    pub fn resolve_generic_type(&self, p: &Pair<Rule>) -> Result<Type> {
-        match get_opt(&p, Rule::Template) {
-            Some(template) => {
-                let mut tys = Vec::new();
+    pub fn get(&self, key: &str) -> Option<&Type> {
+        self.types.get(key)
+    }
 
-                for gty in get_all(&template, Rule::GenericType) {
-                    tys.push(self.resolve_generic_type(&gty)?);
-                }
+    /// Finds the key of the type referred to by `path` from the namespace `scope`.
+    pub fn lookup(&self, scope: &str, path: &str) -> Option<String> {
+        let scope = if scope.is_empty() {
+            Vec::new()
+        } else {
+            scope.split("::").collect::<Vec<_>>()
+        };
 
-                let ident = get(&template, Rule::Identifier).as_str();
+        // Look up from the innermost namespace outwards.
+        for depth in (0..=scope.len()).rev() {
+            let mut key = scope[..depth].to_vec();
+            key.push(path);
+            let key = key.join("::");
 
-                Ok(Template::new(ident, tys).into())
+            if self.types.contains_key(&key) {
+                return Some(key);
             }
+        }
+
+        None
+    }
+
+    pub fn resolve_generic_type(&self, p: &Pair<Rule>) -> Result<Type> {
+        match get_opt(p, Rule::Template) {
+            Some(template) => self.resolve_template(&template),
             None => {
-                let ty = get(&p, Rule::Type);
+                let ty = get(p, Rule::Type)?;
                 self.resolve_type(&ty)
             }
         }
     }
 
+    fn resolve_template(&self, p: &Pair<Rule>) -> Result<Type> {
+        let ident = get(p, Rule::Identifier)?;
+
+        let def = self.templates
+            .get(ident.as_str())
+            .ok_or(error::unknown_template(&ident))?;
+
+        let params = p.clone()
+            .into_inner()
+            .filter(|p| p.as_rule() != Rule::Identifier)
+            .collect::<Vec<_>>();
+
+        if params.len() != def.params.len() {
+            return Err(error::template_error(
+                p,
+                format!(
+                    "{} takes {} parameter(s) but {} given",
+                    def.name,
+                    def.params.len(),
+                    params.len()
+                ),
+            ));
+        }
+
+        let mut tys = Vec::new();
+        let mut size = None;
+
+        for (kind, param) in def.params.iter().zip(params) {
+            match (kind, param.as_rule()) {
+                (ParamKind::Size, Rule::Size) => {
+                    let n = param
+                        .as_str()
+                        .parse()
+                        .map_err(|e| error::template_error(&param, e))?;
+
+                    size = Some(n);
+                }
+                (ParamKind::Size, _) => {
+                    return Err(error::template_error(
+                        &param,
+                        format!("{} expects a size but a type given", def.name),
+                    ));
+                }
+                (_, Rule::Size) => {
+                    return Err(error::template_error(
+                        &param,
+                        format!("{} expects a type but a size given", def.name),
+                    ));
+                }
+                (kind, _) => {
+                    let ty = self.resolve_generic_type(&param)?;
+
+                    if *kind == ParamKind::Key && !is_key(ty.tt()) {
+                        return Err(error::template_error(
+                            &param,
+                            format!("{} can't be used as a key of {}", param.as_str(), def.name),
+                        ));
+                    }
+
+                    tys.push(ty);
+                }
+            }
+        }
+
+        Ok(Template::new(ident.as_str(), tys, size).into())
+    }
+
+    pub fn add_template(&mut self, def: TemplateDef) {
+        debug!("Add template: {}", def.name);
+
+        self.templates.insert(def.name.clone(), def);
+    }
+
+    pub fn is_indirect(&self, template: &str) -> bool {
+        self.templates
+            .get(template)
+            .map(|def| def.indirect)
+            .unwrap_or(true)
+    }
+
+    pub fn qualified(&self, ident: &str) -> String {
+        match self.namespace.last() {
+            Some(namespace) if !namespace.is_empty() => format!("{}::{}", namespace, ident),
+            _ => ident.to_string(),
+        }
+    }
+
     pub fn add_type<T>(&mut self, ident: &str, ty: T)
     where
         T: Into<Type>,
     {
-        let path = match self.namespace.last() {
-            Some(namespace) => format!("{}::{}", namespace, ident),
-            None => ident.to_string(),
-        };
+        let path = self.qualified(ident);
 
         debug!("Add type: {}", path);
 
         self.types.insert(path, ty.into());
     }
 
+    pub fn current_ns(&self) -> String {
+        self.namespace.last().cloned().unwrap_or_default()
+    }
+
+    /// Enters a module nested in the current namespace.
     pub fn enter_ns(&mut self, module: &str) {
-        self.namespace.push(module.into());
+        let ns = self.qualified(module);
 
-        debug!("Entered to namespace: {}", module);
+        self.enter_root_ns(&ns);
+    }
+
+    /// Enters a namespace which is independent from the current one.
+    pub fn enter_root_ns(&mut self, ns: &str) {
+        self.namespace.push(ns.into());
+
+        debug!("Entered to namespace: {}", ns);
     }
 
     pub fn exit_ns(&mut self) {
@@ -74,3 +187,7 @@ impl Resolver {
         debug!("Exited to namespace: {}", _ns.unwrap_or("".into()));
     }
 }
+
+fn is_key(tt: Trait) -> bool {
+    matches!(tt, Trait::Bool | Trait::Integer | Trait::String | Trait::Enum)
+}
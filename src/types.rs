@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde_json::value::Value;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,7 +15,9 @@ pub enum TypeInfo {
     Primitive(Primitive),
     Struct(Struct),
     Enum(Enum),
+    Union(Union),
     Template(Template),
+    Ref(Ref),
 }
 
 impl From<Primitive> for Type {
@@ -43,6 +47,15 @@ impl From<Enum> for Type {
     }
 }
 
+impl From<Union> for Type {
+    fn from(p: Union) -> Type {
+        Type {
+            namespace: "".into(),
+            info: TypeInfo::Union(p),
+        }
+    }
+}
+
 impl From<Template> for Type {
     fn from(p: Template) -> Type {
         Type {
@@ -52,7 +65,53 @@ impl From<Template> for Type {
     }
 }
 
+impl From<Ref> for Type {
+    fn from(p: Ref) -> Type {
+        Type {
+            namespace: "".into(),
+            info: TypeInfo::Ref(p),
+        }
+    }
+}
+
 impl Type {
+    pub fn info(&self) -> &TypeInfo {
+        &self.info
+    }
+
+    /// Path of the type, used to look it up.
+    pub fn key(&self) -> String {
+        let name = match self.info {
+            TypeInfo::Primitive(ref p) => &p.name,
+            TypeInfo::Struct(ref p) => &p.name,
+            TypeInfo::Enum(ref p) => &p.name,
+            TypeInfo::Union(ref p) => &p.name,
+            TypeInfo::Template(ref p) => &p.name,
+            TypeInfo::Ref(ref p) => &p.name,
+        };
+
+        if self.namespace.is_empty() {
+            name.clone()
+        } else {
+            format!("{}::{}", self.namespace, name)
+        }
+    }
+
+    pub fn is_ref(&self) -> bool {
+        matches!(self.info, TypeInfo::Ref(_))
+    }
+
+    pub fn tt(&self) -> Trait {
+        match self.info {
+            TypeInfo::Primitive(ref p) => p.tt,
+            TypeInfo::Struct(ref p) => p.tt,
+            TypeInfo::Enum(ref p) => p.tt,
+            TypeInfo::Union(ref p) => p.tt,
+            TypeInfo::Template(ref p) => p.tt,
+            TypeInfo::Ref(ref p) => p.tt,
+        }
+    }
+
     pub fn namespaced(&self, path: &str) -> Type {
         let mut ns = path.split("::").collect::<Vec<_>>();
         ns.pop();
@@ -64,7 +123,7 @@ impl Type {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Trait {
     Bool,
@@ -73,8 +132,10 @@ pub enum Trait {
     String,
     Struct,
     Enum,
+    Union,
     Template,
     Interface,
+    Module,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -103,6 +164,7 @@ pub struct Struct {
     #[serde(rename = "trait")]
     pub tt: Trait,
     pub members: Vec<Field>,
+    pub reserved: Reserved,
     #[serde(flatten)]
     pub custom: Value,
 }
@@ -114,15 +176,36 @@ impl Struct {
             name: name.into(),
             tt: Trait::Struct,
             members,
+            reserved: Reserved::default(),
             custom: json!({}),
         }
     }
 }
 
+/// Ordinals and names retired from a struct, which fields can't use.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Reserved {
+    /// Inclusive ranges of ordinals.
+    pub ranges: Vec<(u32, u32)>,
+    pub names: Vec<String>,
+}
+
+impl Reserved {
+    pub fn has_id(&self, id: u32) -> bool {
+        self.ranges.iter().any(|&(start, end)| start <= id && id <= end)
+    }
+
+    pub fn has_name(&self, name: &str) -> bool {
+        self.names.iter().any(|n| n == name)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Field {
     pub comment: Option<String>,
     pub name: String,
+    /// Ordinal identifying the field on the wire, from 1.
+    pub id: u32,
     #[serde(rename = "type")]
     pub ty: Type,
     pub value: Option<Value>,
@@ -131,10 +214,11 @@ pub struct Field {
 }
 
 impl Field {
-    pub fn new(comment: Option<&str>, name: &str, ty: Type, value: Option<Value>) -> Self {
+    pub fn new(comment: Option<&str>, name: &str, id: u32, ty: Type, value: Option<Value>) -> Self {
         Self {
             comment: comment.map(|s| s.into()),
             name: name.into(),
+            id,
             ty,
             value,
             custom: json!({}),
@@ -190,6 +274,54 @@ impl Variant {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Union {
+    pub comment: Option<String>,
+    pub name: String,
+    #[serde(rename = "trait")]
+    pub tt: Trait,
+    pub members: Vec<UnionVariant>,
+    #[serde(flatten)]
+    pub custom: Value,
+}
+
+impl Union {
+    pub fn new(comment: Option<&str>, name: &str, members: Vec<UnionVariant>) -> Self {
+        Self {
+            comment: comment.map(|s| s.into()),
+            name: name.into(),
+            tt: Trait::Union,
+            members,
+            custom: json!({}),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnionVariant {
+    pub comment: Option<String>,
+    pub name: String,
+    /// Ordinal of the variant, which is its tag in the binary encoding.
+    pub id: u32,
+    /// Payload of the variant, if any.
+    #[serde(rename = "type")]
+    pub ty: Option<Type>,
+    #[serde(flatten)]
+    pub custom: Value,
+}
+
+impl UnionVariant {
+    pub fn new(comment: Option<&str>, name: &str, id: u32, ty: Option<Type>) -> Self {
+        Self {
+            comment: comment.map(|s| s.into()),
+            name: name.into(),
+            id,
+            ty,
+            custom: json!({}),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Interface {
     pub comment: Option<String>,
@@ -262,23 +394,82 @@ pub struct Template {
     #[serde(rename = "trait")]
     pub tt: Trait,
     pub params: Vec<Type>,
+    /// Fixed size given by a `Size` parameter, e.g. `N` of `array<T, N>`.
+    pub size: Option<u64>,
     #[serde(flatten)]
     pub custom: Value,
 }
 
 impl Template {
-    pub fn new(name: &str, params: Vec<Type>) -> Self {
+    pub fn new(name: &str, params: Vec<Type>, size: Option<u64>) -> Self {
         Self {
             name: name.into(),
             tt: Trait::Template,
             params,
+            size,
             custom: json!({}),
         }
     }
 }
 
+/// A type referred to by name only, without its definition.
+///
+/// Used where a type is referred to before it's defined, e.g. by a recursive type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ref {
+    pub name: String,
+    #[serde(rename = "trait")]
+    pub tt: Trait,
+    #[serde(flatten)]
+    pub custom: Value,
+}
+
+impl Ref {
+    pub fn new(name: &str, tt: Trait) -> Self {
+        Self {
+            name: name.into(),
+            tt,
+            custom: json!({}),
+        }
+    }
+}
+
+/// Kind of a parameter a generic type takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParamKind {
+    /// Any type.
+    Type,
+    /// A type usable as a key: bool, integer, string or enum.
+    Key,
+    /// A non-negative integer constant.
+    Size,
+}
+
+/// Declaration of a generic type which can be used as a `Template`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateDef {
+    pub name: String,
+    pub params: Vec<ParamKind>,
+    /// Whether the type holds its parameters through indirection,
+    /// so that a type can recursively contain itself through it.
+    pub indirect: bool,
+}
+
+impl TemplateDef {
+    pub fn new(name: &str, params: Vec<ParamKind>, indirect: bool) -> Self {
+        Self {
+            name: name.into(),
+            params,
+            indirect,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Defs {
+    pub namespace: String,
+    pub path: String,
     pub uses: Vec<Use>,
     pub nodes: Vec<Node>,
     #[serde(flatten)]
@@ -286,8 +477,62 @@ pub struct Defs {
 }
 
 impl Defs {
-    pub fn new(uses: Vec<Use>, nodes: Vec<Node>) -> Self {
+    pub fn new(namespace: &str, path: &str, uses: Vec<Use>, nodes: Vec<Node>) -> Self {
         Self {
+            namespace: namespace.into(),
+            path: path.into(),
+            uses,
+            nodes,
+            custom: json!({}),
+        }
+    }
+}
+
+/// The whole import graph of a compile, with every module keyed by its namespace.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Model {
+    pub root: Defs,
+    pub modules: BTreeMap<String, Defs>,
+    /// Namespaces of `modules`, dependencies first.
+    pub order: Vec<String>,
+}
+
+impl Model {
+    pub fn new(root: Defs, modules: BTreeMap<String, Defs>, order: Vec<String>) -> Self {
+        Self {
+            root,
+            modules,
+            order,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Module {
+    pub comment: Option<String>,
+    pub name: String,
+    pub namespace: String,
+    #[serde(rename = "trait")]
+    pub tt: Trait,
+    pub uses: Vec<Use>,
+    pub nodes: Vec<Node>,
+    #[serde(flatten)]
+    pub custom: Value,
+}
+
+impl Module {
+    pub fn new(
+        comment: Option<&str>,
+        name: &str,
+        namespace: &str,
+        uses: Vec<Use>,
+        nodes: Vec<Node>,
+    ) -> Self {
+        Self {
+            comment: comment.map(|s| s.into()),
+            name: name.into(),
+            namespace: namespace.into(),
+            tt: Trait::Module,
             uses,
             nodes,
             custom: json!({}),
@@ -318,5 +563,7 @@ impl Use {
 pub enum Node {
     Struct(Struct),
     Enum(Enum),
+    Union(Union),
     Interface(Interface),
+    Module(Module),
 }
@@ -3,8 +3,8 @@ use std::path::Path;
 use crate::error::{self, Result};
 
 pub fn fullpath(path: &str) -> Result<String> {
-    let cwd = std::env::current_dir().map_err(|e| error::file_error(e))?;
-    Ok(format!("{}/{}", cwd.to_string_lossy(), path))
+    let cwd = std::env::current_dir().map_err(error::file_error)?;
+    Ok(cwd.join(path).to_string_lossy().to_string())
 }
 
 pub fn namespace<'a>(path: &'a str) -> Result<&'a str> {
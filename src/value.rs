@@ -0,0 +1,205 @@
+use serde_json::Value;
+
+use super::resolver::Resolver;
+use super::types::*;
+
+/// Returns the range of values a built-in integer type can hold.
+pub fn int_range(name: &str) -> Option<(i128, i128)> {
+    let range = match name {
+        "u8" => (0, i128::from(u8::MAX)),
+        "u16" => (0, i128::from(u16::MAX)),
+        "u32" => (0, i128::from(u32::MAX)),
+        "u64" => (0, i128::from(u64::MAX)),
+        "i8" => (i128::from(i8::MIN), i128::from(i8::MAX)),
+        "i16" => (i128::from(i16::MIN), i128::from(i16::MAX)),
+        "i32" => (i128::from(i32::MIN), i128::from(i32::MAX)),
+        "i64" => (i128::from(i64::MIN), i128::from(i64::MAX)),
+        _ => return None,
+    };
+
+    Some(range)
+}
+
+/// Returns the value as an integer if it's an integer.
+pub fn as_int(value: &Value) -> Option<i128> {
+    value
+        .as_i64()
+        .map(i128::from)
+        .or_else(|| value.as_u64().map(i128::from))
+}
+
+/// Checks that `value` is a valid value of the type `ty`.
+///
+/// On mismatch, returns a message describing where in the value the mismatch is.
+pub fn check(resolver: &Resolver, ty: &Type, value: &Value) -> Result<(), String> {
+    check_at(resolver, ty, value, "value")
+}
+
+fn check_at(resolver: &Resolver, ty: &Type, value: &Value, at: &str) -> Result<(), String> {
+    let mismatch = |expected: &str| {
+        Err(format!(
+            "{} needs to be {}, but found {}",
+            at, expected, value
+        ))
+    };
+
+    match ty.info() {
+        TypeInfo::Primitive(p) => match p.tt {
+            Trait::Bool if value.is_boolean() => Ok(()),
+            Trait::Bool => mismatch("a bool"),
+            Trait::Integer => match (as_int(value), int_range(&p.name)) {
+                (Some(n), Some((min, max))) if n >= min && n <= max => Ok(()),
+                (Some(_), None) => Ok(()),
+                (_, Some((min, max))) => {
+                    mismatch(&format!("an integer of {} ({}..={})", p.name, min, max))
+                }
+                (None, None) => mismatch("an integer"),
+            },
+            Trait::Float if value.is_number() => Ok(()),
+            Trait::Float => mismatch("a number"),
+            Trait::String if value.is_string() => Ok(()),
+            Trait::String => mismatch("a string"),
+            _ => Ok(()),
+        },
+        TypeInfo::Enum(e) => {
+            let name = value.as_str().unwrap_or("");
+
+            if e.members.iter().any(|v| v.name == name) {
+                Ok(())
+            } else {
+                let names = e.members
+                    .iter()
+                    .map(|v| format!("\"{}\"", v.name))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                mismatch(&format!("a variant of {} ({})", e.name, names))
+            }
+        }
+        TypeInfo::Struct(s) => {
+            // Members are given either by name or by position.
+            if let Some(array) = value.as_array() {
+                if array.len() > s.members.len() {
+                    return mismatch(&format!("at most {} members of {}", s.members.len(), s.name));
+                }
+
+                for (member, value) in s.members.iter().zip(array) {
+                    check_at(resolver, &member.ty, value, &format!("{}.{}", at, member.name))?;
+                }
+
+                return Ok(());
+            }
+
+            let object = match value.as_object() {
+                Some(object) => object,
+                None => return mismatch(&format!("an object of {}", s.name)),
+            };
+
+            for (key, value) in object {
+                let member = s.members
+                    .iter()
+                    .find(|m| &m.name == key)
+                    .ok_or_else(|| format!("{} has no member {} in {}", s.name, key, at))?;
+
+                check_at(resolver, &member.ty, value, &format!("{}.{}", at, key))?;
+            }
+
+            Ok(())
+        }
+        TypeInfo::Union(u) => {
+            // A variant is either `"Name"` without payload, or `{"Name": payload}`.
+            let (name, payload) = match value {
+                Value::String(name) => (name.as_str(), None),
+                Value::Object(object) if object.len() == 1 => {
+                    let (name, payload) = object.iter().next().unwrap();
+                    (name.as_str(), Some(payload))
+                }
+                _ => return mismatch(&format!("a variant of {}", u.name)),
+            };
+
+            let variant = match u.members.iter().find(|v| v.name == name) {
+                Some(variant) => variant,
+                None => return mismatch(&format!("a variant of {}", u.name)),
+            };
+
+            match (&variant.ty, payload) {
+                (Some(ty), Some(payload)) => {
+                    check_at(resolver, ty, payload, &format!("{}.{}", at, name))
+                }
+                (None, None) => Ok(()),
+                (Some(_), None) => Err(format!("{}: variant {} needs a payload", at, name)),
+                (None, Some(_)) => Err(format!("{}: variant {} has no payload", at, name)),
+            }
+        }
+        TypeInfo::Template(t) => check_template(resolver, t, value, at),
+        TypeInfo::Ref(_) => match resolver.get(&ty.key()) {
+            Some(ty) if !ty.is_ref() => check_at(resolver, ty, value, at),
+            // Not defined yet, so there's nothing to check against.
+            _ => Ok(()),
+        },
+    }
+}
+
+fn check_template(resolver: &Resolver, t: &Template, value: &Value, at: &str) -> Result<(), String> {
+    let mismatch = |expected: &str| {
+        Err(format!(
+            "{} needs to be {}, but found {}",
+            at, expected, value
+        ))
+    };
+
+    match (t.name.as_str(), t.params.as_slice()) {
+        ("vector", [ty]) | ("set", [ty]) | ("array", [ty]) => {
+            let array = match value.as_array() {
+                Some(array) => array,
+                None => return mismatch("an array"),
+            };
+
+            if let Some(size) = t.size {
+                if array.len() as u64 != size {
+                    return mismatch(&format!("an array of {} elements", size));
+                }
+            }
+
+            for (i, value) in array.iter().enumerate() {
+                check_at(resolver, ty, value, &format!("{}[{}]", at, i))?;
+            }
+
+            Ok(())
+        }
+        ("map", [key, ty]) => {
+            let object = match value.as_object() {
+                Some(object) => object,
+                None => return mismatch("an object"),
+            };
+
+            for (k, value) in object {
+                check_at(resolver, key, &key_value(resolver, key, k), &format!("{} key", at))?;
+                check_at(resolver, ty, value, &format!("{}[{}]", at, k))?;
+            }
+
+            Ok(())
+        }
+        ("optional", [_]) if value.is_null() => Ok(()),
+        ("optional", [ty]) => check_at(resolver, ty, value, at),
+        // Templates registered by generators have no known layout.
+        _ => Ok(()),
+    }
+}
+
+/// Value of the map key `k`, which JSON can only spell as a string.
+///
+/// Keys of integers and bools are parsed, so they're checked like any other value.
+fn key_value<'a>(resolver: &'a Resolver, ty: &'a Type, k: &str) -> Value {
+    let ty = match ty.info() {
+        TypeInfo::Ref(_) => resolver.get(&ty.key()).unwrap_or(ty),
+        _ => ty,
+    };
+
+    match ty.info() {
+        TypeInfo::Primitive(p) if p.tt == Trait::Integer || p.tt == Trait::Bool => {
+            serde_json::from_str(k).unwrap_or_else(|_| Value::String(k.into()))
+        }
+        _ => Value::String(k.into()),
+    }
+}
@@ -0,0 +1,145 @@
+{% macro comment(of, indent) -%}
+{% if of.comment %}{{ indent }}///{{ of.comment }}
+{% endif -%}
+{%- endmacro comment %}
+
+{% macro node(node) -%}
+{{ self::comment(of=node, indent="") }}
+{%- if node.trait == "struct" -%}
+struct {{ node.cpp.name }} {
+{%- for f in node.members %}
+{{ self::comment(of=f, indent="    ") }}    {{ f.cpp.type }} {{ f.cpp.name }}{% if f.cpp.default %} = {{ f.cpp.default }}{% else %}{}{% endif %};
+{%- endfor %}
+};
+{% elif node.trait == "enum" -%}
+enum class {{ node.cpp.name }} : {{ node.cpp.utype }} {
+{%- for v in node.members %}
+{{ self::comment(of=v, indent="    ") }}    {{ v.cpp.name }} = {{ v.cpp.value }},
+{%- endfor %}
+};
+{% elif node.trait == "union" -%}
+struct {{ node.cpp.name }} {
+    /// Variants, in the order of the alternatives of `value`.
+    enum class Which {
+{%- for v in node.members %}
+        {{ v.cpp.name }},
+{%- endfor %}
+    };
+{% for v in node.members %}{% if not v.type %}
+    struct {{ v.cpp.name }} {};
+{%- endif %}{% endfor %}
+
+    {{ node.cpp.variant }} value;
+
+    Which which() const {
+        return static_cast<Which>(value.index());
+    }
+};
+{% elif node.trait == "interface" -%}
+class {{ node.cpp.name }} {
+public:
+    virtual ~{{ node.cpp.name }}() = default;
+{% for f in node.funcs %}
+{{ self::comment(of=f, indent="    ") }}    virtual {{ f.cpp.ret }} {{ f.cpp.name }}(
+        {%- for a in f.args %}{{ a.cpp.type }} {{ a.cpp.name }}{% if not loop.last %}, {% endif %}{% endfor -%}
+    ) = 0;
+{%- endfor %}
+};
+{% endif -%}
+{%- endmacro node %}
+
+{% macro codec_decl(node) -%}
+{% if node.trait == "enum" -%}
+inline void write_value(rrpc::Bytes& w, {{ node.cpp.name }} value);
+{%- else -%}
+inline void write_value(rrpc::Bytes& w, const {{ node.cpp.name }}& value);
+{%- endif %}
+inline void read_value(rrpc::Reader& r, {{ node.cpp.name }}& value);
+{%- endmacro codec_decl %}
+
+{% macro codec(node) -%}
+{% if node.trait == "struct" -%}
+inline void write_value(rrpc::Bytes& w, const {{ node.cpp.name }}& value) {
+    rrpc::write_varint(w, {{ node.members | length }});
+{%- for f in node.members %}
+    rrpc::write_field(w, {{ f.id }}, value.{{ f.cpp.name }});
+{%- endfor %}
+}
+
+inline void read_value(rrpc::Reader& r, {{ node.cpp.name }}& value) {
+    for (std::size_t n = r.count(); n > 0; n--) {
+        std::uint64_t tag = 0;
+{%- if node.members %}
+        rrpc::Reader field = r.field(tag);
+
+        // Fields of unknown tags are skipped, and missing ones keep their defaults.
+        switch (tag) {
+{%- for f in node.members %}
+        case {{ f.id }}:
+            rrpc::read(field, value.{{ f.cpp.name }});
+            break;
+{%- endfor %}
+        default:
+            break;
+        }
+{%- else %}
+        r.field(tag);
+{%- endif %}
+    }
+}
+{% elif node.trait == "enum" -%}
+inline void write_value(rrpc::Bytes& w, {{ node.cpp.name }} value) {
+    rrpc::write_value(w, static_cast<{{ node.cpp.utype }}>(value));
+}
+
+inline void read_value(rrpc::Reader& r, {{ node.cpp.name }}& value) {
+    {{ node.cpp.utype }} n{};
+    rrpc::read_value(r, n);
+
+    switch (n) {
+{%- for v in node.members %}
+    case static_cast<{{ node.cpp.utype }}>({{ node.cpp.name }}::{{ v.cpp.name }}):
+{%- endfor %}
+{%- if node.members %}
+        value = static_cast<{{ node.cpp.name }}>(n);
+        return;
+{%- endif %}
+    default:
+        throw rrpc::DecodeError(std::to_string(n) + " isn't a value of {{ node.name }}");
+    }
+}
+{% elif node.trait == "union" -%}
+inline void write_value(rrpc::Bytes& w, const {{ node.cpp.name }}& value) {
+    switch (value.value.index()) {
+{%- for v in node.members %}
+    case {{ loop.index0 }}:
+        rrpc::write_varint(w, {{ v.id }});
+{%- if v.type %}
+        rrpc::write(w, std::get<{{ loop.index0 }}>(value.value));
+{%- endif %}
+        break;
+{%- endfor %}
+    default:
+        break;
+    }
+}
+
+inline void read_value(rrpc::Reader& r, {{ node.cpp.name }}& value) {
+    std::uint64_t tag = r.varint();
+
+    switch (tag) {
+{%- for v in node.members %}
+    case {{ v.id }}:
+{%- if v.type %}
+        rrpc::read(r, value.value.emplace<{{ loop.index0 }}>());
+{%- else %}
+        value.value.emplace<{{ loop.index0 }}>();
+{%- endif %}
+        return;
+{%- endfor %}
+    default:
+        throw rrpc::DecodeError(std::to_string(tag) + " isn't a variant of {{ node.name }}");
+    }
+}
+{% endif -%}
+{%- endmacro codec %}
@@ -0,0 +1,47 @@
+{% import "cpp/macros.hpp" as m -%}
+// Generated by rrpc from {{ source }}. Do not edit.
+
+#ifndef {{ ast.cpp.guard }}
+#define {{ ast.cpp.guard }}
+
+#include <array>
+#include <cstdint>
+#include <map>
+#include <memory>
+#include <optional>
+#include <set>
+#include <string>
+#include <tuple>
+#include <variant>
+#include <vector>
+
+#include "{{ ast.cpp.runtime }}"
+{% for include in ast.cpp.includes %}
+{%- if loop.first %}
+{% endif %}#include "{{ include }}"
+{% endfor %}
+{%- for f in ast.cpp.forward %}
+{%- if loop.first %}
+{% endif %}namespace {{ f.namespace }} { {{ f.decl }}; }
+{% endfor %}
+{%- for item in ast.cpp.items %}
+namespace {{ item.namespace }} {
+
+{{ m::node(node=item.node) }}
+} // namespace {{ item.namespace }}
+{% endfor %}
+{%- for item in ast.cpp.codecs %}
+{%- if loop.first %}
+// Codecs of the binary encoding, declared first as they may use each other.
+{% endif %}
+namespace {{ item.namespace }} {
+{{ m::codec_decl(node=item.node) }}
+} // namespace {{ item.namespace }}
+{% endfor %}
+{%- for item in ast.cpp.codecs %}
+namespace {{ item.namespace }} {
+
+{{ m::codec(node=item.node) }}
+} // namespace {{ item.namespace }}
+{% endfor %}
+#endif // {{ ast.cpp.guard }}
@@ -0,0 +1,458 @@
+// Generated by rrpc. Do not edit.
+
+#ifndef RRPC_RUNTIME_HPP
+#define RRPC_RUNTIME_HPP
+
+#include <algorithm>
+#include <array>
+#include <cstdint>
+#include <cstring>
+#include <map>
+#include <memory>
+#include <optional>
+#include <set>
+#include <stdexcept>
+#include <string>
+#include <utility>
+#include <vector>
+
+namespace rrpc {
+
+/// Version of the binary encoding.
+constexpr std::uint8_t VERSION = 1;
+
+using Bytes = std::vector<std::uint8_t>;
+
+/// Bytes which aren't a value of the binary encoding.
+class DecodeError : public std::runtime_error {
+public:
+    using std::runtime_error::runtime_error;
+};
+
+/// Reads encoded values from bytes, which it doesn't own.
+class Reader {
+public:
+    Reader(const std::uint8_t* data, std::size_t size) : data_(data), size_(size) {}
+
+    explicit Reader(const Bytes& bytes) : Reader(bytes.data(), bytes.size()) {}
+
+    /// Number of bytes not read yet.
+    std::size_t left() const {
+        return size_ - pos_;
+    }
+
+    const std::uint8_t* bytes(std::size_t n) {
+        if (n > left()) {
+            throw DecodeError("unexpected end of message");
+        }
+
+        const std::uint8_t* bytes = data_ + pos_;
+        pos_ += n;
+        return bytes;
+    }
+
+    std::uint8_t byte() {
+        return *bytes(1);
+    }
+
+    std::uint64_t varint() {
+        std::uint64_t n = 0;
+        for (int i = 0; i < 10; i++) {
+            std::uint8_t b = byte();
+            if (i == 9 && b > 1) {
+                break;
+            }
+
+            n |= static_cast<std::uint64_t>(b & 0x7f) << (7 * i);
+            if (b < 0x80) {
+                return n;
+            }
+        }
+
+        throw DecodeError("varint is too long");
+    }
+
+    /// Reads a count of items, each of which is at least a byte long.
+    std::size_t count() {
+        std::uint64_t n = varint();
+        if (n > left()) {
+            throw DecodeError("count of " + std::to_string(n) + " is past the end of message");
+        }
+        return static_cast<std::size_t>(n);
+    }
+
+    /// Reads the tag of a field of a struct, and returns a reader of its value.
+    Reader field(std::uint64_t& tag) {
+        tag = varint();
+        std::size_t n = count();
+        return Reader(bytes(n), n);
+    }
+
+private:
+    const std::uint8_t* data_;
+    std::size_t size_;
+    std::size_t pos_ = 0;
+};
+
+/// Writes an unsigned LEB128 varint.
+inline void write_varint(Bytes& w, std::uint64_t n) {
+    while (n >= 0x80) {
+        w.push_back(static_cast<std::uint8_t>(n | 0x80));
+        n >>= 7;
+    }
+    w.push_back(static_cast<std::uint8_t>(n));
+}
+
+// Values are written by `write_value` and read into by `read_value`, which the
+// generated code overloads in the namespace of each type. The overloads of templates
+// are declared before they're defined, so that they find each other.
+
+template <typename T>
+void write_value(Bytes& w, const std::vector<T>& value);
+template <typename T, std::size_t N>
+void write_value(Bytes& w, const std::array<T, N>& value);
+template <typename T>
+void write_value(Bytes& w, const std::set<T>& value);
+template <typename K, typename V>
+void write_value(Bytes& w, const std::map<K, V>& value);
+template <typename T>
+void write_value(Bytes& w, const std::optional<T>& value);
+template <typename T>
+void write_value(Bytes& w, const std::unique_ptr<T>& value);
+
+template <typename T>
+void read_value(Reader& r, std::vector<T>& value);
+template <typename T, std::size_t N>
+void read_value(Reader& r, std::array<T, N>& value);
+template <typename T>
+void read_value(Reader& r, std::set<T>& value);
+template <typename K, typename V>
+void read_value(Reader& r, std::map<K, V>& value);
+template <typename T>
+void read_value(Reader& r, std::optional<T>& value);
+template <typename T>
+void read_value(Reader& r, std::unique_ptr<T>& value);
+
+inline void write_value(Bytes& w, bool value) {
+    w.push_back(value ? 1 : 0);
+}
+
+inline void read_value(Reader& r, bool& value) {
+    std::uint8_t b = r.byte();
+    if (b > 1) {
+        throw DecodeError(std::to_string(b) + " isn't a bool");
+    }
+    value = b == 1;
+}
+
+inline void write_value(Bytes& w, std::uint8_t value) {
+    w.push_back(value);
+}
+
+inline void read_value(Reader& r, std::uint8_t& value) {
+    value = r.byte();
+}
+
+inline void write_value(Bytes& w, std::int8_t value) {
+    w.push_back(static_cast<std::uint8_t>(value));
+}
+
+inline void read_value(Reader& r, std::int8_t& value) {
+    value = static_cast<std::int8_t>(r.byte());
+}
+
+/// Unsigned integers, encoded as varints.
+template <typename T>
+void write_unsigned(Bytes& w, T value) {
+    write_varint(w, value);
+}
+
+template <typename T>
+void read_unsigned(Reader& r, T& value) {
+    std::uint64_t n = r.varint();
+    if (n > static_cast<std::uint64_t>(static_cast<T>(-1))) {
+        throw DecodeError(std::to_string(n) + " is out of range");
+    }
+    value = static_cast<T>(n);
+}
+
+/// Signed integers, encoded as zigzag varints.
+template <typename T>
+void write_signed(Bytes& w, T value) {
+    std::int64_t n = value;
+    write_varint(w, (static_cast<std::uint64_t>(n) << 1) ^ static_cast<std::uint64_t>(n >> 63));
+}
+
+template <typename T>
+void read_signed(Reader& r, T& value) {
+    std::uint64_t z = r.varint();
+    std::int64_t n = static_cast<std::int64_t>(z >> 1) ^ -static_cast<std::int64_t>(z & 1);
+
+    if constexpr (sizeof(T) < sizeof(std::int64_t)) {
+        constexpr std::int64_t max = (std::int64_t(1) << (sizeof(T) * 8 - 1)) - 1;
+        if (n < -max - 1 || n > max) {
+            throw DecodeError(std::to_string(n) + " is out of range");
+        }
+    }
+    value = static_cast<T>(n);
+}
+
+inline void write_value(Bytes& w, std::uint16_t value) { write_unsigned(w, value); }
+inline void write_value(Bytes& w, std::uint32_t value) { write_unsigned(w, value); }
+inline void write_value(Bytes& w, std::uint64_t value) { write_unsigned(w, value); }
+inline void write_value(Bytes& w, std::int16_t value) { write_signed(w, value); }
+inline void write_value(Bytes& w, std::int32_t value) { write_signed(w, value); }
+inline void write_value(Bytes& w, std::int64_t value) { write_signed(w, value); }
+
+inline void read_value(Reader& r, std::uint16_t& value) { read_unsigned(r, value); }
+inline void read_value(Reader& r, std::uint32_t& value) { read_unsigned(r, value); }
+inline void read_value(Reader& r, std::uint64_t& value) { read_unsigned(r, value); }
+inline void read_value(Reader& r, std::int16_t& value) { read_signed(r, value); }
+inline void read_value(Reader& r, std::int32_t& value) { read_signed(r, value); }
+inline void read_value(Reader& r, std::int64_t& value) { read_signed(r, value); }
+
+/// Floats, encoded little-endian whatever the byte order of the host.
+template <typename T, typename Bits>
+void write_float(Bytes& w, T value) {
+    Bits bits;
+    std::memcpy(&bits, &value, sizeof(bits));
+    for (std::size_t i = 0; i < sizeof(bits); i++) {
+        w.push_back(static_cast<std::uint8_t>(bits >> (8 * i)));
+    }
+}
+
+template <typename T, typename Bits>
+void read_float(Reader& r, T& value) {
+    const std::uint8_t* bytes = r.bytes(sizeof(Bits));
+    Bits bits = 0;
+    for (std::size_t i = 0; i < sizeof(bits); i++) {
+        bits |= static_cast<Bits>(bytes[i]) << (8 * i);
+    }
+    std::memcpy(&value, &bits, sizeof(bits));
+}
+
+inline void write_value(Bytes& w, float value) { write_float<float, std::uint32_t>(w, value); }
+inline void write_value(Bytes& w, double value) { write_float<double, std::uint64_t>(w, value); }
+inline void read_value(Reader& r, float& value) { read_float<float, std::uint32_t>(r, value); }
+inline void read_value(Reader& r, double& value) { read_float<double, std::uint64_t>(r, value); }
+
+/// Whether `s` is valid UTF-8, without overlong encodings or surrogates.
+inline bool utf8(const std::uint8_t* s, std::size_t n) {
+    std::size_t i = 0;
+    while (i < n) {
+        std::uint8_t b = s[i];
+        std::size_t len = b < 0x80 ? 1 : b >= 0xc2 && b < 0xe0 ? 2 : b >= 0xe0 && b < 0xf0 ? 3
+            : b >= 0xf0 && b < 0xf5 ? 4 : 0;
+        if (len == 0 || i + len > n) {
+            return false;
+        }
+
+        std::uint32_t c = len == 1 ? b : b & (0x7f >> len);
+        for (std::size_t j = 1; j < len; j++) {
+            if ((s[i + j] & 0xc0) != 0x80) {
+                return false;
+            }
+            c = (c << 6) | (s[i + j] & 0x3f);
+        }
+
+        bool overlong = (len == 3 && c < 0x800) || (len == 4 && c < 0x10000);
+        if (overlong || (c >= 0xd800 && c < 0xe000) || c > 0x10ffff) {
+            return false;
+        }
+        i += len;
+    }
+    return true;
+}
+
+inline void write_value(Bytes& w, const std::string& value) {
+    write_varint(w, value.size());
+    w.insert(w.end(), value.begin(), value.end());
+}
+
+inline void read_value(Reader& r, std::string& value) {
+    std::size_t n = r.count();
+    const std::uint8_t* bytes = r.bytes(n);
+    if (!utf8(bytes, n)) {
+        throw DecodeError("string isn't valid UTF-8");
+    }
+    value.assign(reinterpret_cast<const char*>(bytes), n);
+}
+
+/// Writes a value of any type, with the `write_value` of the namespace it's defined in.
+template <typename T>
+void write(Bytes& w, const T& value) {
+    write_value(w, value);
+}
+
+/// Reads a value of any type, with the `read_value` of the namespace it's defined in.
+template <typename T>
+void read(Reader& r, T& value) {
+    read_value(r, value);
+}
+
+/// Encodes a value on its own.
+template <typename T>
+Bytes encode(const T& value) {
+    Bytes w;
+    write_value(w, value);
+    return w;
+}
+
+/// Decodes a value, which must use all of `bytes`.
+template <typename T>
+T decode(const Bytes& bytes) {
+    Reader r(bytes);
+    T value{};
+    read_value(r, value);
+    if (r.left() > 0) {
+        throw DecodeError(std::to_string(r.left()) + " bytes left after the value");
+    }
+    return value;
+}
+
+template <typename T>
+void write_value(Bytes& w, const std::vector<T>& value) {
+    write_varint(w, value.size());
+    for (const T& item : value) {
+        write_value(w, item);
+    }
+}
+
+template <typename T>
+void read_value(Reader& r, std::vector<T>& value) {
+    value.clear();
+    for (std::size_t n = r.count(); n > 0; n--) {
+        value.emplace_back();
+        read_value(r, value.back());
+    }
+}
+
+/// Arrays, which are vectors of a given size.
+template <typename T, std::size_t N>
+void write_value(Bytes& w, const std::array<T, N>& value) {
+    write_varint(w, N);
+    for (const T& item : value) {
+        write_value(w, item);
+    }
+}
+
+template <typename T, std::size_t N>
+void read_value(Reader& r, std::array<T, N>& value) {
+    std::size_t n = r.count();
+    if (n != N) {
+        throw DecodeError(std::to_string(n) + " items in an array of " + std::to_string(N));
+    }
+    for (T& item : value) {
+        read_value(r, item);
+    }
+}
+
+/// Writes items sorted by their encodings, as sets and maps are.
+inline void write_sorted(Bytes& w, std::vector<Bytes>& items) {
+    std::sort(items.begin(), items.end());
+    write_varint(w, items.size());
+    for (const Bytes& item : items) {
+        w.insert(w.end(), item.begin(), item.end());
+    }
+}
+
+template <typename T>
+void write_value(Bytes& w, const std::set<T>& value) {
+    std::vector<Bytes> items;
+    for (const T& item : value) {
+        items.push_back(encode(item));
+    }
+    write_sorted(w, items);
+}
+
+template <typename T>
+void read_value(Reader& r, std::set<T>& value) {
+    value.clear();
+    for (std::size_t n = r.count(); n > 0; n--) {
+        T item{};
+        read_value(r, item);
+        value.insert(std::move(item));
+    }
+}
+
+template <typename K, typename V>
+void write_value(Bytes& w, const std::map<K, V>& value) {
+    std::vector<Bytes> entries;
+    for (const auto& entry : value) {
+        Bytes bytes = encode(entry.first);
+        write_value(bytes, entry.second);
+        entries.push_back(std::move(bytes));
+    }
+    // Keys are encoded first, and no key is the prefix of another, so the entries
+    // sort by their keys.
+    write_sorted(w, entries);
+}
+
+template <typename K, typename V>
+void read_value(Reader& r, std::map<K, V>& value) {
+    value.clear();
+    for (std::size_t n = r.count(); n > 0; n--) {
+        K key{};
+        read_value(r, key);
+        read_value(r, value[std::move(key)]);
+    }
+}
+
+/// Reads the byte telling whether an optional has a value.
+inline bool present(Reader& r) {
+    std::uint8_t b = r.byte();
+    if (b > 1) {
+        throw DecodeError(std::to_string(b) + " isn't a presence of optional");
+    }
+    return b == 1;
+}
+
+template <typename T>
+void write_value(Bytes& w, const std::optional<T>& value) {
+    w.push_back(value ? 1 : 0);
+    if (value) {
+        write_value(w, *value);
+    }
+}
+
+template <typename T>
+void read_value(Reader& r, std::optional<T>& value) {
+    if (!present(r)) {
+        value.reset();
+        return;
+    }
+    value.emplace();
+    read_value(r, *value);
+}
+
+/// Optionals of types which contain them.
+template <typename T>
+void write_value(Bytes& w, const std::unique_ptr<T>& value) {
+    w.push_back(value ? 1 : 0);
+    if (value) {
+        write_value(w, *value);
+    }
+}
+
+template <typename T>
+void read_value(Reader& r, std::unique_ptr<T>& value) {
+    if (!present(r)) {
+        value.reset();
+        return;
+    }
+    value = std::make_unique<T>();
+    read_value(r, *value);
+}
+
+/// Writes a field of a struct, tagged by its number and prefixed with its length.
+template <typename T>
+void write_field(Bytes& w, std::uint32_t tag, const T& value) {
+    Bytes bytes = encode(value);
+    write_varint(w, tag);
+    write_varint(w, bytes.size());
+    w.insert(w.end(), bytes.begin(), bytes.end());
+}
+
+} // namespace rrpc
+
+#endif // RRPC_RUNTIME_HPP
@@ -0,0 +1,82 @@
+{% macro docstring(of, indent) -%}
+{% if of.comment %}{{ indent }}"""{{ of.comment | trim }}"""
+{% endif -%}
+{%- endmacro docstring %}
+
+{% macro comment(of, indent) -%}
+{% if of.comment %}{{ indent }}#:{{ of.comment }}
+{% endif -%}
+{%- endmacro comment %}
+
+{% macro node(node, indent, top) -%}
+{% set inner = indent ~ "    " -%}
+{% if node.trait == "struct" -%}
+{{ indent }}@dataclass
+{{ indent }}class {{ node.python.name }}:
+{{ self::docstring(of=node, indent=inner) }}{% for f in node.members -%}
+{{ self::comment(of=f, indent=inner) }}{{ inner }}{{ f.python.name }}: {{ f.python.type }} = {{ f.python.default }}
+{% endfor -%}
+{% if not node.comment and not node.members %}{{ inner }}pass
+{% endif %}
+{% if top %}
+{% endif -%}
+{{ indent }}{{ node.python.name }}.__rrpc__ = rrpc.Struct({{ node.python.name }}, [
+{% for f in node.members -%}
+{{ inner }}({{ f.id }}, "{{ f.python.name }}", {{ f.python.codec }}),
+{% endfor -%}
+{{ indent }}])
+{% elif node.trait == "enum" -%}
+{{ indent }}class {{ node.python.name }}(enum.IntEnum):  # {{ node.utype.name }}
+{{ self::docstring(of=node, indent=inner) }}{% for v in node.members -%}
+{{ self::comment(of=v, indent=inner) }}{{ inner }}{{ v.python.name }} = {{ v.value }}
+{% endfor -%}
+{% if not node.comment and not node.members %}{{ inner }}pass
+{% endif %}
+{% if top %}
+{% endif -%}
+{{ indent }}{{ node.python.name }}.__rrpc__ = rrpc.Enum({{ node.python.name }}, {{ node.python.codec }})
+{% elif node.trait == "union" -%}
+{{ indent }}class {{ node.python.name }}:
+{{ self::docstring(of=node, indent=inner) }}{% if not node.comment %}{{ inner }}pass
+{% endif -%}
+{% for v in node.members %}
+{% if top %}
+{% endif -%}
+{{ self::comment(of=v, indent=indent) }}{{ indent }}@dataclass
+{{ indent }}class {{ v.python.class }}({{ node.python.name }}):
+{% if v.type %}{{ inner }}value: {{ v.python.type }} = {{ v.python.default }}
+{% else %}{{ inner }}pass
+{% endif -%}
+{% endfor %}
+{% if top %}
+{% endif -%}
+{{ indent }}{{ node.python.name }}.__rrpc__ = rrpc.Union({{ node.python.name }}, [
+{% for v in node.members -%}
+{{ inner }}({{ v.id }}, {{ v.python.class }}, {{ v.python.codec }}),
+{% endfor -%}
+{{ indent }}])
+{% elif node.trait == "interface" -%}
+{{ indent }}class {{ node.python.client }}:
+{{ inner }}"""Client of `{{ node.name }}`, which sends the calls through a transport."""
+
+{{ inner }}def __init__(self, transport: Transport) -> None:
+{{ inner }}    self._transport = transport
+{% for f in node.funcs %}
+{{ inner }}def {{ f.python.name }}(self
+    {%- for a in f.args %}, {{ a.python.name }}: {{ a.python.type }}{% endfor -%}
+    ) -> {{ f.python.ret }}:
+{{ self::docstring(of=f, indent=inner ~ "    ") }}{{ inner }}    return self._transport.call(
+{{ inner }}        "{{ node.name }}", "{{ f.name }}", [
+    {%- for a in f.args %}{{ a.python.name }}{% if not loop.last %}, {% endif %}{% endfor -%}
+    ], {{ f.python.ret }}
+{{ inner }}    )
+{% endfor -%}
+{% elif node.trait == "module" -%}
+{{ indent }}class {{ node.python.name }}:
+{{ self::docstring(of=node, indent=inner) }}{% if not node.comment and not node.nodes %}{{ inner }}pass
+{% endif -%}
+{% for child in node.nodes %}
+{{ self::node(node=child, indent=inner, top=false) }}
+{%- endfor -%}
+{% endif -%}
+{%- endmacro node %}
@@ -0,0 +1,21 @@
+{% import "python/macros.py" as m -%}
+# Generated by rrpc from {{ source }}. Do not edit.
+
+from __future__ import annotations
+
+import enum
+from dataclasses import dataclass, field
+from typing import Dict, List, Optional, Set, Tuple
+
+import rrpc
+{% if ast.python.clients -%}
+from rrpc import Transport
+{% endif -%}
+{% for import in ast.python.imports %}
+{%- if loop.first %}
+{% endif %}import {{ import.module }}{% if import.alias != import.module %} as {{ import.alias }}{% endif %}
+{% endfor -%}
+{% for node in ast.nodes %}
+
+{{ m::node(node=node, indent="", top=true) }}
+{%- endfor -%}
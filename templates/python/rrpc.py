@@ -0,0 +1,441 @@
+# Generated by rrpc. Do not edit.
+
+import struct
+from typing import Any, Callable, Dict, List, Optional, Sequence, Tuple
+
+try:
+    from typing import Protocol
+except ImportError:  # Python 3.7
+    Protocol = object
+
+
+class Transport(Protocol):
+    """Carries the calls of client classes to a server."""
+
+    def call(self, interface: str, method: str, args: List[Any], ret: Any) -> Any:
+        """Calls `method` of `interface`, with the arguments in the order they're declared.
+
+        `ret` is the annotation of the result, e.g. to decode it with, or `None` if
+        there's no result. Several results are returned as a tuple.
+        """
+        ...
+
+
+#: Version of the binary encoding.
+VERSION = 1
+
+#: Kinds of errors, by their codes in binary responses.
+KINDS = ["io", "malformed", "invalid_request", "not_found", "invalid_args", "service"]
+
+
+class DecodeError(ValueError):
+    """Bytes which aren't a value of the binary encoding."""
+
+
+class RemoteError(Exception):
+    """Error a server answered a call with."""
+
+    def __init__(self, kind: str, message: str) -> None:
+        super().__init__(f"{kind}: {message}")
+        self.kind = kind
+        self.message = message
+
+
+class Reader:
+    """Reads encoded values from bytes."""
+
+    def __init__(self, data: bytes) -> None:
+        self.data = bytes(data)
+        self.pos = 0
+
+    def left(self) -> int:
+        """Number of bytes not read yet."""
+        return len(self.data) - self.pos
+
+    def bytes(self, n: int) -> bytes:
+        if n > self.left():
+            raise DecodeError("unexpected end of message")
+
+        data = self.data[self.pos:self.pos + n]
+        self.pos += n
+        return data
+
+    def byte(self) -> int:
+        return self.bytes(1)[0]
+
+    def varint(self) -> int:
+        n = 0
+        for i in range(10):
+            b = self.byte()
+            if i == 9 and b > 1:
+                break
+
+            n |= (b & 0x7F) << (7 * i)
+            if b < 0x80:
+                return n
+
+        raise DecodeError("varint is too long")
+
+    def count(self) -> int:
+        """Reads a count of items, each of which is at least a byte long."""
+        n = self.varint()
+        if n > self.left():
+            raise DecodeError(f"count of {n} is past the end of message")
+        return n
+
+
+def write_varint(w: bytearray, n: int) -> None:
+    """Writes an unsigned LEB128 varint."""
+    while n >= 0x80:
+        w.append(n & 0x7F | 0x80)
+        n >>= 7
+    w.append(n)
+
+
+class Codec:
+    """Encodes and decodes the values of a type."""
+
+    def write(self, w: bytearray, value: Any) -> None:
+        raise NotImplementedError
+
+    def read(self, r: Reader) -> Any:
+        raise NotImplementedError
+
+
+class _Bool(Codec):
+    def write(self, w: bytearray, value: bool) -> None:
+        w.append(1 if value else 0)
+
+    def read(self, r: Reader) -> bool:
+        b = r.byte()
+        if b > 1:
+            raise DecodeError(f"{b} isn't a bool")
+        return b == 1
+
+
+class _Byte(Codec):
+    def __init__(self, signed: bool) -> None:
+        self.format = "b" if signed else "B"
+
+    def write(self, w: bytearray, value: int) -> None:
+        w += struct.pack(self.format, value)
+
+    def read(self, r: Reader) -> int:
+        return struct.unpack(self.format, r.bytes(1))[0]
+
+
+class _Unsigned(Codec):
+    """Unsigned integers, encoded as varints."""
+
+    def __init__(self, bits: int) -> None:
+        self.max = (1 << bits) - 1
+
+    def write(self, w: bytearray, value: int) -> None:
+        if not 0 <= value <= self.max:
+            raise ValueError(f"{value} is out of range")
+        write_varint(w, value)
+
+    def read(self, r: Reader) -> int:
+        n = r.varint()
+        if n > self.max:
+            raise DecodeError(f"{n} is out of range")
+        return n
+
+
+class _Signed(Codec):
+    """Signed integers, encoded as zigzag varints."""
+
+    def __init__(self, bits: int) -> None:
+        self.min = -(1 << (bits - 1))
+        self.max = (1 << (bits - 1)) - 1
+
+    def write(self, w: bytearray, value: int) -> None:
+        if not self.min <= value <= self.max:
+            raise ValueError(f"{value} is out of range")
+        write_varint(w, value << 1 if value >= 0 else (-value << 1) - 1)
+
+    def read(self, r: Reader) -> int:
+        n = r.varint()
+        n = (n >> 1) ^ -(n & 1)
+        if not self.min <= n <= self.max:
+            raise DecodeError(f"{n} is out of range")
+        return n
+
+
+class _Float(Codec):
+    """Floats, encoded little-endian."""
+
+    def __init__(self, format: str) -> None:
+        self.format = format
+        self.size = struct.calcsize(format)
+
+    def write(self, w: bytearray, value: float) -> None:
+        w += struct.pack(self.format, value)
+
+    def read(self, r: Reader) -> float:
+        return struct.unpack(self.format, r.bytes(self.size))[0]
+
+
+class _String(Codec):
+    def write(self, w: bytearray, value: str) -> None:
+        data = value.encode("utf-8")
+        write_varint(w, len(data))
+        w += data
+
+    def read(self, r: Reader) -> str:
+        try:
+            return r.bytes(r.count()).decode("utf-8")
+        except UnicodeDecodeError as e:
+            raise DecodeError(str(e)) from None
+
+
+BOOL = _Bool()
+U8 = _Byte(signed=False)
+I8 = _Byte(signed=True)
+U16 = _Unsigned(16)
+U32 = _Unsigned(32)
+U64 = _Unsigned(64)
+I16 = _Signed(16)
+I32 = _Signed(32)
+I64 = _Signed(64)
+F32 = _Float("<f")
+F64 = _Float("<d")
+STRING = _String()
+
+
+class Vector(Codec):
+    def __init__(self, item: Codec) -> None:
+        self.item = item
+
+    def write(self, w: bytearray, value: Sequence[Any]) -> None:
+        write_varint(w, len(value))
+        for item in value:
+            self.item.write(w, item)
+
+    def read(self, r: Reader) -> List[Any]:
+        return [self.item.read(r) for _ in range(r.count())]
+
+
+class Array(Vector):
+    """Arrays, which are vectors of a given size."""
+
+    def __init__(self, item: Codec, size: int) -> None:
+        super().__init__(item)
+        self.size = size
+
+    def write(self, w: bytearray, value: Sequence[Any]) -> None:
+        if len(value) != self.size:
+            raise ValueError(f"{len(value)} items in an array of {self.size}")
+        super().write(w, value)
+
+    def read(self, r: Reader) -> List[Any]:
+        items = super().read(r)
+        if len(items) != self.size:
+            raise DecodeError(f"{len(items)} items in an array of {self.size}")
+        return items
+
+
+def _sorted(items: List[Tuple[bytes, Any]]) -> List[Tuple[bytes, Any]]:
+    return sorted(items, key=lambda item: item[0])
+
+
+class Set(Codec):
+    """Sets, with the items sorted by their encodings."""
+
+    def __init__(self, item: Codec) -> None:
+        self.item = item
+
+    def write(self, w: bytearray, value: Any) -> None:
+        items = sorted(encode(item, self.item) for item in value)
+        write_varint(w, len(items))
+        for item in items:
+            w += item
+
+    def read(self, r: Reader) -> set:
+        return {self.item.read(r) for _ in range(r.count())}
+
+
+class Map(Codec):
+    """Maps, with the entries sorted by the encodings of their keys."""
+
+    def __init__(self, key: Codec, value: Codec) -> None:
+        self.key = key
+        self.value = value
+
+    def write(self, w: bytearray, value: Dict[Any, Any]) -> None:
+        entries = _sorted([(encode(k, self.key), v) for k, v in value.items()])
+        write_varint(w, len(entries))
+        for key, v in entries:
+            w += key
+            self.value.write(w, v)
+
+    def read(self, r: Reader) -> Dict[Any, Any]:
+        entries = {}
+        for _ in range(r.count()):
+            key = self.key.read(r)
+            entries[key] = self.value.read(r)
+        return entries
+
+
+class Optional_(Codec):
+    def __init__(self, item: Codec) -> None:
+        self.item = item
+
+    def write(self, w: bytearray, value: Any) -> None:
+        if value is None:
+            w.append(0)
+        else:
+            w.append(1)
+            self.item.write(w, value)
+
+    def read(self, r: Reader) -> Any:
+        b = r.byte()
+        if b > 1:
+            raise DecodeError(f"{b} isn't a presence of optional")
+        return self.item.read(r) if b else None
+
+
+class Ref(Codec):
+    """Codec of a generated class, looked up when first used, as it may not be defined yet."""
+
+    def __init__(self, cls: Callable[[], Any]) -> None:
+        self.cls = cls
+
+    def write(self, w: bytearray, value: Any) -> None:
+        self.cls().__rrpc__.write(w, value)
+
+    def read(self, r: Reader) -> Any:
+        return self.cls().__rrpc__.read(r)
+
+
+class Struct(Codec):
+    """Structs, with each field tagged by its number and prefixed with its length.
+
+    Fields are `(tag, attribute, codec)`. Fields of unknown tags are skipped, and
+    missing ones keep their defaults.
+    """
+
+    def __init__(self, cls: Any, fields: List[Tuple[int, str, Codec]]) -> None:
+        self.cls = cls
+        self.fields = fields
+        self.tags = {tag: (name, codec) for tag, name, codec in fields}
+
+    def write(self, w: bytearray, value: Any) -> None:
+        write_varint(w, len(self.fields))
+        for tag, name, codec in self.fields:
+            data = encode(getattr(value, name), codec)
+            write_varint(w, tag)
+            write_varint(w, len(data))
+            w += data
+
+    def read(self, r: Reader) -> Any:
+        value = self.cls()
+        for _ in range(r.count()):
+            tag = r.varint()
+            field = Reader(r.bytes(r.count()))
+            if tag in self.tags:
+                name, codec = self.tags[tag]
+                setattr(value, name, codec.read(field))
+        return value
+
+
+class Enum(Codec):
+    """Enums, encoded as the values of their variants."""
+
+    def __init__(self, cls: Any, utype: Codec) -> None:
+        self.cls = cls
+        self.utype = utype
+
+    def write(self, w: bytearray, value: Any) -> None:
+        self.utype.write(w, int(value))
+
+    def read(self, r: Reader) -> Any:
+        n = self.utype.read(r)
+        try:
+            return self.cls(n)
+        except ValueError:
+            raise DecodeError(f"{n} isn't a value of {self.cls.__name__}") from None
+
+
+class Union(Codec):
+    """Unions, encoded as the tag of their variant followed by its value if it has one.
+
+    Variants are `(tag, class, codec)`, with a codec of `None` if there's no value.
+    """
+
+    def __init__(self, cls: Any, variants: List[Tuple[int, Any, Optional[Codec]]]) -> None:
+        self.cls = cls
+        self.tags = {tag: (variant, codec) for tag, variant, codec in variants}
+        self.classes = {variant: (tag, codec) for tag, variant, codec in variants}
+
+    def write(self, w: bytearray, value: Any) -> None:
+        tag, codec = self.classes[type(value)]
+        write_varint(w, tag)
+        if codec is not None:
+            codec.write(w, value.value)
+
+    def read(self, r: Reader) -> Any:
+        tag = r.varint()
+        if tag not in self.tags:
+            raise DecodeError(f"{tag} isn't a variant of {self.cls.__name__}")
+
+        variant, codec = self.tags[tag]
+        return variant() if codec is None else variant(codec.read(r))
+
+
+class Tuple_(Codec):
+    """Several values, one after the other, as the arguments of calls are."""
+
+    def __init__(self, *items: Codec) -> None:
+        self.items = items
+
+    def write(self, w: bytearray, value: Sequence[Any]) -> None:
+        for codec, item in zip(self.items, value):
+            codec.write(w, item)
+
+    def read(self, r: Reader) -> Tuple[Any, ...]:
+        return tuple(codec.read(r) for codec in self.items)
+
+
+def encode(value: Any, codec: Optional[Codec] = None) -> bytes:
+    """Encodes a value, with the codec of its class unless one is given."""
+    w = bytearray()
+    (codec or type(value).__rrpc__).write(w, value)
+    return bytes(w)
+
+
+def decode(codec: Any, data: bytes) -> Any:
+    """Decodes a value with a codec or the codec of a class, which must use all of `data`."""
+    if not isinstance(codec, Codec):
+        codec = codec.__rrpc__
+
+    r = Reader(data)
+    value = codec.read(r)
+    if r.left():
+        raise DecodeError(f"{r.left()} bytes left after the value")
+    return value
+
+
+def encode_request(interface: str, method: str, args: bytes) -> bytes:
+    """Frame of a call of the binary encoding, with its encoded arguments."""
+    w = bytearray([VERSION])
+    STRING.write(w, interface)
+    STRING.write(w, method)
+    return bytes(w + args)
+
+
+def decode_response(frame: bytes) -> bytes:
+    """Encoded result of a response, or raises the error it answers with."""
+    r = Reader(frame)
+    version = r.byte()
+    if version != VERSION:
+        raise DecodeError(f"version {version} of the encoding isn't supported")
+
+    status = r.byte()
+    if status == 0:
+        return r.bytes(r.left())
+    if status == 1:
+        code = r.byte()
+        kind = KINDS[code] if code < len(KINDS) else "service"
+        raise RemoteError(kind, STRING.read(r))
+    raise DecodeError(f"{status} isn't a status")
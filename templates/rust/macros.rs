@@ -0,0 +1,244 @@
+{% macro comment(of, indent) -%}
+{% if of.comment %}{{ indent }}///{{ of.comment }}
+{% endif -%}
+{%- endmacro comment %}
+
+{% macro rename(of, indent) -%}
+{% if of.rust.rename %}{{ indent }}#[serde(rename = "{{ of.rust.rename }}")]
+{% endif -%}
+{%- endmacro rename %}
+
+{% macro node(node) -%}
+{{ self::comment(of=node, indent="") }}
+{%- if node.trait == "struct" -%}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct {{ node.rust.name }} {
+{%- for f in node.members %}
+{{ self::comment(of=f, indent="    ") }}{{ self::rename(of=f, indent="    ") }}    pub {{ f.rust.name }}: {{ f.rust.type }},
+{%- endfor %}
+}
+
+impl Default for {{ node.rust.name }} {
+    fn default() -> Self {
+        Self {
+{%- for f in node.members %}
+            {{ f.rust.name }}: {{ f.rust.default }},
+{%- endfor %}
+        }
+    }
+}
+
+impl rrpc_runtime::Encode for {{ node.rust.name }} {
+    fn encode(&self, w: &mut Vec<u8>) {
+        rrpc_runtime::write_varint(w, {{ node.members | length }});
+{%- for f in node.members %}
+        rrpc_runtime::write_field(w, {{ f.id }}, &self.{{ f.rust.name }});
+{%- endfor %}
+    }
+}
+
+impl rrpc_runtime::Decode for {{ node.rust.name }} {
+    fn decode(r: &mut rrpc_runtime::Reader) -> Result<Self, rrpc_runtime::Error> {
+        let {% if node.members %}mut {% endif %}value = Self::default();
+
+        for _ in 0..r.count()? {
+            let (tag, {% if node.members %}mut {% endif %}field) = r.field()?;
+
+            match tag {
+{%- for f in node.members %}
+                {{ f.id }} => value.{{ f.rust.name }} = field.decode()?,
+{%- endfor %}
+                // Fields of later versions are skipped.
+                _ => {}
+            }
+        }
+
+        Ok(value)
+    }
+}
+{% elif node.trait == "enum" -%}
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+{% if node.members %}#[repr({{ node.rust.repr }})]
+{% endif -%}
+pub enum {{ node.rust.name }} {
+{%- for v in node.members %}
+{{ self::comment(of=v, indent="    ") }}{{ self::rename(of=v, indent="    ") }}    {{ v.rust.name }} = {{ v.value }},
+{%- endfor %}
+}
+{% if node.members %}
+impl Default for {{ node.rust.name }} {
+    fn default() -> Self {
+        {{ node.rust.name }}::{{ node.members[0].rust.name }}
+    }
+}
+{% endif -%}
+
+impl rrpc_runtime::Encode for {{ node.rust.name }} {
+    fn encode(&self, w: &mut Vec<u8>) {
+{%- if node.members %}
+        rrpc_runtime::Encode::encode(&(*self as {{ node.rust.repr }}), w)
+{%- else %}
+        match *self {}
+{%- endif %}
+    }
+}
+
+impl rrpc_runtime::Decode for {{ node.rust.name }} {
+    fn decode(r: &mut rrpc_runtime::Reader) -> Result<Self, rrpc_runtime::Error> {
+        let value: {{ node.rust.repr }} = r.decode()?;
+
+        match value {
+{%- for v in node.members %}
+            {{ v.value }} => Ok({{ node.rust.name }}::{{ v.rust.name }}),
+{%- endfor %}
+            _ => Err(rrpc_runtime::Error::new(
+                rrpc_runtime::ErrorKind::Malformed,
+                format!("{} isn't a value of {{ node.name }}", value),
+            )),
+        }
+    }
+}
+{% elif node.trait == "union" -%}
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum {{ node.rust.name }} {
+{%- for v in node.members %}
+{{ self::comment(of=v, indent="    ") }}{{ self::rename(of=v, indent="    ") }}    {{ v.rust.name }}{% if v.rust.type %}({{ v.rust.type }}){% endif %},
+{%- endfor %}
+}
+{% if node.members %}
+impl Default for {{ node.rust.name }} {
+    fn default() -> Self {
+        {% set v = node.members[0] -%}
+        {{ node.rust.name }}::{{ v.rust.name }}{% if v.rust.type %}(Default::default()){% endif %}
+    }
+}
+{% endif -%}
+
+impl rrpc_runtime::Encode for {{ node.rust.name }} {
+    fn encode(&self, w: &mut Vec<u8>) {
+        match *self {
+{%- for v in node.members %}
+{%- if v.rust.type %}
+            {{ node.rust.name }}::{{ v.rust.name }}(ref value) => {
+                rrpc_runtime::write_varint(w, {{ v.id }});
+                rrpc_runtime::Encode::encode(value, w);
+            }
+{%- else %}
+            {{ node.rust.name }}::{{ v.rust.name }} => rrpc_runtime::write_varint(w, {{ v.id }}),
+{%- endif %}
+{%- endfor %}
+        }
+    }
+}
+
+impl rrpc_runtime::Decode for {{ node.rust.name }} {
+    fn decode(r: &mut rrpc_runtime::Reader) -> Result<Self, rrpc_runtime::Error> {
+        let tag = r.varint()?;
+
+        match tag {
+{%- for v in node.members %}
+            {{ v.id }} => Ok({{ node.rust.name }}::{{ v.rust.name }}{% if v.rust.type %}(r.decode()?){% endif %}),
+{%- endfor %}
+            _ => Err(rrpc_runtime::Error::new(
+                rrpc_runtime::ErrorKind::Malformed,
+                format!("{} isn't a variant of {{ node.name }}", tag),
+            )),
+        }
+    }
+}
+{% elif node.trait == "interface" -%}
+pub trait {{ node.rust.name }} {
+    type Error;
+{% for f in node.funcs %}
+{{ self::comment(of=f, indent="    ") }}    fn {{ f.rust.name }}(&mut self
+        {%- for a in f.args %}, {{ a.rust.name }}: {{ a.rust.type }}{% endfor -%}
+    ) -> Result<{{ f.rust.ret }}, Self::Error>;
+{%- endfor %}
+}
+
+/// Client of `{{ node.name }}`, which sends the calls through a transport.
+pub struct {{ node.rust.client }}<T> {
+    transport: T,
+}
+
+impl<T> {{ node.rust.client }}<T> {
+    pub fn new(transport: T) -> Self {
+        Self { transport }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+}
+
+impl<T: {{ node.rust.root }}Transport> {{ node.rust.name }} for {{ node.rust.client }}<T> {
+    type Error = T::Error;
+{% for f in node.funcs %}
+
+    fn {{ f.rust.name }}(&mut self
+        {%- for a in f.args %}, {{ a.rust.name }}: {{ a.rust.type }}{% endfor -%}
+    ) -> Result<{{ f.rust.ret }}, Self::Error> {
+        self.transport
+            .call("{{ node.name }}", "{{ f.name }}", {{ f.rust.params }}, {{ f.rust.args }})
+    }
+{%- endfor %}
+}
+
+/// Server of `{{ node.name }}`, which dispatches the calls to an implementation.
+pub struct {{ node.rust.server }}<S> {
+    service: S,
+}
+
+impl<S> {{ node.rust.server }}<S> {
+    pub fn new(service: S) -> Self {
+        Self { service }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.service
+    }
+}
+
+impl<S, C> rrpc_runtime::Service<C> for {{ node.rust.server }}<S>
+where
+    S: {{ node.rust.name }},
+    S::Error: std::fmt::Display,
+    C: rrpc_runtime::Codec,
+{
+    fn interface(&self) -> &str {
+        "{{ node.name }}"
+    }
+
+    fn dispatch(
+        &mut self,
+        codec: &C,
+        method: &str,
+        args: C::Value,
+    ) -> Result<C::Value, rrpc_runtime::Error> {
+        match method {
+{%- for f in node.funcs %}
+            "{{ f.name }}" => {
+                let {{ f.rust.args }}: {{ f.rust.types }} = codec
+                    .decode_args({{ f.rust.params }}, args)
+                    .map_err(rrpc_runtime::Error::invalid_args)?;
+                let ret = self.service
+                    .{{ f.rust.name }}({{ f.rust.names }})
+                    .map_err(rrpc_runtime::Error::service)?;
+                codec.encode(&ret)
+            }
+{%- endfor %}
+            _ => Err(rrpc_runtime::Error::not_found("{{ node.name }}", method)),
+        }
+    }
+}
+{% elif node.trait == "module" -%}
+pub mod {{ node.rust.name }} {
+{%- for child in node.nodes %}
+{{ self::node(node=child) }}
+{%- endfor %}
+}
+{% endif -%}
+{%- endmacro node %}
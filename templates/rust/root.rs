@@ -0,0 +1,16 @@
+{% import "rust/macros.rs" as m -%}
+// Generated by rrpc from {{ source }}. Do not edit.
+
+#![allow(dead_code, non_camel_case_types, non_snake_case, unused_imports, clippy::all)]
+
+pub use rrpc_runtime::Transport;
+{% for ns in order -%}
+{% set defs = modules[ns] %}
+{% for mod in defs.rust.mods %}pub mod {{ mod }} {
+{% endfor -%}
+{% for node in defs.nodes -%}
+{{ m::node(node=node) }}
+{% endfor -%}
+{% for mod in defs.rust.mods %}}
+{% endfor -%}
+{% endfor -%}
@@ -0,0 +1,72 @@
+{% macro comment(of, indent) -%}
+{% if of.comment %}{{ indent }}/**{{ of.comment }} */
+{% endif -%}
+{%- endmacro comment %}
+
+{% macro node(node, indent) -%}
+{{ self::comment(of=node, indent=indent) }}
+{%- if node.trait == "struct" -%}
+{{ indent }}export interface {{ node.typescript.name }} {
+{%- for f in node.members %}
+{{ self::comment(of=f, indent=indent ~ "    ") }}{{ indent }}    {{ f.name }}: {{ f.typescript.type }};
+{%- endfor %}
+{{ indent }}}
+
+{{ indent }}export const {{ node.typescript.name }}: rrpc.Codec<{{ node.typescript.name }}> = rrpc.struct<{{ node.typescript.name }}>(
+{{ indent }}    {{ node.typescript.defaults }},
+{{ indent }}    [
+{%- for f in node.members %}
+{{ indent }}        [{{ f.id }}, {{ f.name | json_encode }}, {{ f.typescript.codec }}],
+{%- endfor %}
+{{ indent }}    ],
+{{ indent }});
+{% elif node.trait == "enum" -%}
+{{ indent }}export type {{ node.typescript.name }} = {{ node.typescript.type }};
+
+{{ indent }}export const {{ node.typescript.name }}: rrpc.Codec<{{ node.typescript.name }}> = rrpc.enumeration<{{ node.typescript.name }}, {{ node.typescript.utype }}>({{ node.typescript.codec }}, [
+{%- for v in node.members %}
+{{ indent }}    [{{ v.name | json_encode }}, {{ v.typescript.value }}],
+{%- endfor %}
+{{ indent }}]);
+{% elif node.trait == "union" -%}
+{{ indent }}export type {{ node.typescript.name }} = {{ node.typescript.type }};
+
+{{ indent }}export const {{ node.typescript.name }}: rrpc.Codec<{{ node.typescript.name }}> = rrpc.union<{{ node.typescript.name }}>([
+{%- for v in node.members %}
+{{ indent }}    [{{ v.id }}, {{ v.name | json_encode }}, {{ v.typescript.codec }}],
+{%- endfor %}
+{{ indent }}]);
+{% elif node.trait == "interface" -%}
+{{ indent }}export interface {{ node.typescript.name }} {
+{%- for f in node.funcs %}
+{{ self::comment(of=f, indent=indent ~ "    ") }}{{ indent }}    {{ f.typescript.name }}(
+    {%- for a in f.args %}{{ a.typescript.name }}: {{ a.typescript.type }}{% if not loop.last %}, {% endif %}{% endfor -%}
+    ): Promise<{{ f.typescript.ret }}>;
+{%- endfor %}
+{{ indent }}}
+
+{{ indent }}/** Client of `{{ node.name }}`, which sends the calls through a transport. */
+{{ indent }}export class {{ node.typescript.client }} implements {{ node.typescript.name }} {
+{{ indent }}    private readonly transport: Transport;
+
+{{ indent }}    constructor(transport: Transport) {
+{{ indent }}        this.transport = transport;
+{{ indent }}    }
+{%- for f in node.funcs %}
+
+{{ indent }}    {{ f.typescript.name }}(
+    {%- for a in f.args %}{{ a.typescript.name }}: {{ a.typescript.type }}{% if not loop.last %}, {% endif %}{% endfor -%}
+    ): Promise<{{ f.typescript.ret }}> {
+{{ indent }}        return this.transport("{{ node.name }}", "{{ f.name }}", [
+    {%- for a in f.args %}{{ a.typescript.name }}{% if not loop.last %}, {% endif %}{% endfor -%}
+    ]) as Promise<{{ f.typescript.ret }}>;
+{{ indent }}    }
+{%- endfor %}
+{{ indent }}}
+{% elif node.trait == "module" -%}
+{{ indent }}export namespace {{ node.typescript.name }} {
+{%- for child in node.nodes %}
+{{ self::node(node=child, indent=indent ~ "    ") }}
+{%- endfor %}{{ indent }}}
+{% endif -%}
+{%- endmacro node %}
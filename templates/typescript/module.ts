@@ -0,0 +1,14 @@
+{% import "typescript/macros.ts" as m -%}
+// Generated by rrpc from {{ source }}. Do not edit.
+
+import * as rrpc from "{{ ast.typescript.runtime }}";
+{% if ast.typescript.clients -%}
+import type { Transport } from "{{ ast.typescript.runtime }}";
+{% endif -%}
+{% for import in ast.typescript.imports %}
+{%- if loop.first %}
+{% endif %}import * as {{ import.alias }} from "{{ import.path }}";
+{% endfor -%}
+{% for node in ast.nodes %}
+{{ m::node(node=node, indent="") }}
+{%- endfor -%}
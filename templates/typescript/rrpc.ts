@@ -0,0 +1,497 @@
+// Generated by rrpc. Do not edit.
+
+/**
+ * Carries the calls of client classes to a server.
+ *
+ * The arguments of a call are in the order they're declared, and the promise
+ * resolves to the result, which is an array if there's more than one.
+ */
+export type Transport = (iface: string, method: string, args: unknown[]) => Promise<unknown>;
+
+/** Version of the binary encoding. */
+export const VERSION = 1;
+
+/** Kinds of errors, by their codes in binary responses. */
+export const KINDS = ["io", "malformed", "invalid_request", "not_found", "invalid_args", "service"];
+
+/** Bytes which aren't a value of the binary encoding. */
+export class DecodeError extends Error {}
+
+/** Error a server answered a call with. */
+export class RemoteError extends Error {
+    readonly kind: string;
+
+    constructor(kind: string, message: string) {
+        super(`${kind}: ${message}`);
+        this.kind = kind;
+    }
+}
+
+/** Reads encoded values from bytes. */
+export class Reader {
+    private readonly data: Uint8Array;
+    private pos = 0;
+
+    constructor(data: Uint8Array) {
+        this.data = data;
+    }
+
+    /** Number of bytes not read yet. */
+    left(): number {
+        return this.data.length - this.pos;
+    }
+
+    bytes(n: number): Uint8Array {
+        if (n > this.left()) {
+            throw new DecodeError("unexpected end of message");
+        }
+
+        const bytes = this.data.subarray(this.pos, this.pos + n);
+        this.pos += n;
+        return bytes;
+    }
+
+    byte(): number {
+        return this.bytes(1)[0];
+    }
+
+    varint(): bigint {
+        let n = 0n;
+        for (let i = 0; i < 10; i++) {
+            const b = this.byte();
+            if (i === 9 && b > 1) {
+                break;
+            }
+
+            n |= BigInt(b & 0x7f) << BigInt(7 * i);
+            if (b < 0x80) {
+                return n;
+            }
+        }
+
+        throw new DecodeError("varint is too long");
+    }
+
+    /** Reads a count of items, each of which is at least a byte long. */
+    count(): number {
+        const n = this.varint();
+        if (n > BigInt(this.left())) {
+            throw new DecodeError(`count of ${n} is past the end of message`);
+        }
+        return Number(n);
+    }
+}
+
+/** Writes an unsigned LEB128 varint. */
+export function writeVarint(w: number[], n: bigint | number): void {
+    let v = BigInt(n);
+    while (v >= 0x80n) {
+        w.push(Number(v & 0x7fn) | 0x80);
+        v >>= 7n;
+    }
+    w.push(Number(v));
+}
+
+/**
+ * Encodes and decodes the values of a type, in the JSON shape of the generated types.
+ *
+ * Integers are numbers, but the 64-bit ones, which are bigints so as to keep all of
+ * their bits.
+ */
+export interface Codec<T> {
+    write(w: number[], value: T): void;
+    read(r: Reader): T;
+    /** Value of a key of a map, which is a string in JSON. */
+    key?(key: string): T;
+}
+
+// Codecs of fields and variants are of any type.
+// eslint-disable-next-line @typescript-eslint/no-explicit-any
+type AnyCodec = Codec<any>;
+
+function integer(min: bigint, max: bigint, value: number | bigint): bigint {
+    if (typeof value === "number" && !Number.isInteger(value)) {
+        throw new RangeError(`${value} isn't an integer`);
+    }
+
+    const n = BigInt(value);
+    if (n < min || n > max) {
+        throw new RangeError(`${value} is out of range`);
+    }
+    return n;
+}
+
+function checked(min: bigint, max: bigint, n: bigint): bigint {
+    if (n < min || n > max) {
+        throw new DecodeError(`${n} is out of range`);
+    }
+    return n;
+}
+
+export const bool: Codec<boolean> = {
+    write: (w, value) => w.push(value ? 1 : 0),
+    read: (r) => {
+        const b = r.byte();
+        if (b > 1) {
+            throw new DecodeError(`${b} isn't a bool`);
+        }
+        return b === 1;
+    },
+    key: (key) => key === "true",
+};
+
+/** Integers of a byte. */
+function byte(signed: boolean): Codec<number> {
+    const min = signed ? -128n : 0n;
+    const max = signed ? 127n : 255n;
+
+    return {
+        write: (w, value) => w.push(Number(integer(min, max, value)) & 0xff),
+        read: (r) => {
+            const b = r.byte();
+            return signed && b > 127 ? b - 256 : b;
+        },
+        key: Number,
+    };
+}
+
+/** Unsigned integers, encoded as varints, of which `as` makes numbers or bigints. */
+function unsigned<T extends number | bigint>(bits: number, as: (n: bigint) => T): Codec<T> {
+    const max = (1n << BigInt(bits)) - 1n;
+
+    return {
+        write: (w, value) => writeVarint(w, integer(0n, max, value)),
+        read: (r) => as(checked(0n, max, r.varint())),
+        key: (key) => as(BigInt(key)),
+    };
+}
+
+/** Signed integers, encoded as zigzag varints, of which `as` makes numbers or bigints. */
+function signed<T extends number | bigint>(bits: number, as: (n: bigint) => T): Codec<T> {
+    const min = -(1n << BigInt(bits - 1));
+    const max = (1n << BigInt(bits - 1)) - 1n;
+
+    return {
+        write: (w, value) => {
+            const n = integer(min, max, value);
+            writeVarint(w, n >= 0n ? n << 1n : (-n << 1n) - 1n);
+        },
+        read: (r) => {
+            const n = r.varint();
+            return as(checked(min, max, n & 1n ? -(n >> 1n) - 1n : n >> 1n));
+        },
+        key: (key) => as(BigInt(key)),
+    };
+}
+
+/** Floats, encoded little-endian. */
+function float(size: 4 | 8): Codec<number> {
+    return {
+        write: (w, value) => {
+            const view = new DataView(new ArrayBuffer(size));
+            if (size === 4) {
+                view.setFloat32(0, value, true);
+            } else {
+                view.setFloat64(0, value, true);
+            }
+            w.push(...new Uint8Array(view.buffer));
+        },
+        read: (r) => {
+            const bytes = r.bytes(size);
+            const view = new DataView(bytes.buffer, bytes.byteOffset, size);
+            return size === 4 ? view.getFloat32(0, true) : view.getFloat64(0, true);
+        },
+        key: Number,
+    };
+}
+
+export const u8 = byte(false);
+export const i8 = byte(true);
+export const u16 = unsigned(16, Number);
+export const u32 = unsigned(32, Number);
+export const u64 = unsigned(64, BigInt);
+export const i16 = signed(16, Number);
+export const i32 = signed(32, Number);
+export const i64 = signed(64, BigInt);
+export const f32 = float(4);
+export const f64 = float(8);
+
+/** Strings, which are named `str` as `string` is a type. */
+export const str: Codec<string> = {
+    write: (w, value) => {
+        const bytes = new TextEncoder().encode(value);
+        writeVarint(w, bytes.length);
+        w.push(...bytes);
+    },
+    read: (r) => {
+        try {
+            return new TextDecoder("utf-8", { fatal: true }).decode(r.bytes(r.count()));
+        } catch (e) {
+            throw new DecodeError(String(e));
+        }
+    },
+};
+
+export function vector<T>(item: Codec<T>): Codec<T[]> {
+    return {
+        write: (w, value) => {
+            writeVarint(w, value.length);
+            for (const v of value) {
+                item.write(w, v);
+            }
+        },
+        read: (r) => {
+            const items: T[] = [];
+            for (let n = r.count(); n > 0; n--) {
+                items.push(item.read(r));
+            }
+            return items;
+        },
+    };
+}
+
+/** Arrays, which are vectors of a given size. */
+export function array<T>(item: Codec<T>, size: number): Codec<T[]> {
+    const items = vector(item);
+
+    return {
+        write: (w, value) => {
+            if (value.length !== size) {
+                throw new RangeError(`${value.length} items in an array of ${size}`);
+            }
+            items.write(w, value);
+        },
+        read: (r) => {
+            const value = items.read(r);
+            if (value.length !== size) {
+                throw new DecodeError(`${value.length} items in an array of ${size}`);
+            }
+            return value;
+        },
+    };
+}
+
+function compare(a: Uint8Array, b: Uint8Array): number {
+    for (let i = 0; i < a.length && i < b.length; i++) {
+        if (a[i] !== b[i]) {
+            return a[i] - b[i];
+        }
+    }
+    return a.length - b.length;
+}
+
+/** Sets, with the items sorted by their encodings. */
+export function set<T>(item: Codec<T>): Codec<T[]> {
+    return {
+        write: (w, value) => {
+            const items = value.map((v) => encode(item, v)).sort(compare);
+            writeVarint(w, items.length);
+            for (const bytes of items) {
+                w.push(...bytes);
+            }
+        },
+        read: vector(item).read,
+    };
+}
+
+/** Maps, with the entries sorted by the encodings of their keys. */
+export function map<K, V>(key: Codec<K>, value: Codec<V>): Codec<Record<string, V>> {
+    return {
+        write: (w, entries) => {
+            const sorted = Object.entries(entries)
+                .map(([k, v]): [Uint8Array, V] => [
+                    encode(key, key.key ? key.key(k) : (k as unknown as K)),
+                    v,
+                ])
+                .sort((a, b) => compare(a[0], b[0]));
+
+            writeVarint(w, sorted.length);
+            for (const [k, v] of sorted) {
+                w.push(...k);
+                value.write(w, v);
+            }
+        },
+        read: (r) => {
+            const entries: Record<string, V> = {};
+            for (let n = r.count(); n > 0; n--) {
+                const k = String(key.read(r));
+                entries[k] = value.read(r);
+            }
+            return entries;
+        },
+    };
+}
+
+export function optional<T>(item: Codec<T>): Codec<T | null> {
+    return {
+        write: (w, value) => {
+            if (value === null || value === undefined) {
+                w.push(0);
+            } else {
+                w.push(1);
+                item.write(w, value);
+            }
+        },
+        read: (r) => {
+            const b = r.byte();
+            if (b > 1) {
+                throw new DecodeError(`${b} isn't a presence of optional`);
+            }
+            return b ? item.read(r) : null;
+        },
+    };
+}
+
+/** Codec looked up when first used, as it may not be defined yet. */
+export function lazy<T>(codec: () => Codec<T>): Codec<T> {
+    return {
+        write: (w, value) => codec().write(w, value),
+        read: (r) => codec().read(r),
+        key: (key) => {
+            const c = codec();
+            return c.key ? c.key(key) : (key as unknown as T);
+        },
+    };
+}
+
+/**
+ * Structs, with each field tagged by its number and prefixed with its length.
+ *
+ * Fields are `[tag, name, codec]`. Fields of unknown tags are skipped, and missing
+ * ones are taken from `defaults`.
+ */
+export function struct<T>(defaults: T, fields: [number, string, AnyCodec][]): Codec<T> {
+    return {
+        write: (w, value) => {
+            writeVarint(w, fields.length);
+            for (const [tag, name, codec] of fields) {
+                const bytes = encode(codec, (value as Record<string, unknown>)[name]);
+                writeVarint(w, tag);
+                writeVarint(w, bytes.length);
+                w.push(...bytes);
+            }
+        },
+        read: (r) => {
+            // Unlike JSON, a structured clone keeps bigints.
+            const value = structuredClone(defaults) as Record<string, unknown>;
+            for (let n = r.count(); n > 0; n--) {
+                const tag = Number(r.varint());
+                const field = new Reader(r.bytes(r.count()));
+                const known = fields.find((f) => f[0] === tag);
+                if (known) {
+                    value[known[1]] = known[2].read(field);
+                }
+            }
+            return value as T;
+        },
+    };
+}
+
+/** Enums, encoded as the values of their variants, which are `[name, value]`. */
+export function enumeration<T extends string, N extends number | bigint>(
+    utype: Codec<N>,
+    variants: [T, N][],
+): Codec<T> {
+    return {
+        write: (w, value) => {
+            const variant = variants.find((v) => v[0] === value);
+            if (!variant) {
+                throw new RangeError(`${value} isn't a variant`);
+            }
+            utype.write(w, variant[1]);
+        },
+        read: (r) => {
+            const n = utype.read(r);
+            const variant = variants.find((v) => v[1] === n);
+            if (!variant) {
+                throw new DecodeError(`${n} isn't a value of the enum`);
+            }
+            return variant[0];
+        },
+    };
+}
+
+/**
+ * Unions, encoded as the tag of their variant followed by its value if it has one.
+ *
+ * Variants are `[tag, name, codec]`, with a codec of `null` if there's no value.
+ */
+export function union<T>(variants: [number, string, AnyCodec | null][]): Codec<T> {
+    return {
+        write: (w, value) => {
+            const [name, payload] =
+                typeof value === "string"
+                    ? [value, undefined]
+                    : Object.entries(value as Record<string, unknown>)[0];
+            const variant = variants.find((v) => v[1] === name);
+            if (!variant || (variant[2] === null) !== (payload === undefined)) {
+                throw new RangeError(`${name} isn't a variant`);
+            }
+
+            writeVarint(w, variant[0]);
+            variant[2]?.write(w, payload);
+        },
+        read: (r) => {
+            const tag = Number(r.varint());
+            const variant = variants.find((v) => v[0] === tag);
+            if (!variant) {
+                throw new DecodeError(`${tag} isn't a variant of the union`);
+            }
+
+            const [, name, codec] = variant;
+            return (codec ? { [name]: codec.read(r) } : name) as unknown as T;
+        },
+    };
+}
+
+/** Several values, one after the other, as the arguments of calls are. */
+export function tuple(...items: AnyCodec[]): Codec<unknown[]> {
+    return {
+        write: (w, value) => items.forEach((codec, i) => codec.write(w, value[i])),
+        read: (r) => items.map((codec) => codec.read(r)),
+    };
+}
+
+export function encode<T>(codec: Codec<T>, value: T): Uint8Array {
+    const w: number[] = [];
+    codec.write(w, value);
+    return Uint8Array.from(w);
+}
+
+/** Decodes a value, which must use all of `data`. */
+export function decode<T>(codec: Codec<T>, data: Uint8Array): T {
+    const r = new Reader(data);
+    const value = codec.read(r);
+    if (r.left() > 0) {
+        throw new DecodeError(`${r.left()} bytes left after the value`);
+    }
+    return value;
+}
+
+/** Frame of a call of the binary encoding, with its encoded arguments. */
+export function encodeRequest(iface: string, method: string, args: Uint8Array): Uint8Array {
+    const w = [VERSION];
+    str.write(w, iface);
+    str.write(w, method);
+    w.push(...args);
+    return Uint8Array.from(w);
+}
+
+/** Encoded result of a response, or throws the error it answers with. */
+export function decodeResponse(frame: Uint8Array): Uint8Array {
+    const r = new Reader(frame);
+    const version = r.byte();
+    if (version !== VERSION) {
+        throw new DecodeError(`version ${version} of the encoding isn't supported`);
+    }
+
+    const status = r.byte();
+    if (status === 0) {
+        return r.bytes(r.left());
+    }
+    if (status === 1) {
+        const kind = KINDS[r.byte()] ?? "service";
+        throw new RemoteError(kind, str.read(r));
+    }
+    throw new DecodeError(`${status} isn't a status`);
+}
@@ -0,0 +1,168 @@
+extern crate error_chain;
+extern crate rrpc;
+
+use std::fs;
+use std::path::Path;
+
+use error_chain::ChainedError;
+
+/// Definitions whose encodings are worked out by hand from `docs/binary.md`.
+const DEFS: &str = r#"
+struct Scalars {
+    b: bool {true},
+    u: u8 {255},
+    i: i8 {-128},
+    w: u16 {300},
+    n: i32 {-65},
+    l: u64 {18446744073709551615},
+    f: f32 {1.5},
+    d: f64 {-0.25},
+    s: string {"é"},
+}
+
+struct Bag {
+    v: vector<u8> {[1, 2]},
+    a: array<i8, 2> {[-1, 1]},
+    o: optional<u16> {7},
+    n: optional<string>,
+    m: map<string, bool> {{"b": true, "a": false}},
+    t: set<i32> {[1, -1]},
+}
+
+enum Kind(i16) {
+    A{-1},
+    B,
+}
+
+union Shape {
+    Circle: f64,
+    Unit,
+    Point: geo::Point,
+}
+
+mod geo {
+    struct Point {
+        x: i64,
+        y: i64 {-2},
+    }
+}
+
+struct Tagged {
+    reserved 1, 4 to 6, old;
+    2: a: u8,
+    7: b: bool,
+    c: string,
+    300: d: i8 {-1},
+}
+"#;
+
+const EXPECTED: &[(&str, &str)] = &[
+    (
+        "Scalars",
+        concat!(
+            "09",
+            "010101",
+            "0201ff",
+            "030180",
+            "0402ac02",
+            "05028101",
+            "060affffffffffffffffff01",
+            "07040000c03f",
+            "0808000000000000d0bf",
+            "090302c3a9",
+        ),
+    ),
+    (
+        "Bag",
+        concat!(
+            "06",
+            "0103020102",
+            "020302ff01",
+            "03020107",
+            "040100",
+            "050702016100016201",
+            "0603020102",
+        ),
+    ),
+    ("Kind", "01"),
+    ("Kind", "00"),
+    ("Shape", "010000000000000000"),
+    ("Shape", "02"),
+    ("Shape", "0302010100020103"),
+    ("geo::Point", "02010100020103"),
+    ("Tagged", "04020100070100080100ac0201ff"),
+];
+
+/// Vectors of `DEFS`, compiled from a file of its own for each test.
+fn vectors(name: &str) -> Vec<serde_json::Value> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/binary");
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join(format!("{}.rpc", name));
+    fs::write(&input, DEFS).unwrap();
+
+    let files = rrpc::render_files("vectors", &input.to_string_lossy(), "", &Default::default())
+        .unwrap_or_else(|e| panic!("{}", e));
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path, "vectors.json");
+
+    let document: serde_json::Value = serde_json::from_str(&files[0].contents).unwrap();
+    assert_eq!(document["version"], 1);
+
+    document["vectors"].as_array().unwrap().clone()
+}
+
+#[test]
+fn vectors_follow_the_spec() {
+    let vectors = vectors("spec");
+    let hex = vectors
+        .iter()
+        .map(|v| (v["type"].as_str().unwrap(), v["hex"].as_str().unwrap()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(hex, EXPECTED);
+}
+
+#[test]
+fn vectors_have_complete_values() {
+    let vectors = vectors("values");
+
+    assert_eq!(vectors[1]["value"]["n"], serde_json::Value::Null);
+    assert_eq!(vectors[1]["value"]["m"], json(r#"{"a": false, "b": true}"#));
+    assert_eq!(vectors[4]["value"], json(r#"{"Circle": 0.0}"#));
+    assert_eq!(vectors[5]["value"], json(r#""Unit""#));
+    assert_eq!(vectors[6]["value"], json(r#"{"Point": {"x": 0, "y": -2}}"#));
+}
+
+fn json(s: &str) -> serde_json::Value {
+    serde_json::from_str(s).unwrap()
+}
+
+/// Error of compiling `source`.
+fn error(name: &str, source: &str) -> String {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/binary");
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join(format!("{}.rpc", name));
+    fs::write(&input, source).unwrap();
+
+    match rrpc::render_files("vectors", &input.to_string_lossy(), "", &Default::default()) {
+        Ok(_) => panic!("{} compiled", source),
+        Err(e) => e.display_chain().to_string(),
+    }
+}
+
+#[test]
+fn ordinals_are_checked() {
+    let cases = [
+        ("duplicated", "struct A { 2: a: u8, 2: b: u8, }", "ordinal 2 is already used by a"),
+        ("implicit", "struct A { a: u8, 1: b: u8, }", "ordinal 1 is already used by a"),
+        ("zero", "struct A { 0: a: u8, }", "ordinal needs to be between 1 and"),
+        ("reserved_id", "struct A { reserved 2 to 3; a: u8, b: u8, }", "ordinal 2 is reserved"),
+        ("reserved_name", "struct A { reserved b; a: u8, b: u8, }", "name b is reserved"),
+        ("range", "struct A { reserved 3 to 2; }", "range ends before it starts"),
+    ];
+
+    for &(name, source, message) in &cases {
+        let e = error(name, source);
+        assert!(e.contains(message), "{}: {}", source, e);
+    }
+}
@@ -0,0 +1,319 @@
+extern crate rrpc;
+
+mod common;
+
+use std::path::Path;
+
+fn check(path: &Path, opts: &rrpc::Options) -> Result<rrpc::Model, Vec<rrpc::Diagnostic>> {
+    diagnostics(rrpc::generate("null", &path.to_string_lossy(), opts))
+}
+
+/// Diagnostics of a failed compile.
+fn diagnostics<T>(result: rrpc::Result<T>) -> Result<T, Vec<rrpc::Diagnostic>> {
+    result.map_err(|e| {
+        let mut cause = Some(&e);
+
+        while let Some(e) = cause {
+            if let rrpc::ErrorKind::Diagnostics(ref diags) = *e.kind() {
+                return diags.clone();
+            }
+
+            cause = e.1.next_error.as_ref().and_then(|e| e.downcast_ref::<rrpc::Error>());
+        }
+
+        panic!("no diagnostics: {}", e)
+    })
+}
+
+/// Codes and lines of the diagnostics of compiling `source`.
+fn errors(name: &str, source: &str) -> Vec<(&'static str, usize)> {
+    let path = common::entry("check", name, &[("main.rpc", source)]);
+
+    check(&path, &Default::default())
+        .map(|_| panic!("{} compiled", source))
+        .unwrap_err()
+        .iter()
+        .map(|d| (d.code, d.start.map_or(0, |l| l.line)))
+        .collect()
+}
+
+#[test]
+fn infinite_types_are_rejected_in_any_order() {
+    let first = "struct A {\n    x: vector<B>,\n    y: B,\n}\n\nstruct B {\n    a: A,\n}\n";
+    let second = "struct A {\n    y: B,\n    x: vector<B>,\n}\n\nstruct B {\n    a: A,\n}\n";
+
+    assert_eq!(errors("direct_last", first), [("E0009", 3)]);
+    assert_eq!(errors("direct_first", second), [("E0009", 2)]);
+    assert_eq!(errors("self", "struct A {\n    a: A,\n}\n"), [("E0009", 2)]);
+}
+
+#[test]
+fn infinite_types_are_rejected_through_modules() {
+    let outer = "struct A {\n    b: m::B,\n}\n\n\
+                 mod m {\n    struct B {\n        a: A,\n    }\n}\n";
+    let nested = "mod m {\n    struct B {\n        c: n::C,\n    }\n\n    \
+                  mod n {\n        struct C {\n            b: B,\n        }\n    }\n}\n";
+
+    assert_eq!(errors("module_outer", outer), [("E0009", 2)]);
+    assert_eq!(errors("module_nested", nested), [("E0009", 3)]);
+
+    let source = "struct A {\n    b: optional<m::B>,\n}\n\n\
+                  mod m {\n    struct B {\n        a: A,\n    }\n}\n";
+    let path = common::entry("check", "module_indirect", &[("main.rpc", source)]);
+
+    assert!(check(&path, &Default::default()).is_ok());
+}
+
+#[test]
+fn cycles_through_indirection_are_allowed() {
+    let source = "struct A {\n    b: optional<B>,\n}\n\nstruct B {\n    a: A,\n    c: vector<B>,\n}\n";
+    let path = common::entry("check", "indirect", &[("main.rpc", source)]);
+
+    assert!(check(&path, &Default::default()).is_ok());
+}
+
+#[test]
+fn union_variants_have_unique_ordinals() {
+    let source = "union U {\n    2: A,\n    B: u8,\n    3: C,\n}\n";
+
+    assert_eq!(errors("union_ordinals", source), [("E0014", 4)]);
+}
+
+#[test]
+fn unions_hold_payloads_of_any_type() {
+    let source = "struct A {\n    s: S,\n    v: vector<S>,\n}\n\n\
+                  union S {\n    A: optional<A>,\n    B: map<string, u8>,\n    C,\n}\n";
+    let path = common::entry("check", "unions", &[("main.rpc", source)]);
+
+    assert!(check(&path, &Default::default()).is_ok());
+
+    let source = "union U {\n    A: Missing,\n    B: u8,\n    B,\n}\n";
+    assert_eq!(errors("union_errors", source), [("E0006", 2), ("E0007", 4)]);
+}
+
+/// Registers `pair<K, V>`, which takes a key and any type.
+struct Pairs;
+
+impl rrpc::LangGenerator for Pairs {
+    fn templates(&mut self) -> Vec<rrpc::TemplateDef> {
+        let params = vec![rrpc::ParamKind::Key, rrpc::ParamKind::Type];
+        vec![rrpc::TemplateDef::new("pair", params, false)]
+    }
+}
+
+#[test]
+fn templates_are_checked_against_their_declarations() {
+    let fields = [
+        ("vectr<u32>", "E0008"),
+        ("map<u32>", "E0008"),
+        ("map<vector<u8>, u8>", "E0008"),
+        ("array<u8>", "E0008"),
+        ("optional<u8, u8>", "E0008"),
+        ("vector<Missing>", "E0006"),
+        ("pair<u8, string>", "E0008"),
+    ];
+
+    for (i, &(ty, code)) in fields.iter().enumerate() {
+        let source = format!("struct A {{\n    x: {},\n}}\n", ty);
+
+        assert_eq!(errors(&format!("template{}", i), &source), [(code, 2)], "{}", ty);
+    }
+
+    // Templates registered by a generator are only known to it.
+    rrpc::register_generator("pairs", Pairs);
+
+    let source = "struct A {\n    x: pair<u8, vector<string>>,\n    y: pair<string, u8>,\n}\n";
+    let path = common::entry("check", "registered", &[("main.rpc", source)]);
+    assert!(diagnostics(rrpc::generate("pairs", &path.to_string_lossy(), &Default::default()))
+        .is_ok());
+
+    let source = "struct A {\n    x: pair<vector<u8>, u8>,\n}\n";
+    let path = common::entry("check", "registered_key", &[("main.rpc", source)]);
+    let diags = diagnostics(rrpc::generate("pairs", &path.to_string_lossy(), &Default::default()))
+        .map(|_| panic!("compiled"))
+        .unwrap_err();
+    assert_eq!(diags.iter().map(|d| d.code).collect::<Vec<_>>(), ["E0008"]);
+}
+
+#[test]
+fn signatures_take_generic_types() {
+    let source = "struct Item {}\n\ninterface api(p) {\n    \
+                  fn list() -> vector<Item>;\n    \
+                  fn put(items: map<string, u32>, ids: set<u64>) -> optional<Item>;\n    \
+                  fn pair(a: array<u8, 4>) -> (vector<Item>, map<u8, Item>);\n}\n";
+    let path = common::entry("check", "signatures", &[("main.rpc", source)]);
+
+    assert!(check(&path, &Default::default()).is_ok());
+
+    let source = "interface api(p) {\n    fn get(a: vectr<u8>) -> map<u8>;\n}\n";
+    assert_eq!(errors("bad_signatures", source), [("E0008", 2), ("E0008", 2)]);
+}
+
+#[test]
+fn default_values_match_their_types() {
+    let fields = [
+        "x: u32 {\"hello\"}",
+        "x: bool {3}",
+        "x: u8 {300}",
+        "x: i8 {-129}",
+        "x: f32 {\"1.5\"}",
+        "x: vector<u8> {[1, 256]}",
+        "x: array<u8, 2> {[1]}",
+        "x: Point {{\"z\": 1}}",
+        "x: Kind {\"C\"}",
+        "x: map<u8, string> {{\"256\": \"a\"}}",
+        "x: map<u64, string> {{\"-1\": \"a\"}}",
+        "x: map<bool, u8> {{\"yes\": 1}}",
+        "x: map<Kind, u8> {{\"C\": 1}}",
+    ];
+    let types = "struct Point {\n    y: u8,\n}\n\nenum Kind(u8) {\n    A,\n    B,\n}\n";
+
+    for (i, field) in fields.iter().enumerate() {
+        let source = format!("struct A {{\n    {},\n}}\n\n{}", field, types);
+
+        assert_eq!(errors(&format!("value{}", i), &source), [("E0005", 2)], "{}", field);
+    }
+
+    let source = format!(
+        "struct A {{\n    \
+         a: map<u8, string> {{{{\"255\": \"a\"}}}},\n    \
+         b: map<bool, u8> {{{{\"true\": 1}}}},\n    \
+         c: map<Kind, vector<u8>> {{{{\"B\": [2]}}}},\n    \
+         d: optional<Point> {{[3]}},\n\
+         }}\n\n{}",
+        types
+    );
+    let path = common::entry("check", "values", &[("main.rpc", &source)]);
+
+    assert!(check(&path, &Default::default()).is_ok(), "{}", source);
+}
+
+#[test]
+fn enum_discriminants_fit_their_type_once() {
+    let enums = [
+        ("enum X(u8) {\n    A{256},\n}\n", 2),
+        ("enum X(u8) {\n    A{254},\n    B,\n    C,\n}\n", 4),
+        ("enum X(i8) {\n    A{-129},\n}\n", 2),
+        ("enum X(u8) {\n    A{\"1\"},\n}\n", 2),
+        ("enum X(i16) {\n    A{1},\n    B{0},\n    C,\n}\n", 4),
+        ("enum X(string) {\n    A,\n}\n", 1),
+    ];
+
+    for (i, &(source, line)) in enums.iter().enumerate() {
+        assert_eq!(errors(&format!("enum{}", i), source), [("E0010", line)], "{}", source);
+    }
+
+    let source = "enum X(i8) {\n    A{-128},\n    B{126},\n    C,\n}\n";
+    let path = common::entry("check", "enum", &[("main.rpc", source)]);
+
+    assert!(check(&path, &Default::default()).is_ok());
+}
+
+/// Definitions with errors in different definitions, none of which hides the others.
+const ERRORS: &str = "struct A {\n    x: Missing,\n    x: u8,\n}\n\n\
+                      enum E(u8) {\n    V{300},\n}\n\n\
+                      struct B {\n    a: A,\n    n: u8 {\"1\"},\n}\n";
+
+#[test]
+fn independent_errors_are_all_reported() {
+    assert_eq!(
+        errors("independent", ERRORS),
+        [("E0006", 2), ("E0007", 3), ("E0010", 7), ("E0005", 12)]
+    );
+}
+
+#[test]
+fn errors_stop_at_the_maximum() {
+    let path = common::entry("check", "max_errors", &[("main.rpc", ERRORS)]);
+    let opts = rrpc::Options {
+        max_errors: 2,
+        ..Default::default()
+    };
+
+    let diags = check(&path, &opts).map(|_| panic!("compiled")).unwrap_err();
+    let codes = diags.iter().map(|d| d.code).collect::<Vec<_>>();
+
+    assert_eq!(codes, ["E0006", "E0007"]);
+}
+
+#[test]
+fn redundant_uses_are_warnings() {
+    let source = "use dep;\nuse dep;\n\nstruct A {\n    x: Missing,\n}\n";
+    let files = [("main.rpc", source), ("dep.rpc", "struct D {}\n")];
+    let path = common::entry("check", "redundant_use", &files);
+
+    let diags = check(&path, &Default::default()).map(|_| panic!("compiled")).unwrap_err();
+    let diags = diags.iter().map(|d| (d.severity, d.code, d.start.unwrap().line));
+
+    assert_eq!(
+        diags.collect::<Vec<_>>(),
+        [
+            (rrpc::Severity::Warning, "W0001", 2),
+            (rrpc::Severity::Error, "E0006", 5),
+        ]
+    );
+
+    // Warnings alone don't fail the compile.
+    let files = [("main.rpc", "use dep;\nuse dep;\n"), ("dep.rpc", "")];
+    let path = common::entry("check", "redundant_use_only", &files);
+    assert!(check(&path, &Default::default()).is_ok());
+}
+
+/// Fails in `generate_defs`, after the definitions are checked.
+struct Failing;
+
+impl rrpc::LangGenerator for Failing {
+    fn generate_defs(&mut self, _: rrpc::Defs) -> rrpc::Result<rrpc::Defs> {
+        Err(rrpc::ErrorKind::OutputError("can't generate".into()).into())
+    }
+}
+
+#[test]
+fn generator_errors_keep_the_diagnostics() {
+    rrpc::register_generator("failing", Failing);
+
+    let source = "struct A {\n    x: Missing,\n}\n";
+    let path = common::entry("check", "failing", &[("main.rpc", source)]);
+    let result = rrpc::generate("failing", &path.to_string_lossy(), &Default::default());
+
+    let diags = diagnostics(result).map(|_| panic!("compiled")).unwrap_err();
+    let diags = diags.iter().map(|d| (d.code, d.message.contains("can't generate")));
+
+    // Diagnostics without a location come first.
+    assert_eq!(diags.collect::<Vec<_>>(), [("E0000", true), ("E0006", false)]);
+}
+
+#[test]
+fn modules_are_loaded_once_per_file() {
+    let files = [
+        ("main.rpc", "use x;\nuse sub::a;\n"),
+        ("x.rpc", "struct X {}\n"),
+        ("sub/a.rpc", "use x;\n\nstruct A {\n    x: x::X,\n}\n"),
+    ];
+    let path = common::entry("check", "same_file", &files);
+
+    // `sub/a.rpc` finds `x.rpc` in the include directory, which is the same file.
+    let opts = rrpc::Options {
+        include: vec![path.parent().unwrap().to_string_lossy().into()],
+        ..Default::default()
+    };
+    assert!(check(&path, &opts).is_ok());
+
+    // Tests run in the directory of the package, which relative paths start from.
+    let relative = path.strip_prefix(env!("CARGO_MANIFEST_DIR")).unwrap();
+    assert!(check(relative, &opts).is_ok());
+
+    // Another `x.rpc` next to `sub/a.rpc` would be another module with the same namespace.
+    let files = [
+        ("main.rpc", "use x;\nuse sub::a;\n"),
+        ("x.rpc", "struct X {}\n"),
+        ("sub/a.rpc", "use x;\n"),
+        ("sub/x.rpc", "struct Y {}\n"),
+    ];
+    let path = common::entry("check", "other_file", &files);
+
+    let diags = check(&path, &Default::default()).map(|_| panic!("compiled")).unwrap_err();
+    assert_eq!(diags.len(), 1);
+    assert!(diags[0].path.ends_with("a.rpc"));
+    assert!(diags[0].message.contains("another file is already loaded as module x"));
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+const VALID: &str = "struct A {\n    x: u8,\n}\n";
+
+/// Has a type which isn't defined.
+const INVALID: &str = "struct A {\n    x: Missing,\n}\n";
+
+/// Runs the binary in `dir`, with logging off so that only what's printed is seen.
+fn rrpc(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rrpc"))
+        .arg("--log-level")
+        .arg("off")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn check_fails_on_errors_even_without_logs() {
+    let dir = common::fixture("cli", "check", &[("valid.rpc", VALID), ("invalid.rpc", INVALID)]);
+
+    let ok = rrpc(&dir, &["check", "valid.rpc"]);
+    assert_eq!(ok.status.code(), Some(0));
+    assert!(ok.stderr.is_empty(), "{}", stderr(&ok));
+
+    let failed = rrpc(&dir, &["check", "valid.rpc", "invalid.rpc"]);
+    assert_eq!(failed.status.code(), Some(1));
+    assert!(stderr(&failed).contains("E0006"), "{}", stderr(&failed));
+
+    let missing = rrpc(&dir, &["check", "missing.rpc"]);
+    assert_eq!(missing.status.code(), Some(1));
+    assert!(!missing.stderr.is_empty());
+
+    assert_eq!(rrpc(&dir, &["check"]).status.code(), Some(2));
+
+    let usage = rrpc(&dir, &["check", "--max-errors", "none", "valid.rpc"]);
+    assert_eq!(usage.status.code(), Some(2));
+}
+
+#[test]
+fn compile_fails_on_errors_and_unwritable_output() {
+    let dir = common::fixture("cli", "compile", &[("valid.rpc", VALID), ("invalid.rpc", INVALID)]);
+
+    let ok = rrpc(&dir, &["compile", "-g", "rust", "--out-dir", "out", "valid.rpc"]);
+    assert_eq!(ok.status.code(), Some(0), "{}", stderr(&ok));
+    assert!(String::from_utf8_lossy(&ok.stdout).lines().any(|l| l == "out/valid.rs"));
+    assert!(dir.join("out/valid.rs").exists());
+
+    let failed = rrpc(&dir, &["compile", "-g", "rust", "--out-dir", "out", "invalid.rpc"]);
+    assert_eq!(failed.status.code(), Some(1));
+    assert!(stderr(&failed).contains("E0006"), "{}", stderr(&failed));
+
+    // The output directory can't be created where a file is.
+    fs::write(dir.join("file"), "").unwrap();
+    let unwritable = rrpc(&dir, &["compile", "-g", "rust", "--out-dir", "file", "valid.rpc"]);
+    assert_eq!(unwritable.status.code(), Some(1));
+    assert!(!unwritable.stderr.is_empty());
+
+    assert_eq!(rrpc(&dir, &["compile", "-g", "missing", "valid.rpc"]).status.code(), Some(1));
+}
+
+#[test]
+fn fmt_check_fails_on_unformatted_files() {
+    let unformatted = "struct A {\nx: u8,\n  }\n";
+    let files = [("valid.rpc", VALID), ("unformatted.rpc", unformatted)];
+    let dir = common::fixture("cli", "fmt", &files);
+
+    assert_eq!(rrpc(&dir, &["fmt", "--check", "valid.rpc"]).status.code(), Some(0));
+
+    let check = rrpc(&dir, &["fmt", "--check", "valid.rpc", "unformatted.rpc"]);
+    assert_eq!(check.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&check.stdout), "unformatted.rpc\n");
+    assert_eq!(fs::read_to_string(dir.join("unformatted.rpc")).unwrap(), unformatted);
+
+    // Formatting in place fixes it.
+    assert_eq!(rrpc(&dir, &["fmt", "unformatted.rpc"]).status.code(), Some(0));
+    assert_eq!(fs::read_to_string(dir.join("unformatted.rpc")).unwrap(), VALID);
+    assert_eq!(rrpc(&dir, &["fmt", "--check", "unformatted.rpc"]).status.code(), Some(0));
+
+    let missing = rrpc(&dir, &["fmt", "missing.rpc"]);
+    assert_eq!(missing.status.code(), Some(1));
+    assert!(stderr(&missing).contains("missing.rpc"), "{}", stderr(&missing));
+
+    fs::write(dir.join("broken.rpc"), "struct {\n").unwrap();
+    let broken = rrpc(&dir, &["fmt", "broken.rpc"]);
+    assert_eq!(broken.status.code(), Some(1));
+    assert!(!broken.stderr.is_empty());
+}
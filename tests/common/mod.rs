@@ -0,0 +1,43 @@
+//! Helpers shared by the tests, each of which only uses some of them.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+/// Writes `files` into `target/<group>/<name>`, which is emptied first, and returns it.
+pub fn fixture(group: &str, name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target").join(group).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    for &(file, source) in files {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, source).unwrap();
+    }
+
+    dir
+}
+
+/// Writes `files` as [`fixture`] does, and returns the path of the first one.
+pub fn entry(group: &str, name: &str, files: &[(&str, &str)]) -> PathBuf {
+    fixture(group, name, files).join(files[0].0)
+}
+
+/// Every `$ref` in `value`.
+pub fn refs<'a>(value: &'a Value, out: &mut Vec<&'a str>) {
+    match *value {
+        Value::Object(ref object) => {
+            for (key, value) in object {
+                match value.as_str() {
+                    Some(r) if key == "$ref" => out.push(r),
+                    _ => refs(value, out),
+                }
+            }
+        }
+        Value::Array(ref array) => array.iter().for_each(|v| refs(v, out)),
+        _ => {}
+    }
+}
@@ -0,0 +1,159 @@
+extern crate rrpc;
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use rrpc::Compat::{Breaking, Compatible};
+
+const OLD: &str = r#"
+struct Point {
+    x: i64,
+    y: i64,
+    z: i64,
+    w: i64,
+}
+
+enum Kind(u8) {
+    A,
+    B,
+    C,
+}
+
+union Shape {
+    Circle: f64,
+    Unit,
+}
+
+interface store(p) {
+    fn get(key: u32) -> Point;
+    fn pair() -> (u8, u8);
+    fn drop(key: u32);
+}
+"#;
+
+const NEW: &str = r#"
+struct Point {
+    reserved 3, z;
+    x: i64,
+    y: i32,
+    5: v: i64 {1},
+}
+
+enum Kind(u8) {
+    A,
+    C{5},
+    D,
+}
+
+union Shape {
+    Square: f64,
+    Circle: f64,
+    Unit,
+}
+
+interface store(p) {
+    fn get(id: u32) -> Point;
+    fn pair() -> u8;
+    fn list() -> vector<Point>;
+}
+"#;
+
+fn compat(old: &Path, new: &Path) -> rrpc::Report {
+    rrpc::compat(&old.to_string_lossy(), &new.to_string_lossy(), &Default::default())
+        .unwrap_or_else(|e| panic!("{}", e))
+}
+
+#[test]
+fn changes_are_classified() {
+    let old = common::fixture("compat", "classified/old", &[("api.rpc", OLD)]);
+    let new = common::fixture("compat", "classified/new", &[("api.rpc", NEW)]);
+    let report = compat(&old, &new);
+
+    let changes = report
+        .changes
+        .iter()
+        .map(|c| (c.path.as_str(), c.message.as_str(), c.wire, c.source))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        changes,
+        [
+            ("api::Kind.B", "variant removed", Breaking, Breaking),
+            ("api::Kind.C", "variant renumbered from 2 to 5", Breaking, Compatible),
+            ("api::Kind.D", "variant added", Compatible, Compatible),
+            ("api::Point.y", "type of field changed from i64 to i32", Breaking, Breaking),
+            ("api::Point.z", "field 3 removed and reserved", Compatible, Breaking),
+            ("api::Point.w", "field 4 removed without reserving it", Breaking, Breaking),
+            ("api::Point.v", "field 5 added", Compatible, Compatible),
+            ("api::Shape.Circle", "tag changed from 1 to 2", Breaking, Compatible),
+            ("api::Shape.Unit", "tag changed from 2 to 3", Breaking, Compatible),
+            ("api::Shape.Square", "variant added", Compatible, Compatible),
+            ("api::store.get", "argument key renamed to id", Breaking, Compatible),
+            (
+                "api::store.pair",
+                "number of return values changed from 2 to 1",
+                Breaking,
+                Breaking
+            ),
+            ("api::store.drop", "function removed", Breaking, Breaking),
+            ("api::store.list", "function added", Compatible, Compatible),
+        ]
+    );
+    assert!(report.breaking);
+}
+
+#[test]
+fn imported_modules_match_their_roots() {
+    let shared = "struct Id {\n    n: u64,\n}\n";
+    let old = common::fixture("compat", "imported/old", &[("shared.rpc", shared)]);
+    let new = common::fixture("compat", 
+        "imported/new",
+        &[
+            ("api.rpc", "use shared;\n\nstruct Ref {\n    id: shared::Id,\n}\n"),
+            ("shared.rpc", shared),
+        ],
+    );
+    let report = compat(&old, &new);
+
+    let changes = report
+        .changes
+        .iter()
+        .map(|c| (c.path.as_str(), c.message.as_str()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(changes, [("api::Ref", "struct added")]);
+    assert!(!report.breaking);
+}
+
+#[test]
+fn breaking_changes_fail_the_command() {
+    let old = common::fixture("compat", "command/old", &[("api.rpc", OLD)]);
+    let new = common::fixture("compat", "command/new", &[("api.rpc", NEW)]);
+    let json = old.with_file_name("report.json");
+
+    let run = |new: &Path| {
+        Command::new(env!("CARGO_BIN_EXE_rrpc"))
+            .arg("compat")
+            .arg(&old)
+            .arg(new)
+            .arg("--json")
+            .arg(&json)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&new);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("14 changes, 10 breaking"));
+
+    let report: serde_json::Value = serde_json::from_slice(&fs::read(&json).unwrap()).unwrap();
+    assert_eq!(report["breaking"], true);
+    assert_eq!(report["changes"][0]["wire"], "breaking");
+
+    let output = run(&old);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "no changes\n");
+}
@@ -0,0 +1,225 @@
+extern crate rrpc;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Definitions covering what the generator maps, imports `examples/init.rpc`.
+const KITCHEN: &str = r#"use init;
+
+/// Refers to a type which contains it, through an optional only.
+struct A {
+    b: optional<B>,
+}
+
+struct B {
+    a: A,
+}
+
+/// A tree.
+struct Node {
+    kind: Kind {"B"},
+    children: vector<Node>,
+    parent: optional<Node>,
+    shape: Shape {{"Circle": 2.5}},
+    late: optional<Late> {{"x": 3}},
+    tags: map<u32, string> {{"1": "one"}},
+    other: init::name,
+}
+
+enum Kind(i16) {
+    A{-1},
+    B,
+}
+
+union Shape {
+    Circle: f64,
+    Square: geo::Point,
+    Unit,
+}
+
+struct Late {
+    x: u8 {7},
+}
+
+mod geo {
+    struct Point {
+        x: i64,
+        y: i64 {-2},
+    }
+
+    mod deep {
+        struct Segment {
+            a: Point,
+            b: geo::Point,
+            c: Kind,
+        }
+    }
+}
+
+struct Empty {}
+
+struct Scalars {
+    b: bool {true},
+    u: u8 {255},
+    i: i8 {-128},
+    w: u16 {300},
+    n: i32 {-65},
+    l: u64 {18446744073709551615},
+    m: i64 {-9223372036854775808},
+    f: f32 {1.5},
+    d: f64 {-0.25},
+    s: string {"é"},
+    a: array<i8, 2> {[-1, 1]},
+    t: set<i32> {[1, -1]},
+    o: optional<u16> {7},
+}
+
+interface store(p) {
+    fn get(key: u32) -> optional<Node>;
+    fn pair(a: Kind, b: set<Kind>) -> (string, geo::Point);
+}
+"#;
+
+/// Uses the generated types, to be run once compiled.
+const MAIN: &str = r#"#include "kitchen.hpp"
+
+#include <cassert>
+#include <map>
+#include <string>
+
+/// Encodings of the first vector of each type, which is its default.
+std::map<std::string, std::string> defaults;
+
+std::string hex(const rrpc::Bytes& bytes) {
+    std::string out;
+    for (std::uint8_t b : bytes) {
+        out += "0123456789abcdef"[b >> 4];
+        out += "0123456789abcdef"[b & 0xf];
+    }
+    return out;
+}
+
+rrpc::Bytes bytes(const std::string& hex) {
+    rrpc::Bytes out;
+    for (std::size_t i = 0; i < hex.size(); i += 2) {
+        out.push_back(static_cast<std::uint8_t>(std::stoi(hex.substr(i, 2), nullptr, 16)));
+    }
+    return out;
+}
+
+/// Decodes a golden vector, and checks it encodes back to the same bytes.
+template <typename T>
+void check(const std::string& type, const std::string& vector) {
+    defaults.emplace(type, vector);
+    assert(hex(rrpc::encode(rrpc::decode<T>(bytes(vector)))) == vector);
+}
+
+#include "vectors.hpp"
+
+int main() {
+    kitchen::B b;
+    b.a.b = std::make_unique<kitchen::B>();
+
+    kitchen::Node node;
+    assert(node.kind == kitchen::Kind::B);
+    assert(!node.parent);
+    assert(node.late->x == 3);
+    assert(node.tags.at(1) == "one");
+    assert(node.shape.which() == kitchen::Shape::Which::Circle);
+    assert(kitchen::Late{}.x == 7);
+    assert(kitchen::geo::deep::Segment{}.b.y == -2);
+    assert(kitchen::geo::deep::Segment{}.c == kitchen::Kind::A);
+
+    vectors();
+    assert(hex(rrpc::encode(kitchen::Node{})) == defaults.at("Node"));
+    assert(hex(rrpc::encode(kitchen::B{})) == defaults.at("B"));
+    assert(hex(rrpc::encode(kitchen::geo::deep::Segment{})) == defaults.at("geo::deep::Segment"));
+    assert(hex(rrpc::encode(kitchen::Empty{})) == defaults.at("Empty"));
+    assert(hex(rrpc::encode(kitchen::Scalars{})) == defaults.at("Scalars"));
+    assert(hex(rrpc::encode(init::name{})) == defaults.at("init::name"));
+
+    // Unknown fields are skipped, and missing ones keep their defaults.
+    auto late = rrpc::decode<kitchen::Late>(bytes("02" "0901ff" "010109"));
+    assert(late.x == 9);
+
+    try {
+        rrpc::decode<kitchen::Kind>(bytes("04"));
+        assert(false);
+    } catch (const rrpc::DecodeError&) {
+    }
+
+    try {
+        rrpc::decode<kitchen::Scalars>(bytes("01010102"));
+        assert(false);
+    } catch (const rrpc::DecodeError&) {
+    }
+
+    return 0;
+}
+"#;
+
+#[test]
+fn generated_headers_compile_and_work() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/generated-cpp");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let input = dir.join("kitchen.rpc");
+    fs::write(&input, KITCHEN).unwrap();
+
+    let opts = rrpc::Options {
+        include: vec![concat!(env!("CARGO_MANIFEST_DIR"), "/examples").into()],
+        ..Default::default()
+    };
+
+    let files = rrpc::render_files("cpp", &input.to_string_lossy(), "", &opts)
+        .unwrap_or_else(|e| panic!("{}", e));
+
+    for file in &files {
+        let path = dir.join(&file.path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, &file.contents).unwrap();
+    }
+
+    let vectors = rrpc::render_files("vectors", &input.to_string_lossy(), "", &opts)
+        .unwrap_or_else(|e| panic!("{}", e));
+    let vectors: serde_json::Value = serde_json::from_str(&vectors[0].contents).unwrap();
+
+    // Types of the root module are keyed without its namespace.
+    let imported = files
+        .iter()
+        .filter_map(|f| f.path.strip_suffix(".hpp"))
+        .filter(|stem| !stem.contains('/'))
+        .map(|stem| format!("{}::", stem))
+        .filter(|prefix| prefix != "kitchen::" && prefix != "rrpc::")
+        .collect::<Vec<_>>();
+
+    let mut checks = String::from("inline void vectors() {\n");
+    for vector in vectors["vectors"].as_array().unwrap() {
+        let key = vector["type"].as_str().unwrap();
+        let name = if imported.iter().any(|prefix| key.starts_with(prefix.as_str())) {
+            format!("::{}", key)
+        } else {
+            format!("::kitchen::{}", key)
+        };
+
+        checks.push_str(&format!("    check<{}>({:?}, {});\n", name, key, vector["hex"]));
+    }
+    checks.push_str("}\n");
+
+    fs::write(dir.join("vectors.hpp"), checks).unwrap();
+    fs::write(dir.join("main.cpp"), MAIN).unwrap();
+
+    let cxx = env::var("CXX").unwrap_or_else(|_| "g++".into());
+    let status = Command::new(cxx)
+        .args(["-std=c++17", "-Wall", "-Werror", "-o", "main", "main.cpp"])
+        .current_dir(&dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "generated headers failed, see {}", dir.display());
+
+    let status = Command::new(dir.join("main")).status().unwrap();
+    assert!(status.success(), "generated code failed, see {}", dir.display());
+}
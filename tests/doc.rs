@@ -0,0 +1,153 @@
+extern crate rrpc;
+
+mod common;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Definitions with a doc comment on every kind of item, importing `geo.rpc`.
+const MAIN: &str = r#"use geo;
+
+/// A tree.
+struct Node {
+    /// Name of the node.
+    name: string {"root"},
+    kind: Kind {"B"},
+    at: optional<geo::Point>,
+}
+
+/// What a node is.
+enum Kind(u8) {
+    A,
+    B,
+}
+
+mod inner {
+    /// Nested in a module.
+    struct Leaf {
+        node: Node,
+    }
+}
+
+/// Stores the nodes.
+interface store(p) {
+    /// Finds a node by name.
+    fn get(name: string) -> optional<Node>;
+}
+"#;
+
+const GEO: &str = "/// A point.\nstruct Point {\n    x: i64,\n    y: i64 {-2},\n}\n";
+
+/// Writes the documentation in `format`, and returns the pages by their file names.
+fn document(name: &str, format: rrpc::DocFormat) -> HashMap<String, String> {
+    let dir = common::fixture("doc-pages", name, &[("main.rpc", MAIN), ("geo.rpc", GEO)]);
+
+    let out: PathBuf = dir.join("out");
+    let opts = rrpc::Options {
+        out_dir: Some(out.to_string_lossy().into()),
+        ..Default::default()
+    };
+
+    let main = dir.join("main.rpc");
+    let manifest = rrpc::document(&main.to_string_lossy(), "", format, &opts)
+        .unwrap_or_else(|e| panic!("{}", e));
+
+    manifest
+        .files
+        .iter()
+        .map(|f| {
+            let path = Path::new(&f.path);
+            let name = path.strip_prefix(&out).unwrap().to_string_lossy().into_owned();
+            (name, fs::read_to_string(path).unwrap())
+        })
+        .collect()
+}
+
+/// Values of the attribute `attr` in `page`.
+fn attrs<'a>(page: &'a str, attr: &str) -> Vec<&'a str> {
+    let pattern = format!("{}=\"", attr);
+
+    page.match_indices(&pattern)
+        .map(|(at, _)| {
+            let value = &page[at + pattern.len()..];
+            &value[..value.find('"').unwrap()]
+        })
+        .collect()
+}
+
+/// Checks that every link of every page leads to a page, and to an anchor of it.
+fn check_links(pages: &HashMap<String, String>, links: impl Fn(&str) -> Vec<&str>) {
+    for (name, page) in pages {
+        let ids = attrs(page, "id");
+
+        for id in &ids {
+            assert_eq!(ids.iter().filter(|i| *i == id).count(), 1, "{}: {} isn't unique", name, id);
+        }
+
+        for link in links(page) {
+            let (file, anchor) = match link.find('#') {
+                Some(at) => (&link[..at], Some(&link[at + 1..])),
+                None => (link, None),
+            };
+            let target = if file.is_empty() { name.as_str() } else { file };
+            let target = pages
+                .get(target)
+                .unwrap_or_else(|| panic!("{}: {} leads to no page", name, link));
+
+            if let Some(anchor) = anchor {
+                assert!(attrs(target, "id").contains(&anchor), "{}: {} is dangling", name, link);
+            }
+        }
+    }
+}
+
+#[test]
+fn html_pages_link_to_existing_anchors() {
+    let pages = document("html", rrpc::DocFormat::Html);
+
+    let mut names = pages.keys().map(String::as_str).collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["geo.html", "index.html", "main.html"]);
+
+    check_links(&pages, |page| attrs(page, "href"));
+
+    let main = &pages["main.html"];
+    for id in &["Node", "Kind", "inner", "inner.Leaf", "store", "store.get"] {
+        assert!(attrs(main, "id").contains(id), "no anchor {}", id);
+    }
+    let comments = ["A tree.", "Name of the node.", "Nested in a module.", "Finds a node by name."];
+    for comment in &comments {
+        assert!(main.contains(comment), "no comment {:?}", comment);
+    }
+
+    // Types link to where they're defined, and defaults are shown.
+    assert!(main.contains(r#"<a href="geo.html#Point">geo::Point</a>"#));
+    assert!(main.contains("<code>&quot;root&quot;</code>"));
+    assert!(pages["geo.html"].contains("<code>-2</code>"));
+    assert!(pages["index.html"].contains(r#"href="main.html#inner.Leaf""#));
+}
+
+#[test]
+fn markdown_pages_link_to_existing_anchors() {
+    let pages = document("markdown", rrpc::DocFormat::Markdown);
+
+    let mut names = pages.keys().map(String::as_str).collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["geo.md", "index.md", "main.md"]);
+
+    // Links are either in Markdown or in the HTML of the types.
+    check_links(&pages, |page| {
+        let mut links = attrs(page, "href");
+        links.extend(page.match_indices("](").map(|(at, _)| {
+            let link = &page[at + 2..];
+            &link[..link.find(')').unwrap()]
+        }));
+        links
+    });
+
+    let main = &pages["main.md"];
+    assert!(main.contains("Finds a node by name."));
+    assert!(main.contains(r#"<a href="geo.md#Point">geo::Point</a>"#));
+    assert!(pages["index.md"].contains("(main.md#store)"));
+}
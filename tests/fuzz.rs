@@ -0,0 +1,100 @@
+extern crate rrpc;
+
+use std::fs;
+use std::panic;
+use std::path::PathBuf;
+
+/// Tokens random inputs are made of, so that they get past the lexer often enough.
+const TOKENS: &[&str] = &[
+    "struct ", "enum ", "union ", "interface ", "mod ", "use ", "fn ", "{", "}", "(", ")", "<",
+    ">", ",", ";", ":", "::", "->", "..", "///", "// c", "\n", " ", "A", "B", "x", "y", "u8",
+    "u32", "i64", "f32", "bool", "string", "vector", "map", "set", "optional", "array", "3",
+    "{0}", "{300}", "{\"a\"}", "{[1, 2]}", "{{\"x\": 1}}", "{true}", "é",
+];
+
+const SEEDS: &[&str] = &[
+    include_str!("../examples/init.rpc"),
+    include_str!("../examples/sample.rpc"),
+    include_str!("../examples/child.rpc"),
+    "mod m { struct A { a: vector<A>, } } struct B { a: m::A, b: C, } struct C { x: u8 {3}, }",
+    "enum E(u8) { A{255}, B, } union U { A: E, None } interface api(p) { fn f(u: U) -> (E, U); }",
+];
+
+/// A xorshift generator, so that failures can be reproduced from the seed.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+fn tokens(rng: &mut Rng) -> String {
+    (0..rng.below(64))
+        .map(|_| TOKENS[rng.below(TOKENS.len())])
+        .collect()
+}
+
+fn mutate(rng: &mut Rng, seed: &str) -> String {
+    let mut chars = seed.chars().collect::<Vec<_>>();
+
+    for _ in 0..=rng.below(4) {
+        let at = rng.below(chars.len() + 1);
+
+        match rng.below(3) {
+            0 => {
+                let len = rng.below(8).min(chars.len() - at);
+                chars.drain(at..at + len);
+            }
+            1 => {
+                let token = TOKENS[rng.below(TOKENS.len())];
+                chars.splice(at..at, token.chars());
+            }
+            _ => {
+                let from = rng.below(chars.len() + 1);
+                let len = rng.below(16).min(chars.len() - from);
+                let copy = chars[from..from + len].to_vec();
+                chars.splice(at..at, copy);
+            }
+        }
+    }
+
+    chars.into_iter().collect()
+}
+
+#[test]
+fn render_never_panics() {
+    let dir = std::env::temp_dir().join(format!("rrpc-fuzz-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let templates = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/templates/**/*");
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+    for i in 0..2000 {
+        let input = if i % 2 == 0 {
+            tokens(&mut rng)
+        } else {
+            let seed = SEEDS[rng.below(SEEDS.len())];
+            mutate(&mut rng, seed)
+        };
+
+        let path: PathBuf = dir.join("fuzz.rpc");
+        fs::write(&path, &input).unwrap();
+
+        let path = path.to_string_lossy().to_string();
+        let result = panic::catch_unwind(|| {
+            rrpc::render_files("null", &path, templates, &rrpc::Options::default())
+        });
+
+        assert!(result.is_ok(), "render panicked on input:\n{}", input);
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,170 @@
+extern crate rrpc;
+
+mod common;
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::Value;
+
+/// Definitions covering what the schemas map, importing `geo.rpc`.
+const MAIN: &str = r#"use geo;
+
+/// A tree.
+struct Node {
+    /// Name of the node.
+    name: string {"root"},
+    size: u8 {3},
+    children: vector<Node>,
+    parent: optional<Node>,
+    kind: Kind {"B"},
+    modes: map<Kind, i8> {{"A": -1}},
+    tags: map<u32, string>,
+    shape: Shape {{"Circle": 2.5}},
+    at: geo::Point,
+    grid: array<f32, 2>,
+}
+
+/// What a node is.
+enum Kind(i16) {
+    /// The first.
+    A{-1},
+    B,
+}
+
+union Shape {
+    Circle: f64,
+    Square: geo::Point,
+    Unit,
+}
+"#;
+
+const GEO: &str = "struct Point {\n    x: i64,\n    y: i64 {-2},\n}\n";
+
+/// Files of the definitions, written for each test.
+const FILES: &[(&str, &str)] = &[("main.rpc", MAIN), ("geo.rpc", GEO)];
+
+/// Validates instances against the schemas, with the documents referencing each other by `$id`.
+const CHECK: &str = r#"import json
+import sys
+
+import jsonschema
+import referencing
+
+docs = [json.load(open(path)) for path in ["main.schema.json", "geo.schema.json"]]
+registry = referencing.Registry().with_resources(
+    (doc["$id"], referencing.Resource.from_contents(doc)) for doc in docs
+)
+
+for doc in docs:
+    jsonschema.Draft202012Validator.check_schema(doc)
+
+node = jsonschema.Draft202012Validator(
+    {"$ref": "main.schema.json#/$defs/Node"}, registry=registry
+)
+
+valid = {
+    "name": "a",
+    "size": 255,
+    "children": [],
+    "parent": None,
+    "kind": "A",
+    "modes": {"B": 1},
+    "tags": {"20": "x"},
+    "shape": "Unit",
+    "at": {"x": 1, "y": 2},
+    "grid": [1.5, 2],
+}
+node.validate(valid)
+
+invalid = [
+    {"size": 256},
+    {"kind": "C"},
+    {"modes": {"C": 1}},
+    {"tags": {"x": "y"}},
+    {"shape": {"Circle": "round"}},
+    {"shape": {"Circle": 1, "Unit": None}},
+    {"at": {"x": "1", "y": 2}},
+    {"grid": [1]},
+    {"name": 1},
+]
+for change in invalid:
+    if node.is_valid(dict(valid, **change)):
+        sys.exit("{} is valid".format(change))
+"#;
+
+/// Schema documents of the definitions in `dir`, by their paths.
+fn schemas(dir: &Path) -> HashMap<String, Value> {
+    let main = dir.join("main.rpc");
+
+    rrpc::render_files("jsonschema", &main.to_string_lossy(), "", &Default::default())
+        .unwrap_or_else(|e| panic!("{}", e))
+        .into_iter()
+        .map(|f| (f.path, serde_json::from_str(&f.contents).unwrap()))
+        .collect()
+}
+
+#[test]
+fn documents_reference_existing_definitions() {
+    let docs = schemas(&common::fixture("jsonschema", "refs", FILES));
+
+    let mut paths = docs.keys().map(String::as_str).collect::<Vec<_>>();
+    paths.sort();
+    assert_eq!(paths, ["geo.schema.json", "main.schema.json"]);
+
+    for (path, doc) in &docs {
+        assert_eq!(doc["$schema"], "https://json-schema.org/draft/2020-12/schema");
+        assert_eq!(doc["$id"], path.as_str());
+
+        let mut found = Vec::new();
+        common::refs(doc, &mut found);
+
+        for r in found {
+            let (file, pointer) = r.split_at(r.find('#').unwrap());
+            let target = if file.is_empty() { doc } else { &docs[file] };
+
+            assert!(target.pointer(&pointer[1..]).is_some(), "{}: {} is dangling", path, r);
+        }
+    }
+
+    let main = &docs["main.schema.json"]["$defs"];
+    assert_eq!(main["Node"]["description"], "A tree.");
+    assert_eq!(main["Node"]["properties"]["name"]["description"], "Name of the node.");
+    assert_eq!(main["Node"]["properties"]["size"]["default"], 3);
+    assert_eq!(main["Node"]["properties"]["at"]["$ref"], "geo.schema.json#/$defs/Point");
+    assert_eq!(main["Kind"]["description"], "What a node is.");
+    assert_eq!(main["Kind"]["oneOf"][0]["const"], "A");
+    assert_eq!(main["Kind"]["oneOf"][0]["description"], "The first.");
+    assert_eq!(main["Shape"]["oneOf"].as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn instances_validate_against_the_schemas() {
+    let dir = common::fixture("jsonschema", "validate", FILES);
+
+    for (path, doc) in schemas(&dir) {
+        fs::write(dir.join(path), doc.to_string()).unwrap();
+    }
+    fs::write(dir.join("check.py"), CHECK).unwrap();
+
+    let python = env::var("PYTHON").unwrap_or_else(|_| "python3".into());
+    let found = Command::new(&python)
+        .args(["-c", "import jsonschema, referencing"])
+        .status()
+        .is_ok_and(|status| status.success());
+
+    if !found {
+        eprintln!("jsonschema isn't installed, so no instances are validated");
+        return;
+    }
+
+    let status = Command::new(python)
+        .arg("check.py")
+        .current_dir(&dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "schemas failed, see {}", dir.display());
+}
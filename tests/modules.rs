@@ -0,0 +1,94 @@
+extern crate rrpc;
+
+mod common;
+
+/// Modules nested in one another, with a type of the same name in two of them.
+const NESTED: &str = r#"struct Root {}
+
+mod geometry {
+    struct Point {
+        x: i32,
+    }
+
+    mod shape {
+        struct Point {}
+
+        struct Circle {
+            near: Point,
+            far: geometry::Point,
+            root: Root,
+        }
+    }
+
+    struct Line {
+        a: Point,
+        b: shape::Point,
+    }
+}
+"#;
+
+/// Namespaces of the types of the members of `node`, by member name.
+fn members(node: &serde_json::Value) -> Vec<(&str, &str)> {
+    node["members"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| (m["name"].as_str().unwrap(), m["type"]["namespace"].as_str().unwrap()))
+        .collect()
+}
+
+#[test]
+fn names_resolve_from_the_innermost_module_out() {
+    let path = common::entry("modules", "resolve", &[("nested.rpc", NESTED)]);
+    let model = rrpc::generate("null", &path.to_string_lossy(), &Default::default())
+        .unwrap_or_else(|e| panic!("{}", e));
+    let model = serde_json::to_value(model).unwrap();
+
+    let geometry = &model["modules"]["nested"]["nodes"][1];
+    assert_eq!(geometry["name"], "geometry");
+
+    let shape = &geometry["nodes"][1];
+    assert_eq!(shape["name"], "shape");
+    assert_eq!(
+        members(&shape["nodes"][1]),
+        [("near", "geometry::shape"), ("far", "geometry"), ("root", "")]
+    );
+    assert_eq!(members(&geometry["nodes"][2]), [("a", "geometry"), ("b", "geometry::shape")]);
+}
+
+#[test]
+fn example_templates_render_nested_modules() {
+    let path = common::entry("modules", "render", &[("nested.rpc", NESTED)]);
+    let templates = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/templates/**/*");
+
+    let files = rrpc::render_files("null", &path.to_string_lossy(), templates, &Default::default())
+        .unwrap_or_else(|e| panic!("{}", e));
+    let root = files.iter().find(|f| f.path == "nested.cpp").unwrap();
+
+    let lines = root.contents.lines().map(str::trim).collect::<Vec<_>>();
+    let shape = lines.iter().position(|l| *l == "namespace shape {").unwrap();
+    let circle = lines.iter().position(|l| *l == "struct Circle {").unwrap();
+    let end = lines.iter().position(|l| *l == "} // shape").unwrap();
+
+    assert!(shape < circle && circle < end, "{}", root.contents);
+    assert!(lines[end..].contains(&"struct Line {"), "{}", root.contents);
+}
+
+#[test]
+fn headers_name_the_source_files() {
+    let files = [
+        ("nested.rpc", "use sub::leaf;\n\nstruct A {\n    l: sub::leaf::L,\n}\n"),
+        ("sub/leaf.rpc", "struct L {}\n"),
+    ];
+    let path = common::entry("modules", "headers", &files);
+
+    let files = rrpc::render_files("typescript", &path.to_string_lossy(), "", &Default::default())
+        .unwrap_or_else(|e| panic!("{}", e));
+    let header = |path: &str| {
+        let file = files.iter().find(|f| f.path == path).unwrap();
+        file.contents.lines().find(|l| l.starts_with("// Generated")).unwrap().to_string()
+    };
+
+    assert_eq!(header("nested.ts"), "// Generated by rrpc from nested.rpc. Do not edit.");
+    assert_eq!(header("sub/leaf.ts"), "// Generated by rrpc from sub/leaf.rpc. Do not edit.");
+}
@@ -0,0 +1,122 @@
+extern crate rrpc;
+
+mod common;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::Value;
+
+/// Interfaces in the root module and in `geo.rpc`, which it imports.
+const MAIN: &str = r#"use geo;
+
+/// A tree.
+struct Node {
+    name: string,
+    kind: Kind {"B"},
+    at: optional<geo::Point>,
+}
+
+enum Kind(u8) {
+    A,
+    B,
+}
+
+/// Stores the nodes.
+interface store(nodes) {
+    /// Finds a node by key.
+    fn get(key: u32) -> optional<Node>;
+    fn put(nodes: vector<Node>, kinds: set<Kind>);
+    fn clear();
+    fn pair() -> (string, geo::Point);
+}
+"#;
+
+const GEO: &str = r#"struct Point {
+    x: i64,
+    y: i64,
+}
+
+interface map(map) {
+    fn locate(name: string) -> Point;
+}
+"#;
+
+/// Files of the definitions, written for each test.
+const FILES: &[(&str, &str)] = &[("main.rpc", MAIN), ("geo.rpc", GEO)];
+
+/// The document exported for the definitions in `dir`.
+fn document(dir: &Path) -> Value {
+    let main = dir.join("main.rpc");
+    let files = rrpc::render_files("openapi", &main.to_string_lossy(), "", &Default::default())
+        .unwrap_or_else(|e| panic!("{}", e));
+
+    assert_eq!(files.len(), 1);
+    serde_json::from_str(&files[0].contents).unwrap()
+}
+
+#[test]
+fn functions_are_operations_on_component_schemas() {
+    let doc = document(&common::fixture("openapi", "operations", FILES));
+
+    assert_eq!(doc["openapi"], "3.1.0");
+
+    let mut paths = doc["paths"].as_object().unwrap().keys().collect::<Vec<_>>();
+    paths.sort();
+    assert_eq!(
+        paths,
+        ["/geo/map/locate", "/nodes/clear", "/nodes/get", "/nodes/pair", "/nodes/put"]
+    );
+
+    let get = &doc["paths"]["/nodes/get"]["post"];
+    assert_eq!(get["operationId"], "store.get");
+    assert_eq!(get["description"], "Finds a node by key.");
+    let body = &get["requestBody"]["content"]["application/json"]["schema"];
+    assert_eq!(body["required"], serde_json::json!(["key"]));
+    let ret = &get["responses"]["200"]["content"]["application/json"]["schema"];
+    assert_eq!(ret["anyOf"][0]["$ref"], "#/components/schemas/Node");
+
+    // Without arguments there's no body, and without a return value no content.
+    let clear = &doc["paths"]["/nodes/clear"]["post"];
+    assert!(clear.get("requestBody").is_none());
+    assert!(clear["responses"].get("200").is_none());
+    assert!(doc["paths"]["/nodes/pair"]["post"].get("requestBody").is_none());
+
+    let schemas = &doc["components"]["schemas"];
+    assert_eq!(schemas["Node"]["description"], "A tree.");
+    assert!(schemas.get("geo.Point").is_some());
+
+    let mut found = Vec::new();
+    common::refs(&doc, &mut found);
+    assert!(!found.is_empty());
+
+    for r in found {
+        assert!(doc.pointer(&r[1..]).is_some(), "{} is dangling", r);
+    }
+}
+
+#[test]
+fn document_is_valid_openapi() {
+    let dir = common::fixture("openapi", "validate", FILES);
+    fs::write(dir.join("openapi.json"), document(&dir).to_string()).unwrap();
+
+    let python = env::var("PYTHON").unwrap_or_else(|_| "python3".into());
+    let found = Command::new(&python)
+        .args(["-c", "import openapi_spec_validator"])
+        .status()
+        .is_ok_and(|status| status.success());
+
+    if !found {
+        eprintln!("openapi-spec-validator isn't installed, so the document isn't validated");
+        return;
+    }
+
+    let status = Command::new(python)
+        .args(["-m", "openapi_spec_validator", "openapi.json"])
+        .current_dir(&dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "invalid document, see {}", dir.display());
+}
@@ -0,0 +1,179 @@
+extern crate rrpc;
+
+mod common;
+
+use std::path::Path;
+
+/// A proto file using what the translation maps, with a package, importing `geo.proto`.
+const PROTO: &str = r#"syntax = "proto3";
+
+package foo.bar;
+
+import "google/protobuf/empty.proto";
+import "geo.proto";
+
+// A tree.
+message Node {
+  string name = 1;
+  // What it holds.
+  oneof value {
+    int64 number = 4;
+    Node child = 7;
+    google.protobuf.Empty nothing = 9;
+  }
+  repeated geo.Point points = 2;
+}
+
+message Query {
+  string name = 1;
+}
+
+service Tree {
+  rpc Find(Query) returns (Node);
+  rpc Clear(google.protobuf.Empty) returns (google.protobuf.Empty);
+}
+"#;
+
+const GEO: &str = r#"syntax = "proto3";
+
+package geo;
+
+message Point {
+  int32 x = 1;
+  int32 y = 2;
+}
+"#;
+
+/// Definitions which export to messages of every kind.
+const RPC: &str = r#"/// A point.
+struct Point {
+    x: i32,
+    /// Where it's going.
+    heading: optional<Heading>,
+    shape: Shape,
+}
+
+/// Tags of the variants are free in the structs holding it, so it's a `oneof` of them.
+union Heading {
+    4: North,
+    5: Angle: f32,
+}
+
+union Shape {
+    Circle: f64,
+    Square: string,
+}
+
+interface map(rpc) {
+    fn locate(point: Point) -> Point;
+    fn distance(a: Point, b: Point) -> f64;
+    fn reset();
+}
+"#;
+
+/// Exports the definitions of `path` as proto files, by their paths.
+fn export(path: &Path) -> Vec<(String, String)> {
+    rrpc::render_files("proto", &path.to_string_lossy(), "", &Default::default())
+        .unwrap_or_else(|e| panic!("{}", e))
+        .into_iter()
+        .map(|f| (f.path, f.contents))
+        .collect()
+}
+
+fn contents<'a>(files: &'a [(String, String)], path: &str) -> &'a str {
+    &files.iter().find(|f| f.0 == path).unwrap().1
+}
+
+#[test]
+fn imported_files_export_wire_compatible() {
+    let path = common::entry("proto", "import", &[("tree.proto", PROTO), ("geo.proto", GEO)]);
+    let files = export(&path);
+    let tree = contents(&files, "tree.proto");
+
+    assert!(tree.starts_with("// Generated by rrpc from tree.proto. Do not edit.\n"));
+    assert!(tree.contains("\npackage foo.bar;\n"));
+    assert!(contents(&files, "geo.proto").contains("\npackage geo;\n"));
+
+    // The oneof keeps its field numbers, and has no message of its own.
+    assert!(tree.contains(
+        "  oneof value {\n    \
+         int64 number = 4;\n    \
+         .foo.bar.Node child = 7;\n    \
+         .google.protobuf.Empty nothing = 9;\n  \
+         }\n"
+    ));
+    assert!(tree.contains("  repeated .geo.Point points = 2;\n"));
+    assert!(!tree.contains("Node_value"));
+
+    // Rpcs keep their messages.
+    assert!(tree.contains("  rpc Find(.foo.bar.Query) returns (.foo.bar.Node);\n"));
+    assert!(tree.contains(
+        "  rpc Clear(.google.protobuf.Empty) returns (.google.protobuf.Empty);\n"
+    ));
+    assert!(!tree.contains("Request"));
+    assert!(!tree.contains("Response"));
+}
+
+#[test]
+fn exported_files_import_back() {
+    let path = common::entry("proto", "export", &[("map.rpc", RPC)]);
+    let files = export(&path);
+    let map = contents(&files, "map.proto");
+
+    assert!(map.contains(
+        "  oneof heading {\n    \
+         .google.protobuf.Empty North = 4;\n    \
+         float Angle = 5;\n  \
+         }\n"
+    ));
+    assert!(map.contains("  .map.Shape shape = 3;\n"));
+    assert!(map.contains("    string Square = 2;\n"));
+    assert!(!map.contains("message Heading"));
+
+    // Only functions which don't take or return a single struct have messages of their own.
+    assert!(map.contains("  rpc locate(.map.Point) returns (.map.Point);\n"));
+    assert!(map.contains(
+        "  rpc distance(.map.MapDistanceRequest) returns (.map.MapDistanceResponse);\n"
+    ));
+    assert!(map.contains(
+        "  rpc reset(.google.protobuf.Empty) returns (.google.protobuf.Empty);\n"
+    ));
+
+    // Importing the export gives the same messages and services back.
+    let path = common::entry("proto", "export-import", &[("map.proto", map)]);
+    let again = export(&path);
+    let again = contents(&again, "map.proto");
+
+    assert_eq!(
+        again.replacen("map.proto", "map.rpc", 1),
+        map,
+        "{} exported differently",
+        path.display()
+    );
+}
+
+#[test]
+fn bytes_round_trip_and_other_encodings_are_rejected() {
+    let bytes = "syntax = \"proto3\";\n\nmessage Blob {\n  bytes data = 7;\n  \
+                 repeated bytes parts = 1;\n  optional bytes tail = 2;\n  \
+                 map<string, bytes> named = 3;\n}\n";
+    let path = common::entry("proto", "bytes", &[("blob.proto", bytes)]);
+    let files = export(&path);
+    let blob = contents(&files, "blob.proto");
+
+    assert!(blob.contains("  bytes data = 7;\n"), "{}", blob);
+    assert!(blob.contains("  repeated bytes parts = 1;\n"), "{}", blob);
+    assert!(blob.contains("  optional bytes tail = 2;\n"), "{}", blob);
+    assert!(blob.contains("  map<string, bytes> named = 3;\n"), "{}", blob);
+
+    for ty in &["sint32", "sint64", "fixed32", "fixed64", "sfixed32", "sfixed64"] {
+        let source = format!("syntax = \"proto3\";\n\nmessage A {{\n  {} x = 1;\n}}\n", ty);
+        let path = common::entry("proto", &format!("encoding-{}", ty), &[("a.proto", &source)]);
+
+        let e = rrpc::render_files("proto", &path.to_string_lossy(), "", &Default::default())
+            .map(|_| panic!("{} was translated", ty))
+            .unwrap_err();
+        let message = e.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(": ");
+        assert!(message.contains(ty), "{}", message);
+    }
+}
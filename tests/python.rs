@@ -0,0 +1,146 @@
+extern crate rrpc;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Definitions covering what the codecs map, imports `examples/init.rpc`.
+const KITCHEN: &str = r#"use init;
+
+/// A tree.
+struct Node {
+    kind: Kind {"B"},
+    children: vector<Node>,
+    parent: optional<Node>,
+    shape: Shape {{"Circle": 2.5}},
+    tags: map<u32, string> {{"1": "one", "20": "twenty"}},
+    modes: map<Kind, i8> {{"A": -1}},
+    seen: set<string> {["a", "b"]},
+    other: init::name,
+}
+
+enum Kind(i16) {
+    A{-1},
+    B,
+}
+
+union Shape {
+    Circle: f64,
+    Square: geo::Point,
+    Unit,
+}
+
+struct Scalars {
+    b: bool {true},
+    u: u8 {255},
+    i: i8 {-128},
+    w: u16 {300},
+    n: i32 {-65},
+    l: u64 {18446744073709551615},
+    m: i64 {-9223372036854775808},
+    f: f32 {1.5},
+    d: f64 {-0.25},
+    s: string {"é"},
+    a: array<i8, 2> {[-1, 1]},
+    o: optional<u16> {7},
+}
+
+mod geo {
+    struct Point {
+        reserved 2, z;
+        x: i64,
+        3: y: i64 {-2},
+    }
+}
+"#;
+
+/// Checks every golden vector, run in the directory of the generated modules.
+const CHECK: &str = r#"import dataclasses
+import enum
+import importlib
+import json
+import os
+
+import kitchen
+import rrpc
+
+
+def resolve(key):
+    """Class of the type `key`, which is in the root module unless it's in a file of its own."""
+    path = key.split("::")
+    module = kitchen
+    if len(path) > 1 and os.path.exists(path[0] + ".py"):
+        module = importlib.import_module(path[0])
+        path = path[1:]
+
+    for name in path:
+        module = getattr(module, name)
+    return module
+
+
+with open("vectors.json") as f:
+    vectors = json.load(f)["vectors"]
+
+seen = set()
+for vector in vectors:
+    cls = resolve(vector["type"])
+    data = bytes.fromhex(vector["hex"])
+
+    value = rrpc.decode(cls, data)
+    assert rrpc.encode(value, cls.__rrpc__) == data, vector
+
+    # The first vector of a type is its default.
+    if vector["type"] not in seen:
+        seen.add(vector["type"])
+        if dataclasses.is_dataclass(cls):
+            assert rrpc.encode(cls()) == data, vector
+        elif issubclass(cls, enum.Enum):
+            assert rrpc.encode(list(cls)[0]) == data, vector
+
+scalars = rrpc.decode(kitchen.Scalars, rrpc.encode(kitchen.Scalars()))
+assert scalars.l == 2**64 - 1 and scalars.m == -(2**63), scalars
+
+try:
+    rrpc.decode(kitchen.Kind, bytes.fromhex("04"))
+    raise AssertionError("decoded 2 as a Kind")
+except rrpc.DecodeError:
+    pass
+"#;
+
+#[test]
+fn generated_codecs_match_the_vectors() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/generated-python");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let input = dir.join("kitchen.rpc");
+    fs::write(&input, KITCHEN).unwrap();
+
+    let opts = rrpc::Options {
+        include: vec![concat!(env!("CARGO_MANIFEST_DIR"), "/examples").into()],
+        ..Default::default()
+    };
+
+    let mut files = rrpc::render_files("python", &input.to_string_lossy(), "", &opts)
+        .unwrap_or_else(|e| panic!("{}", e));
+    files.extend(
+        rrpc::render_files("vectors", &input.to_string_lossy(), "", &opts)
+            .unwrap_or_else(|e| panic!("{}", e)),
+    );
+
+    for file in &files {
+        let path = dir.join(&file.path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, &file.contents).unwrap();
+    }
+    fs::write(dir.join("check.py"), CHECK).unwrap();
+
+    let python = env::var("PYTHON").unwrap_or_else(|_| "python3".into());
+    let status = Command::new(python)
+        .arg("check.py")
+        .current_dir(&dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "generated codecs failed, see {}", dir.display());
+}
@@ -0,0 +1,119 @@
+extern crate rrpc;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Writes the definitions and templates of a test into a directory of its own, and
+/// returns it.
+fn setup(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/render").join(name);
+    let _ = fs::remove_dir_all(&dir);
+
+    let files = [
+        ("main.rpc", "use geo::shapes;\n\nstruct Main {}\n"),
+        ("geo/shapes.rpc", "struct Circle {}\n"),
+        (
+            "templates/module.txt",
+            "{{ namespace }}:{% for n in ast.nodes %} {{ n.name }}{% endfor %}\n",
+        ),
+        ("templates/index.txt", "{% for ns in order %}{{ ns }}\n{% endfor %}"),
+    ];
+    for &(file, contents) in &files {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    dir
+}
+
+fn options(outputs: &[(&str, &str, bool)]) -> rrpc::Options {
+    rrpc::Options {
+        outputs: outputs
+            .iter()
+            .map(|&(template, path, per_module)| rrpc::Output::new(template, path, per_module))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+fn compile(dir: &Path, opts: &rrpc::Options) -> rrpc::Result<rrpc::Manifest> {
+    let main = dir.join("main.rpc");
+    let templates = format!("{}/templates/**/*", dir.display());
+
+    rrpc::compile_with("null", &main.to_string_lossy(), &templates, opts)
+}
+
+#[test]
+fn modules_are_written_with_a_manifest() {
+    let dir = setup("manifest");
+    let out = dir.join("out");
+    let opts = rrpc::Options {
+        out_dir: Some(out.to_string_lossy().into()),
+        ..options(&[
+            ("module.txt", "{{ namespace_path }}.txt", true),
+            ("index.txt", "index.txt", false),
+        ])
+    };
+
+    let manifest = compile(&dir, &opts).unwrap_or_else(|e| panic!("{}", e));
+    assert_eq!(manifest.out_dir, opts.out_dir);
+
+    let files = manifest
+        .files
+        .iter()
+        .map(|f| (f.path.as_str(), f.template.as_str(), f.namespace.as_str()))
+        .collect::<Vec<_>>();
+    let geo = out.join("geo/shapes.txt");
+    let main = out.join("main.txt");
+    let index = out.join("index.txt");
+
+    // Modules are rendered dependencies first.
+    assert_eq!(
+        files,
+        [
+            (&*geo.to_string_lossy(), "module.txt", "geo::shapes"),
+            (&*main.to_string_lossy(), "module.txt", "main"),
+            (&*index.to_string_lossy(), "index.txt", "main"),
+        ]
+    );
+
+    assert_eq!(fs::read_to_string(&geo).unwrap(), "geo::shapes: Circle\n");
+    assert_eq!(fs::read_to_string(&main).unwrap(), "main: Main\n");
+    assert_eq!(fs::read_to_string(&index).unwrap(), "geo::shapes\nmain\n");
+
+    for file in &manifest.files {
+        assert_eq!(fs::metadata(&file.path).unwrap().len() as usize, file.size);
+    }
+}
+
+#[test]
+fn nothing_is_written_without_an_output_directory() {
+    let dir = setup("no_out_dir");
+    let opts = options(&[("index.txt", "rendered-without-out-dir.txt", false)]);
+
+    let manifest = compile(&dir, &opts).unwrap_or_else(|e| panic!("{}", e));
+
+    assert_eq!(manifest.out_dir, None);
+    assert_eq!(manifest.files[0].path, "rendered-without-out-dir.txt");
+    assert!(!Path::new("rendered-without-out-dir.txt").exists());
+    assert!(!dir.join("rendered-without-out-dir.txt").exists());
+}
+
+#[test]
+fn paths_outside_the_output_directory_are_rejected() {
+    let dir = setup("outside");
+
+    for pattern in &["../{{ namespace }}.txt", "/tmp/{{ namespace }}.txt", "", "a/../../b"] {
+        let opts = rrpc::Options {
+            out_dir: Some(dir.join("out").to_string_lossy().into()),
+            ..options(&[("index.txt", pattern, false)])
+        };
+
+        let e = compile(&dir, &opts).map(|_| panic!("{} rendered", pattern)).unwrap_err();
+        assert!(e.to_string().contains("isn't a relative path"), "{}", e);
+    }
+
+    assert!(!dir.join("out").exists());
+    assert!(!dir.join("main.txt").exists());
+}
@@ -0,0 +1,433 @@
+extern crate rrpc;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Definitions covering what the generator maps, imports `examples/init.rpc`.
+const KITCHEN: &str = r#"use init;
+
+/// A tree.
+struct Node {
+    /// Name of the node.
+    name: string {"root \"quoted\""},
+    children: vector<Node>,
+    parent: optional<Node>,
+    type: u8 {3},
+    self: bool {true},
+    tags: map<u32, string> {{"1": "one", "20": "twenty"}},
+    modes: map<Kind, i8> {{"A": -1}},
+    grid: array<f32, 3> {[1.5, 2, 3]},
+    big: array<u8, 40>,
+    kind: Kind {"B"},
+    shape: Shape {{"Circle": 2.5}},
+    point: geo::Point {{"x": 1}},
+    pair: geo::Point {[3, 4]},
+    maybe: optional<Kind> {"A"},
+    other: init::name,
+    seen: set<string> {["a", "b"]},
+}
+
+enum Kind(i16) {
+    A{-1},
+    B,
+}
+
+/// Refers to a type which contains it, through an optional only.
+struct Owner {
+    pet: optional<Pet>,
+}
+
+struct Pet {
+    owner: Owner,
+}
+
+union Shape {
+    Circle: f64,
+    Square: geo::Point,
+    Unit,
+}
+
+mod geo {
+    struct Point {
+        reserved 2, z;
+        x: i64,
+        3: y: i64 {-2},
+    }
+
+    mod deep {
+        struct Segment {
+            a: Point,
+            b: geo::Point,
+            c: Kind,
+        }
+    }
+}
+
+interface store(p) {
+    fn get(key: u32) -> optional<Node>;
+    fn clear();
+    fn pair(a: Kind, b: set<Kind>, self: string) -> (string, geo::Point);
+}
+"#;
+
+/// Tests of the generated code, run in the crate it's generated into.
+const TESTS: &str = r##"
+mod kitchen;
+
+use kitchen::*;
+
+/// Records the calls, and answers `null` to every one of them.
+struct Recorder(Vec<(String, String, String)>);
+
+impl Transport for Recorder {
+    type Error = serde_json::Error;
+
+    fn call<A, R>(
+        &mut self,
+        interface: &str,
+        method: &str,
+        _names: &[&str],
+        args: A,
+    ) -> Result<R, Self::Error>
+    where
+        A: serde::Serialize + rrpc_runtime::Encode,
+        R: serde::de::DeserializeOwned + rrpc_runtime::Decode,
+    {
+        let args = serde_json::to_string(&args)?;
+        self.0.push((interface.into(), method.into(), args));
+        serde_json::from_str("null")
+    }
+}
+
+#[test]
+fn defaults() {
+    let node = Node::default();
+
+    assert_eq!(node.name, "root \"quoted\"");
+    assert_eq!(node.r#type, 3);
+    assert_eq!(node.self_, true);
+    assert_eq!(node.tags[&20], "twenty");
+    assert_eq!(node.modes[&Kind::A], -1);
+    assert_eq!(node.grid, [1.5, 2.0, 3.0]);
+    assert_eq!(*node.big, [0; 40]);
+    assert_eq!(node.point, geo::Point { x: 1, y: -2 });
+    assert_eq!(node.pair, geo::Point { x: 3, y: 4 });
+    assert_eq!(node.shape, Shape::Circle(2.5));
+    assert_eq!(node.maybe, Some(Kind::A));
+    assert_eq!(node.other.y, vec![1, 2, 3]);
+    assert_eq!(Kind::A as i16, -1);
+    assert_eq!(Kind::B as i16, 0);
+    assert_eq!(init::Mode::Multi as u32, 1);
+
+    let pet = Pet { owner: Owner { pet: None } };
+    assert_eq!(Owner { pet: Some(Box::new(pet)) }.pet.unwrap().owner, Owner::default());
+}
+
+#[test]
+fn serde() {
+    let value = serde_json::to_value(&Node::default()).unwrap();
+
+    assert_eq!(value["self"], true);
+    assert_eq!(value["type"], 3);
+    assert_eq!(value["kind"], "B");
+    assert_eq!(value["shape"], serde_json::json!({ "Circle": 2.5 }));
+
+    let node: Node = serde_json::from_str(r#"{ "name": "x", "shape": "Unit" }"#).unwrap();
+
+    assert_eq!(node.name, "x");
+    assert_eq!(node.shape, Shape::Unit);
+    assert_eq!(node.r#type, 3);
+}
+
+#[test]
+fn long_arrays_keep_their_length() {
+    use rrpc_runtime::Codec;
+
+    let node: Node = serde_json::from_value(serde_json::json!({ "big": vec![7; 40] })).unwrap();
+    assert_eq!(node.big[39], 7);
+    assert!(serde_json::from_value::<Node>(serde_json::json!({ "big": vec![7; 39] })).is_err());
+    assert!(serde_json::from_value::<Node>(serde_json::json!({ "big": vec![7; 41] })).is_err());
+
+    let bytes = rrpc_runtime::BinaryCodec.encode(&node).unwrap();
+    assert_eq!(rrpc_runtime::BinaryCodec.decode::<Node>(bytes).unwrap(), node);
+
+    // A `Node` of a single field, `big` with its tag 9, holding an item less.
+    let short = rrpc_runtime::BinaryCodec.encode(&vec![0u8; 39]).unwrap();
+    let bytes = [&[1, 9, short.len() as u8][..], &short].concat();
+    assert!(rrpc_runtime::BinaryCodec.decode::<Node>(bytes).is_err());
+}
+
+#[test]
+fn client() {
+    let mut client = storeClient::new(Recorder(Vec::new()));
+
+    assert_eq!(client.get(7).unwrap(), None);
+    client.clear().unwrap();
+
+    let calls = client.into_inner().0;
+
+    assert_eq!(calls[0], ("store".into(), "get".into(), "[7]".into()));
+    assert_eq!(calls[1], ("store".into(), "clear".into(), "null".into()));
+}
+
+/// Keeps the nodes it's asked for.
+#[derive(Default)]
+struct Store(Vec<u32>);
+
+impl store for Store {
+    type Error = String;
+
+    fn get(&mut self, key: u32) -> Result<Option<Node>, String> {
+        if key == 0 {
+            return Err("no node 0".into());
+        }
+
+        self.0.push(key);
+        Ok(Some(Node { name: key.to_string(), ..Default::default() }))
+    }
+
+    fn clear(&mut self) -> Result<(), String> {
+        self.0.clear();
+        Ok(())
+    }
+
+    fn pair(
+        &mut self,
+        a: Kind,
+        b: std::collections::HashSet<Kind>,
+        self_: String,
+    ) -> Result<(String, geo::Point), String> {
+        Ok((self_, geo::Point { x: a as i64, y: b.len() as i64 }))
+    }
+}
+
+fn router<C: rrpc_runtime::Codec>(codec: C) -> rrpc_runtime::Router<C> {
+    rrpc_runtime::Router::new(codec).with(storeServer::new(Store::default()))
+}
+
+/// Calls every function of a served `Store`.
+fn calls<T: Transport<Error = rrpc_runtime::Error>>(transport: T) {
+    let mut client = storeClient::new(transport);
+
+    assert_eq!(client.get(7).unwrap().unwrap().name, "7");
+    client.clear().unwrap();
+
+    let kinds = vec![Kind::A, Kind::B].into_iter().collect();
+    assert_eq!(
+        client.pair(Kind::A, kinds, "x".into()).unwrap(),
+        ("x".to_string(), geo::Point { x: -1, y: 2 })
+    );
+
+    let e = client.get(0).unwrap_err();
+    assert_eq!(e.kind(), rrpc_runtime::ErrorKind::Service);
+    assert_eq!(e.message(), "no node 0");
+
+    let mut transport = client.into_inner();
+
+    let e = transport.call::<_, ()>("store", "put", &[], ()).unwrap_err();
+    assert_eq!(e.kind(), rrpc_runtime::ErrorKind::NotFound);
+
+    let e = transport.call::<_, ()>("shop", "get", &["key"], (1,)).unwrap_err();
+    assert_eq!(e.kind(), rrpc_runtime::ErrorKind::NotFound);
+
+    let e = transport.call::<_, ()>("store", "get", &["key"], ("7",)).unwrap_err();
+    assert_eq!(e.kind(), rrpc_runtime::ErrorKind::InvalidArgs);
+}
+
+#[test]
+fn memory() {
+    let transport = rrpc_runtime::Memory::new(router(rrpc_runtime::JsonCodec));
+    calls(transport);
+}
+
+#[test]
+fn binary() {
+    calls(rrpc_runtime::Memory::new(router(rrpc_runtime::BinaryCodec)));
+}
+
+#[test]
+fn tcp() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || router(rrpc_runtime::JsonCodec).serve_tcp(listener));
+
+    calls(rrpc_runtime::Stream::tcp(rrpc_runtime::JsonCodec, addr).unwrap());
+}
+
+#[cfg(unix)]
+#[test]
+fn unix() {
+    let path = std::env::temp_dir().join(format!("rrpc-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+    std::thread::spawn(move || router(rrpc_runtime::JsonCodec).serve_unix(listener));
+
+    calls(rrpc_runtime::Stream::unix(rrpc_runtime::JsonCodec, &path).unwrap());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn jsonrpc_loopback() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || router(rrpc_runtime::JsonRpcCodec::default()).serve_tcp(listener));
+
+    calls(rrpc_runtime::Stream::tcp(rrpc_runtime::JsonRpcCodec::default(), addr).unwrap());
+}
+
+#[test]
+fn jsonrpc_messages() {
+    use serde_json::{json, Value};
+
+    let mut router = router(rrpc_runtime::JsonRpcCodec::default());
+    let mut answer = |request: &str| -> Option<Value> {
+        let answer = router.handle(request.as_bytes()).unwrap()?;
+        Some(serde_json::from_slice(&answer).unwrap())
+    };
+
+    assert_eq!(
+        answer(r#"{"jsonrpc": "2.0", "method": "store.get", "params": {"key": 3}, "id": 1}"#)
+            .unwrap()["result"]["name"],
+        "3"
+    );
+    assert_eq!(answer(r#"{"jsonrpc": "2.0", "method": "store.clear"}"#), None);
+    assert_eq!(answer(r#"{"jsonrpc": "2.0", "method": "store.nope"}"#), None);
+
+    let batch = answer(
+        r#"[
+            {"jsonrpc": "2.0", "method": "store.pair", "params": ["A", ["B"], "s"], "id": "a"},
+            {"jsonrpc": "2.0", "method": "store.pair", "params": {"a": "B", "b": [], "self": "t"}, "id": "b"},
+            {"jsonrpc": "2.0", "method": "store.clear"},
+            {"jsonrpc": "2.0", "method": "store.clear", "params": [], "id": null},
+            {"jsonrpc": "2.0", "method": "store.nope", "id": 2},
+            {"jsonrpc": "2.0", "method": "store.get", "params": {"key": 0}, "id": 3},
+            {"jsonrpc": "2.0", "method": "store.get", "params": {"id": 1}, "id": 4},
+            {"method": "store.clear", "id": 5},
+            1
+        ]"#,
+    )
+    .unwrap();
+
+    let codes = |i: usize| (batch[i]["id"].clone(), batch[i]["error"]["code"].clone());
+
+    assert_eq!(batch.as_array().unwrap().len(), 8);
+    assert_eq!(batch[0]["result"], json!(["s", {"x": -1, "y": 1}]));
+    assert_eq!(batch[1]["result"], json!(["t", {"x": 0, "y": 0}]));
+    assert_eq!(batch[2], json!({"jsonrpc": "2.0", "result": null, "id": null}));
+    assert_eq!(codes(3), (json!(2), json!(-32601)));
+    assert_eq!(codes(4), (json!(3), json!(-32000)));
+    assert_eq!(batch[4]["error"]["message"], "no node 0");
+    assert_eq!(codes(5), (json!(4), json!(-32602)));
+    assert_eq!(codes(6), (json!(5), json!(-32600)));
+    assert_eq!(codes(7), (json!(null), json!(-32600)));
+
+    assert_eq!(answer("[]").unwrap()["error"]["code"], -32600);
+    assert_eq!(answer("{").unwrap()["error"]["code"], -32700);
+    assert_eq!(answer(r#"[{"jsonrpc": "2.0", "method": "store.clear"}]"#), None);
+}
+
+/// Checks that a golden vector decodes to its value, which encodes to it.
+fn golden<T>(vector: &serde_json::Value)
+where
+    T: serde::Serialize + serde::de::DeserializeOwned + rrpc_runtime::Encode + rrpc_runtime::Decode,
+    T: PartialEq + std::fmt::Debug,
+{
+    use rrpc_runtime::Codec;
+
+    let hex = vector["hex"].as_str().unwrap();
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect::<Vec<_>>();
+    let value: T = serde_json::from_value(vector["value"].clone()).unwrap();
+
+    assert_eq!(rrpc_runtime::BinaryCodec.encode(&value).unwrap(), bytes, "{}", vector);
+    assert_eq!(rrpc_runtime::BinaryCodec.decode::<T>(bytes).unwrap(), value, "{}", vector);
+}
+
+#[test]
+fn vectors() {
+    let vectors: serde_json::Value = serde_json::from_str(include_str!("../vectors.json")).unwrap();
+
+    for vector in vectors["vectors"].as_array().unwrap() {
+        match vector["type"].as_str().unwrap() {
+            "Node" => golden::<Node>(vector),
+            "Kind" => golden::<Kind>(vector),
+            "Shape" => golden::<Shape>(vector),
+            "Owner" => golden::<Owner>(vector),
+            "Pet" => golden::<Pet>(vector),
+            "geo::Point" => golden::<geo::Point>(vector),
+            "geo::deep::Segment" => golden::<geo::deep::Segment>(vector),
+            "child::Child" => golden::<child::Child>(vector),
+            "sample::Coordinate" => golden::<sample::Coordinate>(vector),
+            "init::Mode" => golden::<init::Mode>(vector),
+            "init::Shape" => golden::<init::Shape>(vector),
+            "init::name" => golden::<init::name>(vector),
+            "init::geometry::Point" => golden::<init::geometry::Point>(vector),
+            "init::geometry::shape::Line" => golden::<init::geometry::shape::Line>(vector),
+            ty => panic!("no vector of {}", ty),
+        }
+    }
+}
+"##;
+
+const MANIFEST: &str = r#"[package]
+name = "generated"
+version = "0.0.0"
+edition = "2018"
+
+[dependencies]
+serde = { version = "1", features = ["derive"] }
+serde_json = "1"
+rrpc-runtime = { path = "{runtime}" }
+
+[workspace]
+"#;
+
+fn root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("target/generated-rust")
+}
+
+#[test]
+fn generated_code_compiles_and_works() {
+    let dir = root().join("crate");
+    let src = dir.join("src");
+    let _ = fs::remove_dir_all(&src);
+    fs::create_dir_all(&src).unwrap();
+
+    let input = dir.join("kitchen.rpc");
+    fs::write(&input, KITCHEN).unwrap();
+
+    let opts = rrpc::Options {
+        include: vec![concat!(env!("CARGO_MANIFEST_DIR"), "/examples").into()],
+        ..Default::default()
+    };
+
+    let files = rrpc::render_files("rust", &input.to_string_lossy(), "", &opts)
+        .unwrap_or_else(|e| panic!("{}", e));
+
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path, "kitchen.rs");
+
+    fs::write(src.join("kitchen.rs"), &files[0].contents).unwrap();
+
+    let vectors = rrpc::render_files("vectors", &input.to_string_lossy(), "", &opts)
+        .unwrap_or_else(|e| panic!("{}", e));
+    fs::write(dir.join("vectors.json"), &vectors[0].contents).unwrap();
+    fs::write(src.join("lib.rs"), TESTS).unwrap();
+    let runtime = Path::new(env!("CARGO_MANIFEST_DIR")).join("runtime");
+    let manifest = MANIFEST.replace("{runtime}", &runtime.to_string_lossy());
+    fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let status = Command::new(cargo)
+        .args(["test", "--offline", "--quiet"])
+        .current_dir(&dir)
+        .env("CARGO_TARGET_DIR", root().join("target"))
+        .status()
+        .unwrap();
+
+    assert!(status.success(), "generated code failed, see {}", dir.display());
+}
@@ -0,0 +1,289 @@
+extern crate rrpc;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Definitions covering what the codecs map, imports `examples/init.rpc`.
+const KITCHEN: &str = r#"use init;
+
+/// A tree.
+struct Node {
+    kind: Kind {"B"},
+    children: vector<Node>,
+    parent: optional<Node>,
+    shape: Shape {{"Circle": 2.5}},
+    tags: map<u32, string> {{"1": "one", "20": "twenty"}},
+    ids: map<u64, i64> {{"18446744073709551615": -1}},
+    seen: set<string> {["a", "b"]},
+    other: init::name,
+}
+
+enum Kind(i16) {
+    A{-1},
+    B,
+}
+
+enum Big(u64) {
+    Small,
+    Large{18446744073709551615},
+}
+
+union Shape {
+    Circle: f64,
+    Square: geo::Point,
+    Unit,
+}
+
+struct Scalars {
+    b: bool {true},
+    u: u8 {255},
+    i: i8 {-128},
+    w: u16 {300},
+    n: i32 {-65},
+    l: u64 {18446744073709551615},
+    m: i64 {-9223372036854775808},
+    f: f32 {1.5},
+    d: f64 {-0.25},
+    s: string {"é"},
+    a: array<i8, 2> {[-1, 1]},
+    o: optional<u16> {7},
+    big: Big {"Large"},
+}
+
+mod geo {
+    struct Point {
+        reserved 2, z;
+        x: i64,
+        3: y: i64 {-2},
+    }
+}
+"#;
+
+/// Checks the golden vectors listed in `vectors.ts`, once compiled.
+const CHECK: &str = r#"import * as rrpc from "./rrpc";
+import * as kitchen from "./kitchen";
+import { VECTORS } from "./vectors";
+
+function hex(bytes: Uint8Array): string {
+    return Array.from(bytes, (b) => b.toString(16).padStart(2, "0")).join("");
+}
+
+function bytes(hex: string): Uint8Array {
+    return Uint8Array.from(hex.match(/../g) ?? [], (b) => parseInt(b, 16));
+}
+
+function assert(ok: boolean, message: string): void {
+    if (!ok) {
+        throw new Error(message);
+    }
+}
+
+for (const [type, codec, vector] of VECTORS) {
+    const again = hex(rrpc.encode(codec, rrpc.decode(codec, bytes(vector))));
+    assert(again === vector, `${type}: ${vector} encodes back to ${again}`);
+}
+
+// Decoding no fields gives the defaults, which are the first vector of a struct.
+const first = (type: string) => VECTORS.find((v) => v[0] === type)?.[2];
+assert(hex(rrpc.encode(kitchen.Scalars, rrpc.decode(kitchen.Scalars, bytes("00")))) === first("Scalars"), "Scalars");
+assert(hex(rrpc.encode(kitchen.Node, rrpc.decode(kitchen.Node, bytes("00")))) === first("Node"), "Node");
+
+const scalars = rrpc.decode(kitchen.Scalars, bytes(first("Scalars") ?? ""));
+assert(scalars.l === 18446744073709551615n && scalars.m === -9223372036854775808n, "64 bits");
+assert(scalars.big === "Large", "enum of u64");
+"#;
+
+/// A module with an interface, importing `geo.rpc`.
+const STORE: &str = r#"use geo;
+
+/// An item.
+struct Item {
+    name: string,
+    tags: map<string, u32>,
+    ids: vector<u64>,
+    at: geo::Point,
+}
+
+enum Kind(u8) {
+    A,
+    B,
+}
+
+/// Keeps the items.
+interface store(p) {
+    fn get(id: u32) -> optional<Item>;
+    fn put(items: vector<Item>, kinds: set<Kind>);
+}
+"#;
+
+/// Calls the client through a transport which records the calls, once compiled.
+const CLIENT: &str = r#"import { storeClient } from "./store";
+
+const calls: unknown[] = [];
+const client = new storeClient(async (iface, method, args) => {
+    calls.push([iface, method, args]);
+    return method === "get" ? { name: "a", tags: {}, ids: [1n], at: { x: 1 } } : undefined;
+});
+
+(async () => {
+    const item = await client.get(7);
+    await client.put([], ["A"]);
+
+    const expected = JSON.stringify([["store", "get", [7]], ["store", "put", [[], ["A"]]]]);
+    if (JSON.stringify(calls) !== expected || item?.name !== "a") {
+        throw new Error(JSON.stringify(calls));
+    }
+})().catch((e) => {
+    console.error(e);
+    process.exit(1);
+});
+"#;
+
+/// Compiles `check.ts` in `dir` with tsc, or the compiler in `TSC`, and runs it.
+fn run(dir: &Path) {
+    let tsc = env::var("TSC").unwrap_or_else(|_| "tsc".into());
+    let status = Command::new(&tsc)
+        .args(["--strict", "--target", "es2020", "--module", "commonjs", "--outDir", "out"])
+        .arg("check.ts")
+        .current_dir(dir)
+        .status()
+        .unwrap_or_else(|e| panic!("couldn't run {}: {}", tsc, e));
+    assert!(status.success(), "generated code failed to compile, see {}", dir.display());
+
+    let status = Command::new("node")
+        .arg("out/check.js")
+        .current_dir(dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "generated code failed, see {}", dir.display());
+}
+
+/// Generates the client of `STORE` into `target/<name>`, checks it and returns the directory.
+fn client(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target").join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let input = dir.join("store.rpc");
+    fs::write(&input, STORE).unwrap();
+    fs::write(dir.join("geo.rpc"), "struct Point {\n    x: i32,\n}\n").unwrap();
+
+    let files = rrpc::render_files("typescript", &input.to_string_lossy(), "", &Default::default())
+        .unwrap_or_else(|e| panic!("{}", e));
+
+    let mut paths = files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>();
+    paths.sort();
+    assert_eq!(paths, ["geo.ts", "rrpc.ts", "store.ts"]);
+
+    for file in &files {
+        fs::write(dir.join(&file.path), &file.contents).unwrap();
+    }
+
+    let store = fs::read_to_string(dir.join("store.ts")).unwrap();
+    assert!(store.contains("import * as geo from \"./geo\";\n"));
+    assert!(store.contains("/** An item. */\nexport interface Item {\n"));
+    assert!(store.contains("    tags: Record<string, number>;\n"));
+    assert!(store.contains("    ids: bigint[];\n"));
+    assert!(store.contains("    at: geo.Point;\n"));
+    assert!(store.contains("export type Kind = \"A\" | \"B\";\n"));
+    assert!(store.contains("    get(id: number): Promise<Item | null>;\n"));
+    assert!(store.contains("export class storeClient implements store {\n"));
+    assert!(store.contains("    constructor(transport: Transport) {\n"));
+
+    fs::write(dir.join("check.ts"), CLIENT).unwrap();
+    dir
+}
+
+#[test]
+fn interfaces_are_clients_over_a_transport() {
+    client("generated-typescript-client");
+}
+
+#[test]
+#[ignore = "needs tsc"]
+fn generated_clients_call_the_transport() {
+    run(&client("generated-typescript-client-run"));
+}
+
+/// Generates the codecs of `KITCHEN` and their vectors into `target/<name>`, checks them and
+/// returns the directory.
+fn codecs(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target").join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let input = dir.join("kitchen.rpc");
+    fs::write(&input, KITCHEN).unwrap();
+
+    let opts = rrpc::Options {
+        include: vec![concat!(env!("CARGO_MANIFEST_DIR"), "/examples").into()],
+        ..Default::default()
+    };
+
+    let files = rrpc::render_files("typescript", &input.to_string_lossy(), "", &opts)
+        .unwrap_or_else(|e| panic!("{}", e));
+
+    for file in &files {
+        let path = dir.join(&file.path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, &file.contents).unwrap();
+    }
+
+    // 64-bit integers are bigints, which keep all of their bits.
+    let kitchen = fs::read_to_string(dir.join("kitchen.ts")).unwrap();
+    assert!(kitchen.contains("l: bigint;"));
+    assert!(kitchen.contains("ids: Record<string, bigint>;"));
+    assert!(kitchen.contains(r#""l":18446744073709551615n"#));
+    assert!(kitchen.contains(r#""ids":{"18446744073709551615":-1n}"#));
+    assert!(kitchen.contains(r#"["Large", 18446744073709551615n]"#));
+
+    let vectors = rrpc::render_files("vectors", &input.to_string_lossy(), "", &opts)
+        .unwrap_or_else(|e| panic!("{}", e));
+    let vectors: serde_json::Value = serde_json::from_str(&vectors[0].contents).unwrap();
+
+    // Types of the root module are keyed without its namespace, and the others are
+    // imported under the name of their module.
+    let mut imports = vec!["kitchen".to_string()];
+    let mut list = String::new();
+    for vector in vectors["vectors"].as_array().unwrap() {
+        let key = vector["type"].as_str().unwrap();
+        let path = key.split("::").collect::<Vec<_>>();
+        let codec = if path.len() > 1 && dir.join(format!("{}.ts", path[0])).exists() {
+            if !imports.iter().any(|i| i == path[0]) {
+                imports.push(path[0].into());
+            }
+            path.join(".")
+        } else {
+            format!("kitchen.{}", path.join("."))
+        };
+
+        list.push_str(&format!("    [{:?}, {}, {}],\n", key, codec, vector["hex"]));
+    }
+
+    let imports = imports
+        .iter()
+        .map(|i| format!("import * as {} from \"./{}\";\n", i, i))
+        .collect::<String>();
+    let module = format!(
+        "import * as rrpc from \"./rrpc\";\n{}\n\
+         // eslint-disable-next-line @typescript-eslint/no-explicit-any\n\
+         export const VECTORS: [string, rrpc.Codec<any>, string][] = [\n{}];\n",
+        imports, list
+    );
+    fs::write(dir.join("vectors.ts"), module).unwrap();
+    fs::write(dir.join("check.ts"), CHECK).unwrap();
+    dir
+}
+
+#[test]
+fn generated_codecs_keep_64_bit_integers() {
+    codecs("generated-typescript");
+}
+
+#[test]
+#[ignore = "needs tsc"]
+fn generated_codecs_match_the_vectors() {
+    run(&codecs("generated-typescript-run"));
+}